@@ -0,0 +1,110 @@
+//! # Render
+//!
+//! Measures the rendering of a full screen of rows (80x24), counting the heap allocations made per render,
+//! without and with guide lines.
+//!
+//! Before indentation was borrowed from a static buffer of spaces, rendering without guide lines made 50 allocations
+//! per render. Guide lines are still built for each row.
+//!
+//! Run with `cargo bench --features fixtures --bench render`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tui_realm_treeview::{fixtures, Tree, TreeState, TreeWidget};
+use tuirealm::ratatui::buffer::Buffer;
+use tuirealm::ratatui::layout::Rect;
+use tuirealm::ratatui::widgets::StatefulWidget;
+
+const ITERATIONS: u32 = 1000;
+
+/// Allocator counting the allocations made through the system allocator
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Build a tree 4 levels deep, with 8 children per node
+fn make_tree() -> Tree<String> {
+    fixtures::generate(4, 8, |route| format!("node {route:?}"))
+}
+
+/// Open the first branch of each level and select the deepest node, so that rows have different indents
+fn make_state(tree: &Tree<String>) -> TreeState {
+    let mut state = TreeState::default();
+    let mut node = tree.root();
+    loop {
+        state.select(tree.root(), node);
+        state.open(tree.root());
+        match node.iter().next() {
+            Some(child) => node = child,
+            None => break,
+        }
+    }
+    state
+}
+
+/// Render `tree` `ITERATIONS` times with the widget made by `widget`, printing the time and the allocations per render
+fn bench<'a, F>(name: &str, tree: &'a Tree<String>, state: &mut TreeState, widget: F)
+where
+    F: Fn(&'a Tree<String>) -> TreeWidget<'a, String>,
+{
+    let area = Rect::new(0, 0, 80, 24);
+    let mut buffer = Buffer::empty(area);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let t_start = Instant::now();
+    for _ in 0..ITERATIONS {
+        StatefulWidget::render(widget(tree), area, &mut buffer, state);
+        black_box(&buffer);
+    }
+    let elapsed: Duration = t_start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!("{name}");
+    println!("  render {:>12?} / iter", elapsed / ITERATIONS);
+    println!(
+        "  allocations {:>7} / iter",
+        allocations / ITERATIONS as usize
+    );
+}
+
+fn main() {
+    let tree = make_tree();
+    let mut state = make_state(&tree);
+    let area = Rect::new(0, 0, 80, 24);
+    let mut buffer = Buffer::empty(area);
+    // Render once to build the visible rows index
+    StatefulWidget::render(TreeWidget::new(&tree), area, &mut buffer, &mut state);
+
+    println!("rows per render: {}", area.height);
+    bench("without guides", &tree, &mut state, |tree| {
+        TreeWidget::new(tree)
+            .indent_size(2)
+            .highlight_symbol(String::from(">"))
+    });
+    bench("with guides", &tree, &mut state, |tree| {
+        TreeWidget::new(tree)
+            .indent_size(2)
+            .highlight_symbol(String::from(">"))
+            .guides(true)
+    });
+}
@@ -0,0 +1,71 @@
+//! # Selected index
+//!
+//! Compares the lookup of the selected row using the visible rows index against walking the tree,
+//! on a tree with 100k visible rows.
+//!
+//! Run with `cargo bench --features fixtures --bench selected_index`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tui_realm_treeview::{fixtures, Tree, TreeState, TreeWidget};
+use tuirealm::ratatui::buffer::Buffer;
+use tuirealm::ratatui::layout::Rect;
+use tuirealm::ratatui::widgets::StatefulWidget;
+
+const BRANCHES: usize = 316;
+const ITERATIONS: u32 = 100;
+
+/// Build a tree with `BRANCHES` branches having `BRANCHES` leaves each
+fn make_tree() -> Tree<String> {
+    fixtures::generate(2, BRANCHES, |route| format!("{route:?}"))
+}
+
+/// Open all branches and select the last leaf
+fn make_state(tree: &Tree<String>) -> TreeState {
+    let mut state = TreeState::default();
+    state.select(tree.root(), tree.root());
+    state.open(tree.root());
+    for branch in tree.root().iter() {
+        state.select(tree.root(), branch);
+        state.open(tree.root());
+    }
+    let last = tree.root().iter().last().unwrap().iter().last().unwrap();
+    state.select(tree.root(), last);
+    state
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    let t_start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed: Duration = t_start.elapsed();
+    println!("{name:<24} {:>12?} / iter", elapsed / ITERATIONS);
+}
+
+fn main() {
+    let tree = make_tree();
+    let mut state = make_state(&tree);
+    let area = Rect::new(0, 0, 80, 24);
+    let mut buffer = Buffer::empty(area);
+    // Render once to build the visible rows index
+    StatefulWidget::render(TreeWidget::new(&tree), area, &mut buffer, &mut state);
+    let rows = state.selected_index(tree.root()).unwrap() + 1;
+    println!("visible rows: {rows}");
+
+    bench("selected_index (index)", || {
+        black_box(state.selected_index(tree.root()));
+    });
+    bench("node_at_row (index)", || {
+        black_box(state.node_at_row(tree.root(), rows - 1));
+    });
+
+    state.invalidate_rows();
+    bench("selected_index (walk)", || {
+        black_box(state.selected_index(tree.root()));
+    });
+    bench("node_at_row (walk)", || {
+        black_box(state.node_at_row(tree.root(), rows - 1));
+    });
+}
@@ -1,3 +1,4 @@
+use std::iter;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tui_realm_stdlib::{Input, Phantom};
@@ -14,15 +15,31 @@ use tuirealm::{
 // tui
 use tuirealm::ratatui::layout::{Constraint, Direction as LayoutDirection, Layout};
 // treeview
-use tui_realm_treeview::{Node, Tree, TreeView, TREE_CMD_CLOSE, TREE_CMD_OPEN};
+use tui_realm_treeview::{Node, NodeValue, Tree, TreeCmd, TreeView};
 
-const MAX_DEPTH: usize = 3;
+// -- node value
+
+/// A directory entry; directories are lazy branches, whose entries are loaded when they're opened
+#[derive(Debug, Default, Clone)]
+pub struct FsEntry {
+    name: String,
+    dir: bool,
+}
+
+impl NodeValue for FsEntry {
+    fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+        iter::once((self.name.as_str(), None))
+    }
+
+    fn is_branch_hint(&self) -> bool {
+        self.dir
+    }
+}
 
 // -- message
 #[derive(Debug, PartialEq)]
 pub enum Msg {
     AppClose,
-    ExtendDir(String),
     FsTreeBlur,
     GoToBlur,
     GoTo(PathBuf),
@@ -41,9 +58,9 @@ pub enum Id {
 struct Model {
     app: Application<Id, Msg, NoUserEvent>,
     path: PathBuf,
-    tree: Tree<String>, // You can choose a Tree<Vec<TextSpan>> for more flexible rendering
-    quit: bool,         // Becomes true when the user presses <ESC>
-    redraw: bool,       // Tells whether to refresh the UI; performance optimization
+    tree: Tree<FsEntry>,
+    quit: bool,   // Becomes true when the user presses <ESC>
+    redraw: bool, // Tells whether to refresh the UI; performance optimization
     terminal: TerminalBridge<CrosstermTerminalAdapter>,
 }
 
@@ -56,7 +73,7 @@ impl Model {
         assert!(app
             .mount(
                 Id::FsTree,
-                Box::new(FsTree::new(Tree::new(Self::dir_tree(p, MAX_DEPTH)), None)),
+                Box::new(FsTree::new(Tree::new(Self::dir_tree(p)), None)),
                 vec![]
             )
             .is_ok());
@@ -83,7 +100,7 @@ impl Model {
             app,
             quit: false,
             redraw: true,
-            tree: Tree::new(Self::dir_tree(p, MAX_DEPTH)),
+            tree: Tree::new(Self::dir_tree(p)),
             path: p.to_path_buf(),
             terminal: TerminalBridge::init_crossterm().expect("Could not initialize terminal"),
         }
@@ -91,41 +108,44 @@ impl Model {
 
     pub fn scan_dir(&mut self, p: &Path) {
         self.path = p.to_path_buf();
-        self.tree = Tree::new(Self::dir_tree(p, MAX_DEPTH));
+        self.tree = Tree::new(Self::dir_tree(p));
     }
 
     pub fn upper_dir(&self) -> Option<PathBuf> {
         self.path.parent().map(|x| x.to_path_buf())
     }
 
-    pub fn extend_dir(&mut self, id: &String, p: &Path, depth: usize) {
-        if let Some(node) = self.tree.root_mut().query_mut(id) {
-            if depth > 0 && p.is_dir() {
-                // Clear node
-                node.clear();
-                // Scan dir
-                if let Ok(e) = std::fs::read_dir(p) {
-                    e.flatten().for_each(|x| {
-                        node.add_child(Self::dir_tree(x.path().as_path(), depth - 1))
-                    });
-                }
-            }
-        }
+    /// Make the node of the directory at `p` with its entries; sub directories are loaded when they're opened
+    fn dir_tree(p: &Path) -> Node<FsEntry> {
+        let mut node = Self::entry(p);
+        Self::dir_entries(p)
+            .into_iter()
+            .for_each(|x| node.add_child(x));
+        node
+    }
+
+    fn dir_entries(p: &Path) -> Vec<Node<FsEntry>> {
+        std::fs::read_dir(p)
+            .map(|e| {
+                e.flatten()
+                    .map(|x| Self::entry(x.path().as_path()))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    fn dir_tree(p: &Path, depth: usize) -> Node<String> {
+    fn entry(p: &Path) -> Node<FsEntry> {
         let name: String = match p.file_name() {
             None => "/".to_string(),
             Some(n) => n.to_string_lossy().into_owned().to_string(),
         };
-        let mut node: Node<String> = Node::new(p.to_string_lossy().into_owned(), name);
-        if depth > 0 && p.is_dir() {
-            if let Ok(e) = std::fs::read_dir(p) {
-                e.flatten()
-                    .for_each(|x| node.add_child(Self::dir_tree(x.path().as_path(), depth - 1)));
-            }
-        }
-        node
+        Node::new(
+            p.to_string_lossy().into_owned(),
+            FsEntry {
+                name,
+                dir: p.is_dir(),
+            },
+        )
     }
 
     fn view(&mut self) {
@@ -196,11 +216,6 @@ impl Update<Msg> for Model {
                 self.quit = true;
                 None
             }
-            Msg::ExtendDir(path) => {
-                self.extend_dir(&path, PathBuf::from(path.as_str()).as_path(), MAX_DEPTH);
-                self.reload_tree();
-                None
-            }
             Msg::GoTo(path) => {
                 // Go to and reload tree
                 self.scan_dir(path.as_path());
@@ -231,11 +246,11 @@ impl Update<Msg> for Model {
 
 #[derive(MockComponent)]
 pub struct FsTree {
-    component: TreeView<String>,
+    component: TreeView<FsEntry>,
 }
 
 impl FsTree {
-    pub fn new(tree: Tree<String>, initial_node: Option<String>) -> Self {
+    pub fn new(tree: Tree<FsEntry>, initial_node: Option<String>) -> Self {
         // Preserve initial node if exists
         let initial_node = match initial_node {
             Some(id) if tree.root().query(&id).is_some() => id,
@@ -255,6 +270,8 @@ impl FsTree {
                 .title(tree.root().id(), Alignment::Left)
                 .highlighted_color(Color::LightYellow)
                 .highlight_symbol("🦄")
+                // Entries of directories are loaded when they're opened
+                .child_loader(|node: &Node<FsEntry>| Some(Model::dir_entries(Path::new(node.id()))))
                 .with_tree(tree)
                 .initial_node(initial_node),
         }
@@ -263,15 +280,15 @@ impl FsTree {
 
 impl Component<Msg, NoUserEvent> for FsTree {
     fn on(&mut self, ev: Event<NoUserEvent>) -> Option<Msg> {
-        let result = match ev {
+        match ev {
             Event::Keyboard(KeyEvent {
                 code: Key::Left,
                 modifiers: KeyModifiers::NONE,
-            }) => self.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            }) => self.perform(TreeCmd::Close.into()),
             Event::Keyboard(KeyEvent {
                 code: Key::Right,
                 modifiers: KeyModifiers::NONE,
-            }) => self.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            }) => self.perform(TreeCmd::Open.into()),
             Event::Keyboard(KeyEvent {
                 code: Key::PageDown,
                 modifiers: KeyModifiers::NONE,
@@ -310,10 +327,7 @@ impl Component<Msg, NoUserEvent> for FsTree {
             }) => return Some(Msg::FsTreeBlur),
             _ => return None,
         };
-        match result {
-            CmdResult::Submit(State::One(StateValue::String(node))) => Some(Msg::ExtendDir(node)),
-            _ => Some(Msg::None),
-        }
+        Some(Msg::None)
     }
 }
 
@@ -0,0 +1,355 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tui_realm_stdlib::{Paragraph, Phantom};
+use tuirealm::terminal::CrosstermTerminalAdapter;
+use tuirealm::{
+    application::PollStrategy,
+    command::{Cmd, CmdResult, Direction, Position},
+    event::{Event, Key, KeyEvent, KeyModifiers},
+    props::{
+        Alignment, AttrValue, Attribute, BorderType, Borders, Color, PropPayload, PropValue, Style,
+        TextSpan,
+    },
+    terminal::TerminalBridge,
+    Application, Component, EventListenerCfg, MockComponent, NoUserEvent, Sub, SubClause,
+    SubEventClause, Update,
+};
+// tui
+use tuirealm::ratatui::layout::{Constraint, Direction as LayoutDirection, Layout};
+// treeview
+use tui_realm_treeview::{ids, Node, Tree, TreeCmd, TreeView};
+
+const MAX_DEPTH: usize = 3;
+/// Lines of the file to display in the preview
+const PREVIEW_LINES: usize = 256;
+/// Time the selection must stay on the same node before loading its preview
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(150);
+
+// -- message
+#[derive(Debug, PartialEq)]
+pub enum Msg {
+    AppClose,
+    Selected(PathBuf),
+    None,
+}
+
+// Let's define the component ids for our application
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Id {
+    FsTree,
+    GlobalListener,
+    Preview,
+}
+
+struct Model {
+    app: Application<Id, Msg, NoUserEvent>,
+    /// Preview to load, with the time the node was selected
+    pending_preview: Option<(PathBuf, Instant)>,
+    quit: bool,   // Becomes true when the user presses <ESC>
+    redraw: bool, // Tells whether to refresh the UI; performance optimization
+    terminal: TerminalBridge<CrosstermTerminalAdapter>,
+}
+
+impl Model {
+    fn new(p: &Path) -> Self {
+        // Setup app
+        let mut app: Application<Id, Msg, NoUserEvent> = Application::init(
+            EventListenerCfg::default().crossterm_input_listener(Duration::from_millis(10), 10),
+        );
+        assert!(app
+            .mount(
+                Id::FsTree,
+                Box::new(FsTree::new(Tree::new(Self::dir_tree(p, MAX_DEPTH)))),
+                vec![]
+            )
+            .is_ok());
+        assert!(app
+            .mount(Id::Preview, Box::new(Preview::default()), vec![])
+            .is_ok());
+        // Mount global listener which will listen for <ESC>
+        assert!(app
+            .mount(
+                Id::GlobalListener,
+                Box::new(GlobalListener::default()),
+                vec![Sub::new(
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Esc,
+                        modifiers: KeyModifiers::NONE,
+                    }),
+                    SubClause::Always
+                )]
+            )
+            .is_ok());
+        assert!(app.active(&Id::FsTree).is_ok());
+        Model {
+            app,
+            pending_preview: Some((p.to_path_buf(), Instant::now())),
+            quit: false,
+            redraw: true,
+            terminal: TerminalBridge::init_crossterm().expect("Could not initialize terminal"),
+        }
+    }
+
+    fn dir_tree(p: &Path, depth: usize) -> Node<String> {
+        let name: String = match p.file_name() {
+            None => "/".to_string(),
+            Some(n) => n.to_string_lossy().into_owned().to_string(),
+        };
+        // Ids are encoded paths, so that they can be turned back into paths, whatever the file names are
+        let mut node: Node<String> = Node::new(ids::encode_path(p), name);
+        if depth > 0 && p.is_dir() {
+            if let Ok(e) = std::fs::read_dir(p) {
+                e.flatten()
+                    .for_each(|x| node.add_child(Self::dir_tree(x.path().as_path(), depth - 1)));
+            }
+        }
+        node
+    }
+
+    /// Load the pending preview, once the selection has been stable for `PREVIEW_DEBOUNCE`.
+    /// This way files are not read while the user scrolls quickly through the tree
+    fn load_pending_preview(&mut self) {
+        let due = matches!(self.pending_preview, Some((_, t)) if t.elapsed() >= PREVIEW_DEBOUNCE);
+        if !due {
+            return;
+        }
+        if let Some((path, _)) = self.pending_preview.take() {
+            let lines = Self::preview(path.as_path());
+            assert!(self
+                .app
+                .attr(
+                    &Id::Preview,
+                    Attribute::Text,
+                    AttrValue::Payload(PropPayload::Vec(
+                        lines
+                            .into_iter()
+                            .map(|x| PropValue::TextSpan(TextSpan::from(x)))
+                            .collect()
+                    )),
+                )
+                .is_ok());
+            assert!(self
+                .app
+                .attr(
+                    &Id::Preview,
+                    Attribute::Title,
+                    AttrValue::Title((path.to_string_lossy().to_string(), Alignment::Left)),
+                )
+                .is_ok());
+            self.redraw = true;
+        }
+    }
+
+    /// Get the lines to preview for `p`: the entries for directories and the first lines for files
+    fn preview(p: &Path) -> Vec<String> {
+        if p.is_dir() {
+            return match std::fs::read_dir(p) {
+                Ok(e) => e
+                    .flatten()
+                    .map(|x| x.file_name().to_string_lossy().to_string())
+                    .collect(),
+                Err(err) => vec![err.to_string()],
+            };
+        }
+        match File::open(p) {
+            Ok(f) => BufReader::new(f)
+                .lines()
+                .take(PREVIEW_LINES)
+                .map(|x| x.unwrap_or_else(|_| String::from("<binary>")))
+                .collect(),
+            Err(err) => vec![err.to_string()],
+        }
+    }
+
+    fn view(&mut self) {
+        let _ = self.terminal.raw_mut().draw(|f| {
+            // Prepare chunks
+            let chunks = Layout::default()
+                .direction(LayoutDirection::Horizontal)
+                .margin(1)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+                .split(f.area());
+            self.app.view(&Id::FsTree, f, chunks[0]);
+            self.app.view(&Id::Preview, f, chunks[1]);
+        });
+    }
+}
+
+fn main() {
+    // Make model
+    let mut model: Model = Model::new(std::env::current_dir().ok().unwrap().as_path());
+    let _ = model.terminal.enable_raw_mode();
+    let _ = model.terminal.enter_alternate_screen();
+    // let's loop until quit is true
+    while !model.quit {
+        // Tick
+        if let Ok(messages) = model.app.tick(PollStrategy::Once) {
+            for msg in messages.into_iter() {
+                let mut msg = Some(msg);
+                while msg.is_some() {
+                    msg = model.update(msg);
+                }
+            }
+        }
+        // Load preview if the selection is stable
+        model.load_pending_preview();
+        // Redraw
+        if model.redraw {
+            model.view();
+            model.redraw = false;
+        }
+    }
+    // Terminate terminal
+    let _ = model.terminal.restore();
+}
+
+// -- update
+
+impl Update<Msg> for Model {
+    fn update(&mut self, msg: Option<Msg>) -> Option<Msg> {
+        self.redraw = true;
+        match msg.unwrap_or(Msg::None) {
+            Msg::AppClose => {
+                self.quit = true;
+                None
+            }
+            Msg::Selected(path) => {
+                // Debounce: replace the pending preview and restart the timer
+                self.pending_preview = Some((path, Instant::now()));
+                None
+            }
+            Msg::None => None,
+        }
+    }
+}
+
+// -- components
+
+#[derive(MockComponent)]
+pub struct FsTree {
+    component: TreeView<String>,
+}
+
+impl FsTree {
+    pub fn new(tree: Tree<String>) -> Self {
+        let root = tree.root().id().to_string();
+        FsTree {
+            component: TreeView::default()
+                .foreground(Color::Reset)
+                .borders(
+                    Borders::default()
+                        .color(Color::LightYellow)
+                        .modifiers(BorderType::Rounded),
+                )
+                .inactive(Style::default().fg(Color::Gray))
+                .indent_size(3)
+                .scroll_step(6)
+                .title(tree.root().id(), Alignment::Left)
+                .highlighted_color(Color::LightYellow)
+                .highlight_symbol("🦄")
+                .close_to_parent(true)
+                .with_tree(tree)
+                .initial_node(root),
+        }
+    }
+}
+
+impl Component<Msg, NoUserEvent> for FsTree {
+    fn on(&mut self, ev: Event<NoUserEvent>) -> Option<Msg> {
+        let result = match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Left,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(TreeCmd::Close.into()),
+            Event::Keyboard(KeyEvent {
+                code: Key::Right,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(TreeCmd::Open.into()),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageDown,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Scroll(Direction::Down)),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageUp,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Scroll(Direction::Up)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Down,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Move(Direction::Down)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Up,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Move(Direction::Up)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Home,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::GoTo(Position::Begin)),
+            Event::Keyboard(KeyEvent {
+                code: Key::End,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::GoTo(Position::End)),
+            _ => return None,
+        };
+        match result {
+            // The selected node changed: get it to update the preview.
+            // Node ids are encoded paths; the value is just the file name
+            CmdResult::Changed(_) => self
+                .component
+                .selected_node()
+                .and_then(|node| ids::decode_path(node.id()))
+                .map(Msg::Selected)
+                .or(Some(Msg::None)),
+            _ => Some(Msg::None),
+        }
+    }
+}
+
+// -- preview
+
+#[derive(MockComponent)]
+pub struct Preview {
+    component: Paragraph,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            component: Paragraph::default()
+                .borders(
+                    Borders::default()
+                        .color(Color::LightBlue)
+                        .modifiers(BorderType::Rounded),
+                )
+                .foreground(Color::Reset)
+                .title("Preview", Alignment::Left),
+        }
+    }
+}
+
+impl Component<Msg, NoUserEvent> for Preview {
+    fn on(&mut self, _ev: Event<NoUserEvent>) -> Option<Msg> {
+        None
+    }
+}
+
+// -- global listener
+
+#[derive(Default, MockComponent)]
+pub struct GlobalListener {
+    component: Phantom,
+}
+
+impl Component<Msg, NoUserEvent> for GlobalListener {
+    fn on(&mut self, ev: Event<NoUserEvent>) -> Option<Msg> {
+        match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Esc,
+                modifiers: KeyModifiers::NONE,
+            }) => Some(Msg::AppClose),
+            _ => None,
+        }
+    }
+}
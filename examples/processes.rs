@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tui_realm_stdlib::Phantom;
+use tuirealm::listener::{ListenerResult, Poll};
+use tuirealm::terminal::CrosstermTerminalAdapter;
+use tuirealm::{
+    application::PollStrategy,
+    command::{Cmd, Direction, Position},
+    event::{Event, Key, KeyEvent, KeyModifiers},
+    props::{Alignment, BorderType, Borders, Color, Style},
+    terminal::TerminalBridge,
+    Application, Component, EventListenerCfg, MockComponent, Sub, SubClause, SubEventClause,
+    Update,
+};
+// treeview
+use tui_realm_treeview::{Node, Tree, TreeCmd, TreeView};
+
+/// Interval between two refreshes of the process list
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Id of the root node; no process has pid 0 in the tree
+const ROOT_ID: &str = "0";
+
+// -- message
+#[derive(Debug, PartialEq)]
+pub enum Msg {
+    AppClose,
+    None,
+}
+
+// Let's define the component ids for our application
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Id {
+    GlobalListener,
+    ProcessTree,
+}
+
+/// A process, as reported by the port
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct Process {
+    pid: u32,
+    parent: Option<u32>,
+    name: String,
+    memory: u64,
+}
+
+// -- user event
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub enum UserEvent {
+    /// A new snapshot of the running processes
+    Processes(Vec<Process>),
+}
+
+/// Port which lists the running processes each time it's polled
+struct ProcessPort {
+    system: System,
+}
+
+impl Default for ProcessPort {
+    fn default() -> Self {
+        Self {
+            system: System::new(),
+        }
+    }
+}
+
+impl Poll<UserEvent> for ProcessPort {
+    fn poll(&mut self) -> ListenerResult<Option<Event<UserEvent>>> {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_memory(),
+        );
+        let processes = self
+            .system
+            .processes()
+            .values()
+            .map(|x| Process {
+                pid: x.pid().as_u32(),
+                parent: x.parent().map(|x| x.as_u32()),
+                name: x.name().to_string_lossy().to_string(),
+                memory: x.memory(),
+            })
+            .collect();
+        Ok(Some(Event::User(UserEvent::Processes(processes))))
+    }
+}
+
+struct Model {
+    app: Application<Id, Msg, UserEvent>,
+    quit: bool,   // Becomes true when the user presses <ESC>
+    redraw: bool, // Tells whether to refresh the UI; performance optimization
+    terminal: TerminalBridge<CrosstermTerminalAdapter>,
+}
+
+impl Model {
+    fn new() -> Self {
+        // Setup app; processes are refreshed through the port
+        let mut app: Application<Id, Msg, UserEvent> = Application::init(
+            EventListenerCfg::default()
+                .crossterm_input_listener(Duration::from_millis(10), 10)
+                .add_port(Box::new(ProcessPort::default()), REFRESH_INTERVAL, 1),
+        );
+        assert!(app
+            .mount(
+                Id::ProcessTree,
+                Box::new(ProcessTree::default()),
+                // Process events are forwarded to the tree, as it's always active
+                vec![]
+            )
+            .is_ok());
+        // Mount global listener which will listen for <ESC>
+        assert!(app
+            .mount(
+                Id::GlobalListener,
+                Box::new(GlobalListener::default()),
+                vec![Sub::new(
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Esc,
+                        modifiers: KeyModifiers::NONE,
+                    }),
+                    SubClause::Always
+                )]
+            )
+            .is_ok());
+        assert!(app.active(&Id::ProcessTree).is_ok());
+        Model {
+            app,
+            quit: false,
+            redraw: true,
+            terminal: TerminalBridge::init_crossterm().expect("Could not initialize terminal"),
+        }
+    }
+
+    fn view(&mut self) {
+        let _ = self.terminal.raw_mut().draw(|f| {
+            self.app.view(&Id::ProcessTree, f, f.area());
+        });
+    }
+}
+
+fn main() {
+    // Make model
+    let mut model: Model = Model::new();
+    let _ = model.terminal.enable_raw_mode();
+    let _ = model.terminal.enter_alternate_screen();
+    // let's loop until quit is true
+    while !model.quit {
+        // Tick
+        if let Ok(messages) = model.app.tick(PollStrategy::Once) {
+            for msg in messages.into_iter() {
+                let mut msg = Some(msg);
+                while msg.is_some() {
+                    msg = model.update(msg);
+                }
+            }
+        }
+        // Redraw
+        if model.redraw {
+            model.view();
+            model.redraw = false;
+        }
+    }
+    // Terminate terminal
+    let _ = model.terminal.restore();
+}
+
+// -- update
+
+impl Update<Msg> for Model {
+    fn update(&mut self, msg: Option<Msg>) -> Option<Msg> {
+        self.redraw = true;
+        match msg.unwrap_or(Msg::None) {
+            Msg::AppClose => {
+                self.quit = true;
+                None
+            }
+            Msg::None => None,
+        }
+    }
+}
+
+// -- components
+
+#[derive(MockComponent)]
+pub struct ProcessTree {
+    component: TreeView<String>,
+}
+
+impl Default for ProcessTree {
+    fn default() -> Self {
+        ProcessTree {
+            component: TreeView::default()
+                .foreground(Color::Reset)
+                .borders(
+                    Borders::default()
+                        .color(Color::LightGreen)
+                        .modifiers(BorderType::Rounded),
+                )
+                .inactive(Style::default().fg(Color::Gray))
+                .indent_size(2)
+                .scroll_step(6)
+                .title("Processes", Alignment::Left)
+                .highlighted_color(Color::LightGreen)
+                .highlight_symbol(">")
+                .close_to_parent(true)
+                // Keep open nodes and selection between refreshes
+                .preserve_state(true)
+                .with_tree(Self::process_tree(&[]))
+                .initial_node(ROOT_ID),
+        }
+    }
+}
+
+impl ProcessTree {
+    /// Build the process tree from a snapshot.
+    /// Ids are pids, so open nodes and selection survive refreshes as long as the processes are alive
+    fn process_tree(processes: &[Process]) -> Tree<String> {
+        let by_pid: HashMap<u32, &Process> = processes.iter().map(|x| (x.pid, x)).collect();
+        let mut children: HashMap<u32, Vec<&Process>> = HashMap::new();
+        for process in processes.iter() {
+            // Orphans are attached to the root
+            let parent = process
+                .parent
+                .filter(|x| by_pid.contains_key(x))
+                .unwrap_or(0);
+            children.entry(parent).or_default().push(process);
+        }
+        children.values_mut().for_each(|x| x.sort_by_key(|x| x.pid));
+        let mut root = Node::new(
+            ROOT_ID.to_string(),
+            format!("processes ({})", processes.len()),
+        );
+        Self::add_children(&mut root, 0, &children);
+        Tree::new(root)
+    }
+
+    fn add_children(node: &mut Node<String>, pid: u32, children: &HashMap<u32, Vec<&Process>>) {
+        for child in children.get(&pid).into_iter().flatten() {
+            // Guard against processes reported as their own parent
+            if child.pid == pid {
+                continue;
+            }
+            let mut child_node = Node::new(
+                child.pid.to_string(),
+                format!("{} [{}] {} KiB", child.name, child.pid, child.memory / 1024),
+            );
+            Self::add_children(&mut child_node, child.pid, children);
+            node.add_child(child_node);
+        }
+    }
+}
+
+impl Component<Msg, UserEvent> for ProcessTree {
+    fn on(&mut self, ev: Event<UserEvent>) -> Option<Msg> {
+        let _ = match ev {
+            Event::User(UserEvent::Processes(processes)) => {
+                // State is preserved, since `preserve_state` is set
+                self.component.set_tree(Self::process_tree(&processes));
+                return Some(Msg::None);
+            }
+            Event::Keyboard(KeyEvent {
+                code: Key::Left,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(TreeCmd::Close.into()),
+            Event::Keyboard(KeyEvent {
+                code: Key::Right,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(TreeCmd::Open.into()),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageDown,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Scroll(Direction::Down)),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageUp,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Scroll(Direction::Up)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Down,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Move(Direction::Down)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Up,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::Move(Direction::Up)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Home,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::GoTo(Position::Begin)),
+            Event::Keyboard(KeyEvent {
+                code: Key::End,
+                modifiers: KeyModifiers::NONE,
+            }) => self.perform(Cmd::GoTo(Position::End)),
+            _ => return None,
+        };
+        Some(Msg::None)
+    }
+}
+
+// -- global listener
+
+#[derive(Default, MockComponent)]
+pub struct GlobalListener {
+    component: Phantom,
+}
+
+impl Component<Msg, UserEvent> for GlobalListener {
+    fn on(&mut self, ev: Event<UserEvent>) -> Option<Msg> {
+        match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Esc,
+                modifiers: KeyModifiers::NONE,
+            }) => Some(Msg::AppClose),
+            _ => None,
+        }
+    }
+}
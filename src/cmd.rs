@@ -0,0 +1,219 @@
+//! # Cmd
+//!
+//! This module exposes the typed commands of the tree view and their policies
+
+use super::{
+    Cmd, TREE_CMD_CLEAR_FLAGS, TREE_CMD_CLOSE, TREE_CMD_CLOSE_ALL, TREE_CMD_CLOSE_PARENT,
+    TREE_CMD_COMMIT, TREE_CMD_GOTO_PARENT, TREE_CMD_INDENT_DEC, TREE_CMD_INDENT_INC,
+    TREE_CMD_MARK_ALL_SIBLINGS, TREE_CMD_NEXT_BOOKMARK, TREE_CMD_OPEN, TREE_CMD_OPEN_ALL,
+    TREE_CMD_PREV_BOOKMARK, TREE_CMD_REDO, TREE_CMD_REORDER, TREE_CMD_SEARCH, TREE_CMD_SEARCH_NEXT,
+    TREE_CMD_SEARCH_PREV, TREE_CMD_SELECT_ALL_VISIBLE, TREE_CMD_SELECT_NONE, TREE_CMD_SORT_COLUMN,
+    TREE_CMD_TOGGLE_MARK, TREE_CMD_UNDO, TREE_CMD_VIEW_BOTTOM, TREE_CMD_VIEW_CENTER,
+    TREE_CMD_VIEW_TOP,
+};
+
+/// ## TreeCmd
+///
+/// Custom commands supported by the tree view. Use `TreeCmd::Open.into()` to get the `Cmd` to perform,
+/// instead of `Cmd::Custom(TREE_CMD_OPEN)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TreeCmd {
+    /// Remove all flags from all nodes
+    ClearFlags,
+    /// Close selected node
+    Close,
+    /// Close selected node and all of its descendants
+    CloseAll,
+    /// Close the parent of the selected node and select it
+    CloseParent,
+    /// Commit the selected node, when the selection is soft
+    Commit,
+    /// Select the parent of the selected node
+    GotoParent,
+    /// Decrement indent size
+    IndentDec,
+    /// Increment indent size
+    IndentInc,
+    /// Mark the selected node and all of its siblings
+    MarkAllSiblings,
+    /// Select the next bookmarked node
+    NextBookmark,
+    /// Open selected node
+    Open,
+    /// Open selected node and all of its descendants
+    OpenAll,
+    /// Select the previous bookmarked node
+    PrevBookmark,
+    /// Restore the state changed by the last undo
+    Redo,
+    /// Start moving the selected node among its siblings
+    Reorder,
+    /// Search the nodes matching `TREE_SEARCH_QUERY` and select the first match
+    Search,
+    /// Select the next match of the last search
+    SearchNext,
+    /// Select the previous match of the last search
+    SearchPrev,
+    /// Mark all the visible nodes
+    SelectAllVisible,
+    /// Unmark all the nodes
+    SelectNone,
+    /// Sort the siblings by the column with the provided index, cycling ascending, descending and unsorted.
+    /// The index must be lower than the length of `TREE_CMD_SORT_COLUMN`
+    SortColumn(usize),
+    /// Mark the selected node, or unmark it if it is marked
+    ToggleMark,
+    /// Restore the state before the last change
+    Undo,
+    /// Scroll the view to put the selected node at the bottom
+    ViewBottom,
+    /// Scroll the view to put the selected node in the middle
+    ViewCenter,
+    /// Scroll the view to put the selected node on top
+    ViewTop,
+}
+
+impl TreeCmd {
+    /// ### as_str
+    ///
+    /// Get the name of the command used in `Cmd::Custom`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClearFlags => TREE_CMD_CLEAR_FLAGS,
+            Self::Close => TREE_CMD_CLOSE,
+            Self::CloseAll => TREE_CMD_CLOSE_ALL,
+            Self::CloseParent => TREE_CMD_CLOSE_PARENT,
+            Self::Commit => TREE_CMD_COMMIT,
+            Self::GotoParent => TREE_CMD_GOTO_PARENT,
+            Self::IndentDec => TREE_CMD_INDENT_DEC,
+            Self::IndentInc => TREE_CMD_INDENT_INC,
+            Self::MarkAllSiblings => TREE_CMD_MARK_ALL_SIBLINGS,
+            Self::NextBookmark => TREE_CMD_NEXT_BOOKMARK,
+            Self::Open => TREE_CMD_OPEN,
+            Self::OpenAll => TREE_CMD_OPEN_ALL,
+            Self::PrevBookmark => TREE_CMD_PREV_BOOKMARK,
+            Self::Redo => TREE_CMD_REDO,
+            Self::Reorder => TREE_CMD_REORDER,
+            Self::Search => TREE_CMD_SEARCH,
+            Self::SearchNext => TREE_CMD_SEARCH_NEXT,
+            Self::SearchPrev => TREE_CMD_SEARCH_PREV,
+            Self::SelectAllVisible => TREE_CMD_SELECT_ALL_VISIBLE,
+            Self::SelectNone => TREE_CMD_SELECT_NONE,
+            Self::SortColumn(column) => TREE_CMD_SORT_COLUMN[*column],
+            Self::ToggleMark => TREE_CMD_TOGGLE_MARK,
+            Self::Undo => TREE_CMD_UNDO,
+            Self::ViewBottom => TREE_CMD_VIEW_BOTTOM,
+            Self::ViewCenter => TREE_CMD_VIEW_CENTER,
+            Self::ViewTop => TREE_CMD_VIEW_TOP,
+        }
+    }
+}
+
+/// ## LazySubmit
+///
+/// What `Submit` does on a lazy branch, i.e. a branch whose children haven't been loaded yet
+/// (see `NodeValue::is_branch_hint` and `TreeView::unload_closed`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LazySubmit {
+    /// Submit the node as any other node
+    #[default]
+    PassThrough,
+    /// Open the node, as `TREE_CMD_OPEN` does: returns `Custom($TREE_CMD_OPEN, state)` so that its children can be loaded
+    Load,
+    /// Close the node if it is open; open it as `Load` does otherwise
+    Toggle,
+}
+
+impl From<TreeCmd> for Cmd {
+    fn from(cmd: TreeCmd) -> Self {
+        Cmd::Custom(cmd.as_str())
+    }
+}
+
+impl TryFrom<&str> for TreeCmd {
+    type Error = ();
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        match cmd {
+            TREE_CMD_CLEAR_FLAGS => Ok(Self::ClearFlags),
+            TREE_CMD_CLOSE => Ok(Self::Close),
+            TREE_CMD_CLOSE_ALL => Ok(Self::CloseAll),
+            TREE_CMD_CLOSE_PARENT => Ok(Self::CloseParent),
+            TREE_CMD_COMMIT => Ok(Self::Commit),
+            TREE_CMD_GOTO_PARENT => Ok(Self::GotoParent),
+            TREE_CMD_INDENT_DEC => Ok(Self::IndentDec),
+            TREE_CMD_INDENT_INC => Ok(Self::IndentInc),
+            TREE_CMD_MARK_ALL_SIBLINGS => Ok(Self::MarkAllSiblings),
+            TREE_CMD_NEXT_BOOKMARK => Ok(Self::NextBookmark),
+            TREE_CMD_OPEN => Ok(Self::Open),
+            TREE_CMD_OPEN_ALL => Ok(Self::OpenAll),
+            TREE_CMD_PREV_BOOKMARK => Ok(Self::PrevBookmark),
+            TREE_CMD_REDO => Ok(Self::Redo),
+            TREE_CMD_REORDER => Ok(Self::Reorder),
+            TREE_CMD_SEARCH => Ok(Self::Search),
+            TREE_CMD_SEARCH_NEXT => Ok(Self::SearchNext),
+            TREE_CMD_SEARCH_PREV => Ok(Self::SearchPrev),
+            TREE_CMD_SELECT_ALL_VISIBLE => Ok(Self::SelectAllVisible),
+            TREE_CMD_SELECT_NONE => Ok(Self::SelectNone),
+            TREE_CMD_TOGGLE_MARK => Ok(Self::ToggleMark),
+            TREE_CMD_UNDO => Ok(Self::Undo),
+            TREE_CMD_VIEW_BOTTOM => Ok(Self::ViewBottom),
+            TREE_CMD_VIEW_CENTER => Ok(Self::ViewCenter),
+            TREE_CMD_VIEW_TOP => Ok(Self::ViewTop),
+            cmd => TREE_CMD_SORT_COLUMN
+                .iter()
+                .position(|x| *x == cmd)
+                .map(Self::SortColumn)
+                .ok_or(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_convert_tree_cmd() {
+        for cmd in [
+            TreeCmd::ClearFlags,
+            TreeCmd::Close,
+            TreeCmd::CloseAll,
+            TreeCmd::CloseParent,
+            TreeCmd::Commit,
+            TreeCmd::GotoParent,
+            TreeCmd::IndentDec,
+            TreeCmd::IndentInc,
+            TreeCmd::MarkAllSiblings,
+            TreeCmd::NextBookmark,
+            TreeCmd::Open,
+            TreeCmd::OpenAll,
+            TreeCmd::PrevBookmark,
+            TreeCmd::Redo,
+            TreeCmd::Reorder,
+            TreeCmd::Search,
+            TreeCmd::SearchNext,
+            TreeCmd::SearchPrev,
+            TreeCmd::SelectAllVisible,
+            TreeCmd::SelectNone,
+            TreeCmd::SortColumn(0),
+            TreeCmd::SortColumn(7),
+            TreeCmd::ToggleMark,
+            TreeCmd::Undo,
+            TreeCmd::ViewBottom,
+            TreeCmd::ViewCenter,
+            TreeCmd::ViewTop,
+        ] {
+            assert_eq!(TreeCmd::try_from(cmd.as_str()), Ok(cmd));
+        }
+        assert_eq!(Cmd::from(TreeCmd::Open), Cmd::Custom(TREE_CMD_OPEN));
+        assert_eq!(
+            Cmd::from(TreeCmd::SortColumn(2)),
+            Cmd::Custom(TREE_CMD_SORT_COLUMN[2])
+        );
+        assert_eq!(TreeCmd::try_from("unknown"), Err(()));
+    }
+}
@@ -0,0 +1,156 @@
+//! # Decoration
+//!
+//! This module exposes the decorations which can be attached to the nodes of a tree view
+
+use tuirealm::props::Style;
+
+use super::{Node, NodeValue};
+
+/// ## Decoration
+///
+/// A decoration is an additional text rendered before and/or after the node label (e.g. an icon or a git status).
+/// Decorations are meant to be resolved by the application, even after the node has been rendered
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Decoration {
+    /// Text to render before the label
+    prefix: Option<String>,
+    /// Text to render after the label
+    suffix: Option<String>,
+    /// Style for decoration text. If `None`, the row style is used
+    style: Option<Style>,
+}
+
+impl Decoration {
+    /// ### prefix
+    ///
+    /// Set text to render before the label
+    pub fn prefix<S: AsRef<str>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.as_ref().to_string());
+        self
+    }
+
+    /// ### suffix
+    ///
+    /// Set text to render after the label
+    pub fn suffix<S: AsRef<str>>(mut self, suffix: S) -> Self {
+        self.suffix = Some(suffix.as_ref().to_string());
+        self
+    }
+
+    /// ### style
+    ///
+    /// Set style for decoration text
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// ### merge
+    ///
+    /// Merge `other` on top of this decoration: prefixes and suffixes are concatenated, while the style of
+    /// `other` is overlaid on this style
+    pub fn merge(mut self, other: &Decoration) -> Self {
+        fn concat(a: Option<String>, b: Option<&str>) -> Option<String> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or_else(|| b.map(|x| x.to_string())),
+            }
+        }
+        self.prefix = concat(self.prefix, other.get_prefix());
+        self.suffix = concat(self.suffix, other.get_suffix());
+        self.style = match (self.style, other.style) {
+            (Some(a), Some(b)) => Some(a.patch(b)),
+            (a, b) => a.or(b),
+        };
+        self
+    }
+
+    /// ### get_prefix
+    ///
+    /// Get text to render before the label
+    pub fn get_prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// ### get_suffix
+    ///
+    /// Get text to render after the label
+    pub fn get_suffix(&self) -> Option<&str> {
+        self.suffix.as_deref()
+    }
+
+    /// ### get_style
+    ///
+    /// Get style for decoration text
+    pub fn get_style(&self) -> Option<Style> {
+        self.style
+    }
+}
+
+/// ## NodeDecorator
+///
+/// A decorator computes a decoration for nodes (e.g. git status, bookmarks, search matches).
+/// Many decorators can be registered on a tree view: they run in order and their decorations are merged
+/// (see `Decoration::merge`) on top of the one set for the node, so the latest decorator takes precedence on styles.
+///
+/// Closures taking a node and returning an optional decoration are decorators too
+pub trait NodeDecorator<V: NodeValue> {
+    /// Get the decoration for `node`; `None` if the decorator has nothing to render for it
+    fn decorate(&self, node: &Node<V>) -> Option<Decoration>;
+}
+
+impl<V, F> NodeDecorator<V> for F
+where
+    V: NodeValue,
+    F: Fn(&Node<V>) -> Option<Decoration>,
+{
+    fn decorate(&self, node: &Node<V>) -> Option<Decoration> {
+        self(node)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use tuirealm::props::Color;
+
+    #[test]
+    fn should_build_decoration() {
+        let decoration = Decoration::default();
+        assert_eq!(decoration.get_prefix(), None);
+        assert_eq!(decoration.get_suffix(), None);
+        assert_eq!(decoration.get_style(), None);
+        let decoration = Decoration::default()
+            .prefix("📁 ")
+            .suffix(" M")
+            .style(Style::default().fg(Color::Yellow));
+        assert_eq!(decoration.get_prefix(), Some("📁 "));
+        assert_eq!(decoration.get_suffix(), Some(" M"));
+        assert_eq!(
+            decoration.get_style(),
+            Some(Style::default().fg(Color::Yellow))
+        );
+    }
+
+    #[test]
+    fn should_merge_decorations() {
+        let decoration = Decoration::default()
+            .prefix("M")
+            .style(Style::default().fg(Color::Yellow))
+            .merge(&Decoration::default().prefix("★").suffix(" *"))
+            .merge(
+                &Decoration::default()
+                    .suffix("!")
+                    .style(Style::default().bg(Color::Red)),
+            );
+        assert_eq!(decoration.get_prefix(), Some("M★"));
+        assert_eq!(decoration.get_suffix(), Some(" *!"));
+        assert_eq!(
+            decoration.get_style(),
+            Some(Style::default().fg(Color::Yellow).bg(Color::Red))
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! # Defaults
+//!
+//! This module exposes the defaults which can be shared by many tree views
+
+use tuirealm::props::{Borders, Color, Style, TextModifiers};
+
+/// ## TreeDefaults
+///
+/// Bundles the properties which are usually the same for all the tree views of an application
+/// (colors, indentation, scroll step, symbols), so that they're defined once and applied to each component
+/// with `TreeView::with_defaults`. Only the properties set to `Some` are applied; each component can still
+/// override them with the builder methods called after `with_defaults`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TreeDefaults {
+    /// Foreground color
+    pub foreground: Option<Color>,
+    /// Background color
+    pub background: Option<Color>,
+    /// Color to highlight the selected node
+    pub highlighted_color: Option<Color>,
+    /// Style to use when the component is inactive
+    pub inactive: Option<Style>,
+    /// Borders
+    pub borders: Option<Borders>,
+    /// Text modifiers
+    pub modifiers: Option<TextModifiers>,
+    /// Symbol displayed on the side of the selected node
+    pub highlight_symbol: Option<String>,
+    /// Indentation size of each depth level
+    pub indent_size: Option<u16>,
+    /// Rows to move on scroll
+    pub scroll_step: Option<usize>,
+    /// Whether closing a leaf or a closed node selects its parent
+    pub close_to_parent: Option<bool>,
+}
@@ -0,0 +1,142 @@
+//! # Description
+//!
+//! This module exposes the readable description of the selected node, meant to be announced by screen readers
+
+use super::{Node, NodeValue, TreeState};
+
+/// ## DescriptionPart
+///
+/// A component of the description returned by `TreeView::selected_description`.
+/// Components are joined with ", " in the order they are provided; empty ones are skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptionPart {
+    /// The label of the node (e.g. "readme.md")
+    Label,
+    /// The kind of the node (see `NodeValue::kind`), "branch" or "leaf" by default (e.g. "file")
+    Kind,
+    /// Whether the branch is open or closed (e.g. "expanded")
+    Expanded,
+    /// The labels of the ancestors of the node (e.g. "inside home slash omar")
+    Path,
+    /// The position of the node among its siblings (e.g. "item 3 of 7")
+    Position,
+    /// The depth of the node, where the root is at level 1 (e.g. "level 2")
+    Level,
+}
+
+impl DescriptionPart {
+    /// ### defaults
+    ///
+    /// Components of the description by default: label, kind, path and position
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::Label, Self::Kind, Self::Path, Self::Position]
+    }
+}
+
+/// ### describe
+///
+/// Describe `node` of the tree with root `root`, with the provided components
+pub(crate) fn describe<V: NodeValue>(
+    root: &Node<V>,
+    states: &TreeState,
+    node: &Node<V>,
+    parts: &[DescriptionPart],
+) -> String {
+    let mut ancestors = Vec::new();
+    let mut parent = root.parent(node.id());
+    while let Some(p) = parent {
+        ancestors.insert(0, p);
+        parent = root.parent(p.id());
+    }
+    parts
+        .iter()
+        .map(|part| match part {
+            DescriptionPart::Label => label(node),
+            DescriptionPart::Kind => match node.value().kind() {
+                Some(kind) => kind.to_string(),
+                None if states.is_branch(node) => String::from("branch"),
+                None => String::from("leaf"),
+            },
+            DescriptionPart::Expanded if !states.is_branch(node) => String::new(),
+            DescriptionPart::Expanded => match states.is_open(node) {
+                true => String::from("expanded"),
+                false => String::from("collapsed"),
+            },
+            DescriptionPart::Path if ancestors.is_empty() => String::new(),
+            DescriptionPart::Path => format!(
+                "inside {}",
+                ancestors
+                    .iter()
+                    .map(|x| label(x))
+                    .collect::<Vec<String>>()
+                    .join(" slash ")
+            ),
+            DescriptionPart::Position => {
+                let siblings = ancestors.last().map(|x| x.children()).unwrap_or_default();
+                let position = siblings
+                    .iter()
+                    .position(|x| x.id() == node.id())
+                    .unwrap_or(0);
+                format!("item {} of {}", position + 1, siblings.len().max(1))
+            }
+            DescriptionPart::Level => format!("level {}", ancestors.len() + 1),
+        })
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ### label
+///
+/// Get the label of `node`, made of its render parts
+fn label<V: NodeValue>(node: &Node<V>) -> String {
+    node.value()
+        .render_parts_iter()
+        .map(|(text, _)| text)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::mock::mock_tree;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_describe_node() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        let node = tree.root().query(&String::from("aB1")).unwrap();
+        state.select(tree.root(), node);
+        assert_eq!(
+            describe(tree.root(), &state, node, &DescriptionPart::defaults()),
+            "aB1, leaf, inside / slash a slash aB, item 2 of 3"
+        );
+        let node = tree.root().query(&String::from("aB")).unwrap();
+        assert_eq!(
+            describe(
+                tree.root(),
+                &state,
+                node,
+                &[
+                    DescriptionPart::Label,
+                    DescriptionPart::Expanded,
+                    DescriptionPart::Level
+                ]
+            ),
+            "aB, expanded, level 3"
+        );
+        // The root has no path
+        assert_eq!(
+            describe(
+                tree.root(),
+                &state,
+                tree.root(),
+                &DescriptionPart::defaults()
+            ),
+            "/, branch, item 1 of 1"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! # Diff
+//!
+//! This module compares two snapshots of a tree, so that tests can assert precisely how an update routine
+//! changed the tree
+
+use super::{Node, Tree};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// ## DiffEntry
+///
+/// A difference between two snapshots of a tree. Nodes are matched by id.
+///
+/// The debug output is a single line per entry (e.g. `+ /src/main.rs at /src[0]`), to keep assertion failures readable
+#[derive(Clone, PartialEq, Eq)]
+pub enum DiffEntry {
+    /// The node has been added to `parent` at `index`
+    Added {
+        id: String,
+        parent: Option<String>,
+        index: usize,
+    },
+    /// The node has been removed from `parent`
+    Removed { id: String, parent: Option<String> },
+    /// The value of the node has changed
+    Changed { id: String },
+    /// The node has been moved to another parent, or to another position among its siblings
+    Moved {
+        id: String,
+        from: (Option<String>, usize),
+        to: (Option<String>, usize),
+    },
+}
+
+impl DiffEntry {
+    /// ### id
+    ///
+    /// Get the id of the node
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Added { id, .. }
+            | Self::Removed { id, .. }
+            | Self::Changed { id }
+            | Self::Moved { id, .. } => id,
+        }
+    }
+}
+
+impl fmt::Debug for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Format the position at `index` of the children of `parent`
+        fn position(parent: &Option<String>, index: usize) -> String {
+            format!("{}[{index}]", parent.as_deref().unwrap_or("<root>"))
+        }
+
+        match self {
+            Self::Added { id, parent, index } => {
+                write!(f, "+ {id} at {}", position(parent, *index))
+            }
+            Self::Removed { id, parent } => {
+                write!(f, "- {id} from {}", parent.as_deref().unwrap_or("<root>"))
+            }
+            Self::Changed { id } => write!(f, "~ {id}"),
+            Self::Moved { id, from, to } => write!(
+                f,
+                "> {id}: {} -> {}",
+                position(&from.0, from.1),
+                position(&to.0, to.1)
+            ),
+        }
+    }
+}
+
+/// Position of a node in a tree: its parent id, its index among its siblings and the node
+type Position<'a, V> = (Option<&'a str>, usize, &'a Node<V>);
+
+/// ### tree_diff
+///
+/// Compare `old` with `new`, matching nodes by id. Removed nodes are reported first, in the order of `old`;
+/// then added, changed and moved nodes, in the order of `new`.
+///
+/// A node is moved if its parent changed, or if its relative order with its remaining siblings changed: the fewest
+/// siblings are reported as moved, so that inserting or removing a sibling doesn't move the others
+pub fn tree_diff<V: PartialEq>(old: &Tree<V>, new: &Tree<V>) -> Vec<DiffEntry> {
+    let mut old_nodes = Vec::new();
+    index_nodes(old.root(), None, 0, &mut old_nodes);
+    let mut new_nodes = Vec::new();
+    index_nodes(new.root(), None, 0, &mut new_nodes);
+    let old_map: HashMap<&str, Position<V>> =
+        old_nodes.iter().map(|x| (x.2.id().as_str(), *x)).collect();
+    let new_map: HashMap<&str, Position<V>> =
+        new_nodes.iter().map(|x| (x.2.id().as_str(), *x)).collect();
+    let mut diff: Vec<DiffEntry> = old_nodes
+        .iter()
+        .filter(|(_, _, node)| !new_map.contains_key(node.id().as_str()))
+        .map(|(parent, _, node)| DiffEntry::Removed {
+            id: node.id().to_string(),
+            parent: parent.map(str::to_string),
+        })
+        .collect();
+    // Reordered children by parent
+    let mut reorders: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (parent, index, node) in new_nodes.iter() {
+        let id = node.id().as_str();
+        let Some((old_parent, old_index, old_node)) = old_map.get(id) else {
+            diff.push(DiffEntry::Added {
+                id: id.to_string(),
+                parent: parent.map(str::to_string),
+                index: *index,
+            });
+            continue;
+        };
+        if old_node.value() != node.value() {
+            diff.push(DiffEntry::Changed { id: id.to_string() });
+        }
+        let moved = match (old_parent, parent) {
+            (Some(old_parent), Some(parent)) if old_parent == parent => reorders
+                .entry(parent)
+                .or_insert_with(|| reordered(old_map[parent].2, new_map[parent].2, &new_map))
+                .contains(&id),
+            (None, None) => false,
+            _ => true,
+        };
+        if moved {
+            diff.push(DiffEntry::Moved {
+                id: id.to_string(),
+                from: (old_parent.map(str::to_string), *old_index),
+                to: (parent.map(str::to_string), *index),
+            });
+        }
+    }
+    diff
+}
+
+/// ### index_nodes
+///
+/// Collect the position of `node` and its descendants, in pre-order
+fn index_nodes<'a, V>(
+    node: &'a Node<V>,
+    parent: Option<&'a str>,
+    index: usize,
+    nodes: &mut Vec<Position<'a, V>>,
+) {
+    nodes.push((parent, index, node));
+    for (i, child) in node.children().iter().enumerate() {
+        index_nodes(child, Some(node.id().as_str()), i, nodes);
+    }
+}
+
+/// ### reordered
+///
+/// Get the ids of the children kept in `new` whose relative order changed from `old`: the children which are not
+/// part of the longest common subsequence of the kept children
+fn reordered<'a, V>(
+    old: &'a Node<V>,
+    new: &'a Node<V>,
+    new_map: &HashMap<&str, Position<V>>,
+) -> Vec<&'a str> {
+    let new_id = new.id().as_str();
+    let kept = |node: &'a Node<V>| -> Vec<&'a str> {
+        node.children()
+            .iter()
+            .map(|x| x.id().as_str())
+            .filter(|x| new_map.get(x).is_some_and(|(p, _, _)| *p == Some(new_id)))
+            .collect()
+    };
+    let before = kept(old);
+    let after: Vec<&str> = kept(new)
+        .into_iter()
+        .filter(|x| before.contains(x))
+        .collect();
+    // Longest common subsequence
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = match before[i] == after[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut moved = Vec::new();
+    while j < after.len() {
+        if i < before.len() && before[i] == after[j] {
+            i += 1;
+            j += 1;
+        } else if i < before.len() && lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            moved.push(after[j]);
+            j += 1;
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn node(id: &str, value: &str) -> Node<String> {
+        Node::new(id.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn should_diff_trees() {
+        let old = Tree::new(
+            node("/", "root")
+                .with_child(
+                    node("a", "a")
+                        .with_child(node("a0", "a0"))
+                        .with_child(node("a1", "a1"))
+                        .with_child(node("a2", "a2"))
+                        .with_child(node("a3", "a3")),
+                )
+                .with_child(node("b", "b").with_child(node("b0", "b0")))
+                .with_child(node("c", "c")),
+        );
+        assert!(tree_diff(&old, &old).is_empty());
+        let new = Tree::new(
+            node("/", "root")
+                .with_child(
+                    node("a", "a")
+                        .with_child(node("x", "x"))
+                        .with_child(node("a0", "a0"))
+                        .with_child(node("a2", "a2"))
+                        .with_child(node("a1", "a1")),
+                )
+                .with_child(node("c", "C").with_child(node("b0", "b0"))),
+        );
+        assert_eq!(
+            tree_diff(&old, &new),
+            vec![
+                DiffEntry::Removed {
+                    id: String::from("a3"),
+                    parent: Some(String::from("a")),
+                },
+                DiffEntry::Removed {
+                    id: String::from("b"),
+                    parent: Some(String::from("/")),
+                },
+                DiffEntry::Added {
+                    id: String::from("x"),
+                    parent: Some(String::from("a")),
+                    index: 0,
+                },
+                DiffEntry::Moved {
+                    id: String::from("a1"),
+                    from: (Some(String::from("a")), 1),
+                    to: (Some(String::from("a")), 3),
+                },
+                DiffEntry::Changed {
+                    id: String::from("c")
+                },
+                DiffEntry::Moved {
+                    id: String::from("b0"),
+                    from: (Some(String::from("b")), 0),
+                    to: (Some(String::from("c")), 0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_format_diff_entries() {
+        let entries = vec![
+            DiffEntry::Added {
+                id: String::from("x"),
+                parent: Some(String::from("a")),
+                index: 0,
+            },
+            DiffEntry::Removed {
+                id: String::from("/"),
+                parent: None,
+            },
+            DiffEntry::Changed {
+                id: String::from("c"),
+            },
+            DiffEntry::Moved {
+                id: String::from("b0"),
+                from: (Some(String::from("b")), 0),
+                to: (Some(String::from("c")), 2),
+            },
+        ];
+        assert_eq!(
+            format!("{entries:?}"),
+            "[+ x at a[0], - / from <root>, ~ c, > b0: b[0] -> c[2]]"
+        );
+        assert_eq!(entries[3].id(), "b0");
+    }
+}
@@ -0,0 +1,82 @@
+//! # Fixtures
+//!
+//! This module provides utilities to generate large deterministic trees, e.g. for tests and benchmarks.
+//! It is available with the `fixtures` feature.
+
+use super::{Node, Tree};
+
+/// ### generate
+///
+/// Generate a tree with `depth` levels below the root, where each branch has `breadth` children.
+///
+/// Node ids are derived from the route of the node: the root id is `/`, while the id of any other node
+/// is the id of its parent followed by `/` and its index (e.g. `/0/2`).
+/// The value of each node is built by `label_fn`, which is called with the route of the node
+/// (the indexes of the children to walk from the root; empty for the root)
+pub fn generate<V, F>(depth: usize, breadth: usize, mut label_fn: F) -> Tree<V>
+where
+    F: FnMut(&[usize]) -> V,
+{
+    /// ### generate_r
+    ///
+    /// Generate the node at `route` and its descendants
+    fn generate_r<V, F>(
+        id: String,
+        route: &mut Vec<usize>,
+        depth: usize,
+        breadth: usize,
+        label_fn: &mut F,
+    ) -> Node<V>
+    where
+        F: FnMut(&[usize]) -> V,
+    {
+        let mut node = Node::new(id.clone(), label_fn(route));
+        if route.len() < depth {
+            for i in 0..breadth {
+                route.push(i);
+                let child_id = match route.len() {
+                    1 => format!("/{i}"),
+                    _ => format!("{id}/{i}"),
+                };
+                node.add_child(generate_r(child_id, route, depth, breadth, label_fn));
+                route.pop();
+            }
+        }
+        node
+    }
+    Tree::new(generate_r(
+        String::from("/"),
+        &mut Vec::new(),
+        depth,
+        breadth,
+        &mut label_fn,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_generate_tree() {
+        let tree = generate(3, 4, |route| format!("{route:?}"));
+        // 1 + 4 + 16 + 64
+        assert_eq!(tree.root().count(), 85);
+        assert_eq!(tree.root().value().as_str(), "[]");
+        let node = tree.root().query(&String::from("/1/3/0")).unwrap();
+        assert_eq!(node.value().as_str(), "[1, 3, 0]");
+        assert!(node.is_leaf());
+        assert_eq!(tree.root().query(&String::from("/1/3")).unwrap().count(), 5);
+    }
+
+    #[test]
+    fn should_generate_root_only() {
+        let tree = generate(0, 4, |_| String::from("root"));
+        assert!(tree.root().is_leaf());
+        let tree = generate(4, 0, |_| String::from("root"));
+        assert!(tree.root().is_leaf());
+    }
+}
@@ -0,0 +1,35 @@
+//! # Flag
+//!
+//! This module exposes the flags which can be set on the nodes of a tree view
+
+/// ## Flag
+///
+/// A flag is a tag which can be set on nodes (e.g. marked, cut, queued), in order to operate on many nodes at once.
+/// Define your own flags as constants:
+///
+/// ```rust
+/// use tui_realm_treeview::Flag;
+///
+/// const QUEUED: Flag = Flag::new("queued");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flag(&'static str);
+
+impl Flag {
+    /// Flag for nodes marked by the user
+    pub const MARKED: Flag = Flag::new("marked");
+
+    /// ### new
+    ///
+    /// Create a new flag with the provided name
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    /// ### name
+    ///
+    /// Get flag name
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
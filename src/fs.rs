@@ -0,0 +1,487 @@
+//! # Fs
+//!
+//! This module builds trees from the file system, for file explorers. It is available with the `fs` feature.
+//!
+//! Nodes have the path of the entry, encoded with `ids::encode_path`, as id, so that the ids of file names which
+//! aren't valid UTF-8 don't collide; get back the path with `ids::decode_path` or `FsNodeValue::path`. Directories which haven't been scanned are lazy branches
+//! (see `NodeValue::is_branch_hint`), whose entries can be loaded with `FsTreeBuilder::scan` or `FsTreeBuilder::extend`.
+
+use super::ids::encode_path;
+use super::{Node, NodeValue, Tree};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tuirealm::props::{Style, TextModifiers};
+
+/// ## FsKind
+///
+/// The type of a file system entry
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FsKind {
+    #[default]
+    File,
+    Directory,
+    /// A symlink which isn't followed
+    Symlink,
+}
+
+impl FsKind {
+    /// ### name
+    ///
+    /// Name of the kind, as announced by `TreeView::selected_description`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Directory => "directory",
+            Self::Symlink => "symlink",
+        }
+    }
+}
+
+/// ## SymlinkPolicy
+///
+/// How symlinks are scanned by `FsTreeBuilder`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Symlinks are leaves, rendered with their target (e.g. `lib -> /usr/lib`)
+    #[default]
+    Show,
+    /// Symlinks are scanned as their target; symlinks to an ancestor directory are not followed, to prevent cycles
+    Follow,
+    /// Symlinks are not part of the tree
+    Skip,
+}
+
+/// ## FsNodeValue
+///
+/// Node value of a tree built from the file system: the name of the entry, followed by the target for symlinks.
+/// Directories are rendered bold
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FsNodeValue {
+    name: String,
+    path: PathBuf,
+    kind: FsKind,
+    /// Target of the symlink, if the entry is a symlink
+    target: Option<PathBuf>,
+    /// Rendered label
+    label: String,
+}
+
+impl FsNodeValue {
+    /// ### new
+    ///
+    /// Instantiate a new `FsNodeValue` for the entry at `path`
+    fn new(path: &Path, kind: FsKind, target: Option<PathBuf>) -> Self {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => path.to_string_lossy().into_owned(),
+        };
+        let label = match (kind, target.as_ref()) {
+            (FsKind::Symlink, Some(target)) => format!("{name} -> {}", target.display()),
+            _ => name.clone(),
+        };
+        Self {
+            name,
+            path: path.to_path_buf(),
+            kind,
+            target,
+            label,
+        }
+    }
+
+    /// ### name
+    ///
+    /// Get the file name of the entry
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// ### path
+    ///
+    /// Get the path of the entry
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ### fs_kind
+    ///
+    /// Get the type of the entry. Followed symlinks have the type of their target
+    pub fn fs_kind(&self) -> FsKind {
+        self.kind
+    }
+
+    /// ### target
+    ///
+    /// Get the target of the entry, if it is a symlink
+    pub fn target(&self) -> Option<&Path> {
+        self.target.as_deref()
+    }
+
+    /// ### is_dir
+    ///
+    /// Returns whether the entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.kind == FsKind::Directory
+    }
+}
+
+impl NodeValue for FsNodeValue {
+    fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+        let style = match self.kind {
+            FsKind::Directory => Some(Style::default().add_modifier(TextModifiers::BOLD)),
+            FsKind::Symlink => Some(Style::default().add_modifier(TextModifiers::ITALIC)),
+            FsKind::File => None,
+        };
+        std::iter::once((self.label.as_str(), style))
+    }
+
+    fn is_branch_hint(&self) -> bool {
+        self.is_dir()
+    }
+
+    fn search_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.name.as_str()))
+    }
+
+    fn kind(&self) -> Option<&str> {
+        Some(self.kind.name())
+    }
+}
+
+/// ## FsTreeBuilder
+///
+/// Scans directories into trees of `FsNodeValue`s. Entries are sorted by name, with directories first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsTreeBuilder {
+    depth: usize,
+    show_hidden: bool,
+    dirs_first: bool,
+    symlinks: SymlinkPolicy,
+}
+
+impl Default for FsTreeBuilder {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            show_hidden: false,
+            dirs_first: true,
+            symlinks: SymlinkPolicy::default(),
+        }
+    }
+}
+
+impl FsTreeBuilder {
+    /// ### depth
+    ///
+    /// Set how many levels of directories are scanned. Directories below are lazy branches. Default is 1
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// ### show_hidden
+    ///
+    /// Set whether hidden entries (whose name starts with a dot) are part of the tree. Default is false
+    pub fn show_hidden(mut self, show: bool) -> Self {
+        self.show_hidden = show;
+        self
+    }
+
+    /// ### dirs_first
+    ///
+    /// Set whether directories are sorted before files. Default is true
+    pub fn dirs_first(mut self, dirs_first: bool) -> Self {
+        self.dirs_first = dirs_first;
+        self
+    }
+
+    /// ### symlinks
+    ///
+    /// Set how symlinks are scanned. Default is `SymlinkPolicy::Show`
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// ### build
+    ///
+    /// Build the tree of the directory at `path`. Fails if `path` can't be read;
+    /// directories below which can't be read are left empty
+    pub fn build<P: AsRef<Path>>(&self, path: P) -> io::Result<Tree<FsNodeValue>> {
+        let path = path.as_ref();
+        let mut root = Node::new(
+            encode_path(path),
+            FsNodeValue::new(path, FsKind::Directory, None),
+        );
+        self.scan_dir(path, self.depth, &mut ancestors(path))?
+            .into_iter()
+            .for_each(|x| root.add_child(x));
+        Ok(Tree::new(root))
+    }
+
+    /// ### scan
+    ///
+    /// Scan the entries of the directory at `path`, e.g. to load them from a `ChildLoader`
+    pub fn scan<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<Node<FsNodeValue>>> {
+        let path = path.as_ref();
+        self.scan_dir(path, self.depth, &mut ancestors(path))
+    }
+
+    /// ### extend
+    ///
+    /// Rescan the directory at `path` in `tree`, replacing the children of its node.
+    /// Returns whether the node exists
+    pub fn extend<P: AsRef<Path>>(
+        &self,
+        tree: &mut Tree<FsNodeValue>,
+        path: P,
+    ) -> io::Result<bool> {
+        let path = path.as_ref();
+        let Some(node) = tree.root_mut().query_mut(&encode_path(path)) else {
+            return Ok(false);
+        };
+        let children = self.scan(path)?;
+        node.clear();
+        children.into_iter().for_each(|x| node.add_child(x));
+        Ok(true)
+    }
+
+    /// ### scan_dir
+    ///
+    /// Scan the entries of the directory at `path`, and their descendants up to `depth` levels.
+    /// `ancestors` are the canonical paths of the directories being scanned, to prevent following cycles
+    fn scan_dir(
+        &self,
+        path: &Path,
+        depth: usize,
+        ancestors: &mut Vec<PathBuf>,
+    ) -> io::Result<Vec<Node<FsNodeValue>>> {
+        if depth == 0 {
+            return Ok(Vec::new());
+        }
+        let mut entries: Vec<FsNodeValue> = fs::read_dir(path)?
+            .flatten()
+            .filter_map(|x| self.entry(&x.path(), ancestors))
+            .filter(|x| self.show_hidden || !x.name.starts_with('.'))
+            .collect();
+        entries.sort_by(|a, b| self.compare(a, b));
+        Ok(entries
+            .into_iter()
+            .map(|value| {
+                let mut node = Node::new(encode_path(&value.path), value);
+                if node.value().is_dir() {
+                    let path = node.value().path.clone();
+                    ancestors.push(fs::canonicalize(&path).unwrap_or_else(|_| path.clone()));
+                    self.scan_dir(&path, depth - 1, ancestors)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .for_each(|x| node.add_child(x));
+                    ancestors.pop();
+                }
+                node
+            })
+            .collect())
+    }
+
+    /// ### entry
+    ///
+    /// Make the value of the entry at `path`, if it is part of the tree according to the symlink policy
+    fn entry(&self, path: &Path, ancestors: &[PathBuf]) -> Option<FsNodeValue> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        if !metadata.file_type().is_symlink() {
+            let kind = match metadata.is_dir() {
+                true => FsKind::Directory,
+                false => FsKind::File,
+            };
+            return Some(FsNodeValue::new(path, kind, None));
+        }
+        let target = fs::read_link(path).ok();
+        match self.symlinks {
+            SymlinkPolicy::Skip => None,
+            SymlinkPolicy::Show => Some(FsNodeValue::new(path, FsKind::Symlink, target)),
+            SymlinkPolicy::Follow => {
+                let kind = match fs::canonicalize(path) {
+                    Ok(real) if real.is_dir() && !ancestors.contains(&real) => FsKind::Directory,
+                    Ok(real) if real.is_dir() => FsKind::Symlink,
+                    Ok(_) => FsKind::File,
+                    // Dangling symlink
+                    Err(_) => FsKind::Symlink,
+                };
+                Some(FsNodeValue::new(path, kind, target))
+            }
+        }
+    }
+
+    /// ### compare
+    ///
+    /// Compare two entries to sort them
+    fn compare(&self, a: &FsNodeValue, b: &FsNodeValue) -> Ordering {
+        let dirs = match self.dirs_first {
+            true => b.is_dir().cmp(&a.is_dir()),
+            false => Ordering::Equal,
+        };
+        dirs.then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            .then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+/// ### ancestors
+///
+/// Canonical paths of `path` and its ancestors
+fn ancestors(path: &Path) -> Vec<PathBuf> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut ancestors: Vec<PathBuf> = path.ancestors().map(Path::to_path_buf).collect();
+    ancestors.reverse();
+    ancestors
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    /// Make an empty temporary directory named `name`
+    fn temp_dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("tui-realm-treeview-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn child_names(node: &Node<FsNodeValue>) -> Vec<&str> {
+        node.children().iter().map(|x| x.value().name()).collect()
+    }
+
+    #[test]
+    fn should_build_tree_from_directory() {
+        let root = temp_dir("build");
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::write(root.join("build.rs"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let tree = FsTreeBuilder::default().build(&root).unwrap();
+        let node = tree.root();
+        assert_eq!(node.id(), &encode_path(&root));
+        assert_eq!(child_names(node), vec!["src", "build.rs", "Cargo.toml"]);
+        // Directories below the depth are lazy branches
+        let src = &node.children()[0];
+        assert_eq!(src.id(), &encode_path(&root.join("src")));
+        assert!(src.is_leaf());
+        assert!(src.value().is_branch_hint());
+        assert_eq!(src.value().kind(), Some("directory"));
+        // Deeper, with hidden entries, without dirs first
+        let tree = FsTreeBuilder::default()
+            .depth(3)
+            .show_hidden(true)
+            .dirs_first(false)
+            .build(&root)
+            .unwrap();
+        assert_eq!(
+            child_names(tree.root()),
+            vec![".git", "build.rs", "Cargo.toml", "src"]
+        );
+        let src = &tree.root().children()[3];
+        assert_eq!(child_names(src), vec!["bin", "lib.rs"]);
+        assert!(FsTreeBuilder::default().build(root.join("none")).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn should_extend_tree_from_directory() {
+        let root = temp_dir("extend");
+        fs::create_dir_all(root.join("src")).unwrap();
+        let builder = FsTreeBuilder::default();
+        let mut tree = builder.build(&root).unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        assert!(builder.extend(&mut tree, root.join("src")).unwrap());
+        assert_eq!(child_names(&tree.root().children()[0]), vec!["main.rs"]);
+        assert!(!builder.extend(&mut tree, root.join("none")).unwrap());
+        assert_eq!(
+            builder
+                .scan(root.join("src"))
+                .unwrap()
+                .iter()
+                .map(|x| x.value().name())
+                .collect::<Vec<_>>(),
+            vec!["main.rs"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_not_collide_ids_of_non_utf8_names() {
+        use crate::ids::decode_path;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = temp_dir("non-utf8");
+        let ff = root.join(OsStr::from_bytes(b"\xff"));
+        let fe = root.join(OsStr::from_bytes(b"\xfe"));
+        // Some file systems (e.g. on macOS) reject non UTF-8 names
+        if fs::write(&ff, "").is_err() || fs::write(&fe, "").is_err() {
+            fs::remove_dir_all(&root).unwrap();
+            return;
+        }
+        let builder = FsTreeBuilder::default();
+        let mut tree = builder.build(&root).unwrap();
+        let ids: Vec<&str> = tree.root().iter().map(|x| x.id().as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+        for path in [&ff, &fe] {
+            let node = tree.root().query(&encode_path(path)).unwrap();
+            assert_eq!(node.value().path(), path.as_path());
+            assert_eq!(decode_path(node.id()).unwrap(), *path);
+        }
+        assert!(builder.extend(&mut tree, &root).unwrap());
+        assert_eq!(tree.root().children().len(), 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_handle_symlinks() {
+        let root = temp_dir("symlinks");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir/file"), "").unwrap();
+        std::os::unix::fs::symlink(root.join("dir"), root.join("link")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("dir/up")).unwrap();
+        let tree = FsTreeBuilder::default().depth(2).build(&root).unwrap();
+        let link = &tree.root().children()[1];
+        assert_eq!(link.value().fs_kind(), FsKind::Symlink);
+        assert!(link
+            .value()
+            .render_parts_iter()
+            .next()
+            .unwrap()
+            .0
+            .contains(" -> "));
+        let tree = FsTreeBuilder::default()
+            .depth(4)
+            .symlinks(SymlinkPolicy::Follow)
+            .build(&root)
+            .unwrap();
+        assert_eq!(child_names(tree.root()), vec!["dir", "link"]);
+        // Cycles are not followed
+        let up = &tree.root().children()[0].children()[1];
+        assert_eq!(up.value().name(), "up");
+        assert_eq!(up.value().fs_kind(), FsKind::Symlink);
+        let link = &tree.root().children()[1];
+        assert_eq!(link.value().fs_kind(), FsKind::Directory);
+        assert_eq!(child_names(link), vec!["file", "up"]);
+        let tree = FsTreeBuilder::default()
+            .symlinks(SymlinkPolicy::Skip)
+            .build(&root)
+            .unwrap();
+        assert_eq!(child_names(tree.root()), vec!["dir"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
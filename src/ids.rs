@@ -0,0 +1,175 @@
+//! # Ids
+//!
+//! This module provides helpers to build node ids from paths and back.
+//!
+//! Using `Path::to_string_lossy` as id is subtly broken: different non UTF-8 file names get the same id and
+//! the original path can't be recovered from it. `encode_path` instead escapes as `%XX` each byte which isn't
+//! valid UTF-8, control characters (e.g. newlines) and `%` itself, so that each path gets a distinct printable id,
+//! which `decode_path` turns back into the original path.
+//!
+//! When nodes have no natural unique id (e.g. song titles), `AutoId::push_child_auto_id` generates one.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::{Node, Tree};
+
+/// ## AutoId
+///
+/// Add children to a tree with a generated id, for datasets whose labels are not unique
+pub trait AutoId<V> {
+    /// ### push_child_auto_id
+    ///
+    /// Add a child with `value` to the node with id `parent` and return the id generated for it.
+    /// The id is the parent id followed by the ordinal of the child (e.g. `album/3`), skipping the ordinals
+    /// which would give an id already in the tree; so the same insertions always give the same ids.
+    /// Returns `None` if `parent` doesn't exist
+    fn push_child_auto_id(&mut self, parent: &str, value: V) -> Option<String>;
+}
+
+impl<V> AutoId<V> for Tree<V> {
+    fn push_child_auto_id(&mut self, parent: &str, value: V) -> Option<String> {
+        let mut ordinal = self.root().query(&parent.to_string())?.children().len();
+        let id = loop {
+            let id = format!("{parent}/{ordinal}");
+            if self.root().query(&id).is_none() {
+                break id;
+            }
+            ordinal += 1;
+        };
+        self.root_mut()
+            .query_mut(&parent.to_string())?
+            .add_child(Node::new(id.clone(), value));
+        Some(id)
+    }
+}
+
+/// ### encode_path
+///
+/// Build a node id from `path`. Ids of different paths are always different
+pub fn encode_path(path: &Path) -> String {
+    let mut id = String::new();
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c == '%' || c.is_control() {
+                let mut buf = [0; 4];
+                c.encode_utf8(&mut buf)
+                    .bytes()
+                    .for_each(|x| push_escaped(&mut id, x));
+            } else {
+                id.push(c);
+            }
+        }
+        chunk
+            .invalid()
+            .iter()
+            .for_each(|x| push_escaped(&mut id, *x));
+    }
+    id
+}
+
+/// ### decode_path
+///
+/// Get back the path from an id built with `encode_path`.
+/// Returns `None` if `id` is not a valid encoded path
+pub fn decode_path(id: &str) -> Option<PathBuf> {
+    let mut bytes = Vec::with_capacity(id.len());
+    let mut iter = id.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let high = hex_value(iter.next()?)?;
+                let low = hex_value(iter.next()?)?;
+                bytes.push(high << 4 | low);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    os_string_from_bytes(bytes).map(PathBuf::from)
+}
+
+fn push_escaped(id: &mut String, byte: u8) {
+    id.push_str(&format!("%{byte:02X}"));
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|x| x as u8)
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Some(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    // Only UTF-8 can be safely converted back on these platforms
+    String::from_utf8(bytes).ok().map(OsString::from)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_encode_and_decode_paths() {
+        for (path, id) in [
+            ("/home/user/notes.txt", "/home/user/notes.txt"),
+            ("/tmp/100%", "/tmp/100%25"),
+            ("/tmp/two\nlines", "/tmp/two%0Alines"),
+            ("/tmp/ü 🦄", "/tmp/ü 🦄"),
+        ] {
+            assert_eq!(encode_path(Path::new(path)), id);
+            assert_eq!(decode_path(id).unwrap(), PathBuf::from(path));
+        }
+        assert!(decode_path("/tmp/%4").is_none());
+        assert!(decode_path("/tmp/%zz").is_none());
+    }
+
+    #[test]
+    fn should_push_children_with_auto_id() {
+        let mut tree: Tree<String> = Tree::new(Node::new(String::from("album"), String::new()));
+        let a = tree
+            .push_child_auto_id("album", String::from("Intro"))
+            .unwrap();
+        let b = tree
+            .push_child_auto_id("album", String::from("Intro"))
+            .unwrap();
+        assert_eq!(a, "album/0");
+        assert_eq!(b, "album/1");
+        assert_eq!(
+            tree.push_child_auto_id("album/1", String::from("Intro"))
+                .unwrap(),
+            "album/1/0"
+        );
+        // Ordinals already taken are skipped
+        tree.root_mut()
+            .add_child(Node::new(String::from("album/3"), String::new()));
+        assert_eq!(
+            tree.push_child_auto_id("album", String::from("Outro"))
+                .unwrap(),
+            "album/4"
+        );
+        assert!(tree.push_child_auto_id("none", String::new()).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_not_collide_on_non_utf8_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let a = Path::new(OsStr::from_bytes(b"/tmp/\xff"));
+        let b = Path::new(OsStr::from_bytes(b"/tmp/\xfe"));
+        // Lossy conversion gives the same string
+        assert_eq!(a.to_string_lossy(), b.to_string_lossy());
+        assert_eq!(encode_path(a), "/tmp/%FF");
+        assert_eq!(encode_path(b), "/tmp/%FE");
+        assert_eq!(decode_path(&encode_path(a)).unwrap(), a);
+    }
+}
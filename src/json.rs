@@ -0,0 +1,283 @@
+//! # Json
+//!
+//! This module builds browsable trees from `serde_json::Value`s, for config or API explorers
+
+use super::{Node, NodeValue, Tree};
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+use tuirealm::props::{Color, Style, TextModifiers};
+
+/// ## JsonIdStrategy
+///
+/// How ids are given to the nodes of a tree built from a JSON value
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JsonIdStrategy {
+    /// JSON pointers (RFC 6901), e.g. `/servers/0/name`. The root id is an empty string
+    #[default]
+    Pointer,
+    /// JSONPath-like paths, e.g. `$.servers[0].name`. Keys which aren't identifiers are quoted (e.g. `$["a.b"]`)
+    Path,
+}
+
+impl JsonIdStrategy {
+    /// ### root_id
+    ///
+    /// Id of the root node
+    fn root_id(self) -> String {
+        match self {
+            Self::Pointer => String::new(),
+            Self::Path => String::from("$"),
+        }
+    }
+
+    /// ### key_id
+    ///
+    /// Id of the member `key` of the object with id `parent`
+    fn key_id(self, parent: &str, key: &str) -> String {
+        match self {
+            Self::Pointer => format!("{parent}/{}", key.replace('~', "~0").replace('/', "~1")),
+            Self::Path if is_identifier(key) => format!("{parent}.{key}"),
+            Self::Path => format!("{parent}[{}]", Value::String(key.to_string())),
+        }
+    }
+
+    /// ### index_id
+    ///
+    /// Id of the item at `index` of the array with id `parent`
+    fn index_id(self, parent: &str, index: usize) -> String {
+        match self {
+            Self::Pointer => format!("{parent}/{index}"),
+            Self::Path => format!("{parent}[{index}]"),
+        }
+    }
+}
+
+/// ## JsonKind
+///
+/// The type of a JSON value
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JsonKind {
+    #[default]
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonKind {
+    /// ### name
+    ///
+    /// Name of the kind, as announced by `TreeView::selected_description`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool => "boolean",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        }
+    }
+
+    /// ### style
+    ///
+    /// Style of the values of this kind
+    fn style(self) -> Style {
+        match self {
+            Self::Null => Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(TextModifiers::ITALIC),
+            Self::Bool => Style::default().fg(Color::Yellow),
+            Self::Number => Style::default().fg(Color::Cyan),
+            Self::String => Style::default().fg(Color::Green),
+            Self::Array | Self::Object => Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+/// ## JsonNodeValue
+///
+/// Node value of a tree built from a JSON value: the member key (or the array index) followed by the value,
+/// styled by its type. Arrays and objects render the amount of their items instead (e.g. `[3]` or `{2}`)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JsonNodeValue {
+    key: String,
+    value: String,
+    kind: JsonKind,
+}
+
+impl JsonNodeValue {
+    /// ### new
+    ///
+    /// Instantiate a new `JsonNodeValue` for `value` with `key`; its children are not rendered
+    pub fn new<S: AsRef<str>>(key: S, value: &Value) -> Self {
+        let (value, kind) = match value {
+            Value::Null => (String::from("null"), JsonKind::Null),
+            Value::Bool(x) => (x.to_string(), JsonKind::Bool),
+            Value::Number(x) => (x.to_string(), JsonKind::Number),
+            Value::String(_) => (value.to_string(), JsonKind::String),
+            Value::Array(x) => (format!("[{}]", x.len()), JsonKind::Array),
+            Value::Object(x) => (format!("{{{}}}", x.len()), JsonKind::Object),
+        };
+        Self {
+            key: key.as_ref().to_string(),
+            value,
+            kind,
+        }
+    }
+
+    /// ### key
+    ///
+    /// Get the member key or the array index; empty for the root
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// ### value
+    ///
+    /// Get the rendered value
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// ### json_kind
+    ///
+    /// Get the type of the value
+    pub fn json_kind(&self) -> JsonKind {
+        self.kind
+    }
+}
+
+impl NodeValue for JsonNodeValue {
+    fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+        let key = match self.key.is_empty() {
+            true => None,
+            false => Some((self.key.as_str(), None)),
+        };
+        key.into_iter().chain(std::iter::once((
+            self.value.as_str(),
+            Some(self.kind.style()),
+        )))
+    }
+
+    fn key_parts(&self) -> Option<usize> {
+        match self.key.is_empty() {
+            true => None,
+            false => Some(1),
+        }
+    }
+
+    fn search_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} {}", self.key, self.value)))
+    }
+
+    fn kind(&self) -> Option<&str> {
+        Some(self.kind.name())
+    }
+}
+
+/// ## FromJson
+///
+/// Build a tree from a JSON value
+pub trait FromJson: Sized {
+    /// ### from_json_value
+    ///
+    /// Build a tree from `value`, where each member of objects and each item of arrays is a child node,
+    /// with ids given by `ids`
+    fn from_json_value(value: &Value, ids: JsonIdStrategy) -> Self;
+}
+
+impl FromJson for Tree<JsonNodeValue> {
+    fn from_json_value(value: &Value, ids: JsonIdStrategy) -> Self {
+        Tree::new(json_node(ids.root_id(), "", value, ids))
+    }
+}
+
+/// ### json_node
+///
+/// Build the node with `id` for `value` and its descendants
+fn json_node(id: String, key: &str, value: &Value, ids: JsonIdStrategy) -> Node<JsonNodeValue> {
+    let mut node = Node::new(id, JsonNodeValue::new(key, value));
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let id = ids.index_id(node.id(), index);
+                node.add_child(json_node(id, &index.to_string(), item, ids));
+            }
+        }
+        Value::Object(members) => {
+            for (key, member) in members {
+                let id = ids.key_id(node.id(), key);
+                node.add_child(json_node(id, key, member, ids));
+            }
+        }
+        _ => {}
+    }
+    node
+}
+
+/// ### is_identifier
+///
+/// Returns whether `key` can be written unquoted in a path
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn should_build_tree_from_json_value() {
+        let value = json!({
+            "servers": [{ "name": "alpha", "port": 8080 }],
+            "a/b~": null,
+            "debug": true,
+        });
+        let tree = Tree::from_json_value(&value, JsonIdStrategy::Pointer);
+        let root = tree.root();
+        assert_eq!(root.id(), "");
+        assert_eq!(root.value().value(), "{3}");
+        assert_eq!(root.value().key_parts(), None);
+        let port = root.query(&String::from("/servers/0/port")).unwrap();
+        assert_eq!(port.value().key(), "port");
+        assert_eq!(port.value().value(), "8080");
+        assert_eq!(port.value().json_kind(), JsonKind::Number);
+        assert_eq!(
+            port.value().render_parts_iter().collect::<Vec<_>>(),
+            vec![
+                ("port", None),
+                ("8080", Some(Style::default().fg(Color::Cyan)))
+            ]
+        );
+        let name = root.query(&String::from("/servers/0/name")).unwrap();
+        assert_eq!(name.value().value(), "\"alpha\"");
+        assert_eq!(name.value().kind(), Some("string"));
+        assert!(root.query(&String::from("/a~1b~0")).is_some());
+        let servers = root.query(&String::from("/servers")).unwrap();
+        assert_eq!(servers.value().value(), "[1]");
+        assert_eq!(servers.children()[0].value().key(), "0");
+    }
+
+    #[test]
+    fn should_build_path_ids_from_json_value() {
+        let value = json!({ "servers": [{ "name": "alpha" }], "a.b": 1 });
+        let tree = Tree::from_json_value(&value, JsonIdStrategy::Path);
+        let root = tree.root();
+        assert_eq!(root.id(), "$");
+        assert!(root.query(&String::from("$.servers[0].name")).is_some());
+        assert!(root.query(&String::from("$[\"a.b\"]")).is_some());
+    }
+}
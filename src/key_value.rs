@@ -0,0 +1,115 @@
+//! # Key value
+//!
+//! This module exposes a node value made of a key and a value, for inspectors of config files, JSON and so on
+
+use super::NodeValue;
+
+use std::borrow::Cow;
+
+use tuirealm::props::Style;
+
+/// ## KeyValueNode
+///
+/// A node value made of a key and a value. The key is rendered left-aligned, while values are aligned to the
+/// same column across the visible siblings, one space after the widest key.
+/// Nodes with an empty value (e.g. tables or objects) only render their key
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KeyValueNode {
+    key: String,
+    value: String,
+    /// Style for the key. If `None`, the row style is used
+    key_style: Option<Style>,
+    /// Style for the value. If `None`, the row style is used
+    value_style: Option<Style>,
+}
+
+impl KeyValueNode {
+    /// ### new
+    ///
+    /// Instantiate a new `KeyValueNode`
+    pub fn new<K: AsRef<str>, S: AsRef<str>>(key: K, value: S) -> Self {
+        Self {
+            key: key.as_ref().to_string(),
+            value: value.as_ref().to_string(),
+            key_style: None,
+            value_style: None,
+        }
+    }
+
+    /// ### key_style
+    ///
+    /// Set style for the key
+    pub fn key_style(mut self, style: Style) -> Self {
+        self.key_style = Some(style);
+        self
+    }
+
+    /// ### value_style
+    ///
+    /// Set style for the value
+    pub fn value_style(mut self, style: Style) -> Self {
+        self.value_style = Some(style);
+        self
+    }
+
+    /// ### key
+    ///
+    /// Get node key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// ### value
+    ///
+    /// Get node value
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl NodeValue for KeyValueNode {
+    fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+        [
+            (self.key.as_str(), self.key_style),
+            (self.value.as_str(), self.value_style),
+        ]
+        .into_iter()
+    }
+
+    fn key_parts(&self) -> Option<usize> {
+        match self.value.is_empty() {
+            true => None,
+            false => Some(1),
+        }
+    }
+
+    fn search_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} {}", self.key, self.value)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use tuirealm::props::Color;
+
+    #[test]
+    fn should_render_key_value_node() {
+        let node = KeyValueNode::new("port", "8080").value_style(Style::default().fg(Color::Cyan));
+        assert_eq!(node.key(), "port");
+        assert_eq!(node.value(), "8080");
+        assert_eq!(
+            node.render_parts_iter().collect::<Vec<_>>(),
+            vec![
+                ("port", None),
+                ("8080", Some(Style::default().fg(Color::Cyan)))
+            ]
+        );
+        assert_eq!(node.key_parts(), Some(1));
+        // Nodes without value have no key column
+        assert_eq!(KeyValueNode::new("server", "").key_parts(), None);
+    }
+}
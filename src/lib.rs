@@ -18,36 +18,129 @@
 //! tui-realm-treeview = { version = "2", default-features = false, features = [ "termion" ] }
 //! ```
 //!
+//! Enable the `fixtures` feature to generate large deterministic trees with `fixtures::generate`, e.g. for tests and
+//! benchmarks.
+//!
+//! Enable the `unstable-widget-ref` feature to render `TreeWidget` by reference, with ratatui's `WidgetRef` and
+//! `StatefulWidgetRef`, so that the widget can be built once and rendered on each frame.
+//!
+//! Enable the `watch` feature to get the changes of the selected node from other threads or tasks through a
+//! `tokio::sync::watch` channel with `TreeView::watch_selection` (e.g. to prefetch metadata or generate previews in background).
+//!
+//! Enable the `serde` feature to serialize the `PersistedState` of the tree view (open nodes, selected node and bookmarks), to restore it between sessions.
+//!
+//! Enable the `regex` feature to search and highlight nodes with regular expressions, with `RegexMatcher`.
+//!
+//! Enable the `strict` feature to panic early on invariant violations in debug builds: duplicate node ids or a stale
+//! state after `tree_mut`. Release builds are not affected.
+//!
+//! Enable the `debug` feature to log state transitions (select, open, close, tree changed) through the
+//! [log](https://docs.rs/log) crate, with target `tui_realm_treeview`.
+//!
+//! Enable the `json` feature to build a browsable tree from a `serde_json::Value` with `Tree::from_json_value`
+//! (see `FromJson`): nodes get JSON pointers as ids and a `JsonNodeValue`, styled by the type of the value.
+//!
+//! Enable the `fs` feature to scan directories into trees with `FsTreeBuilder`, with configurable depth, hidden
+//! entries, sorting and symlink handling. Nodes have their path, encoded with `ids::encode_path`, as id; directories
+//! below the depth are lazy branches, which can be loaded with `FsTreeBuilder::scan` (e.g. from a `ChildLoader`) or
+//! `FsTreeBuilder::extend`.
+//!
 //! ## Component API
 //!
 //! **Commands**:
 //!
-//! | Cmd                       | Result           | Behaviour                                            |
-//! |---------------------------|------------------|------------------------------------------------------|
-//! | `Custom($TREE_CMD_CLOSE)` | `None`           | Close selected node                                  |
-//! | `Custom($TREE_CMD_OPEN)`  | `None`           | Open selected node                                   |
-//! | `GoTo(Begin)`             | `Changed | None` | Move cursor to the top of the current tree node      |
-//! | `GoTo(End)`               | `Changed | None` | Move cursor to the bottom of the current tree node   |
-//! | `Move(Down)`              | `Changed | None` | Go to next element                                   |
-//! | `Move(Up)`                | `Changed | None` | Go to previous element                               |
-//! | `Scroll(Down)`            | `Changed | None` | Move cursor down by defined max steps or end of node |
-//! | `Scroll(Up)`              | `Changed | None` | Move cursor up by defined max steps or begin of node |
-//! | `Submit`                  | `Submit`         | Just returns submit result with current state        |
+//! | Cmd                                    | Result                        | Behaviour                                                                                                                                                                            |
+//! |----------------------------------------|-------------------------------|--------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------|
+//! | `Custom($TREE_CMD_CLOSE)`              | `Changed | None`              | Close selected node; if `TREE_CLOSE_TO_PARENT` is set and the node is a leaf or is closed, select its parent                                                                         |
+//! | `Custom($TREE_CMD_CLOSE_ALL)`          | `None`                        | Close selected node and all of its descendants, even if the node is closed                                                                                                           |
+//! | `Custom($TREE_CMD_CLOSE_PARENT)`       | `Changed | None`              | Close the nearest open ancestor of the selected node (i.e. its parent) and select it                                                                                                 |
+//! | `Custom($TREE_CMD_GOTO_PARENT)`        | `Changed | None`              | Select the parent of the selected node, whatever its position among its siblings; the parent is not closed                                                                           |
+//! | `Custom($TREE_CMD_COMMIT)`             | `Changed | None`              | Promote the cursor to the committed selection, if `TREE_SOFT_SELECTION` is set                                                                                                       |
+//! | `Custom($TREE_CMD_CLEAR_FLAGS)`        | `None`                        | Remove all flags from all nodes                                                                                                                                                      |
+//! | `Custom($TREE_CMD_INDENT_INC)`         | `None`                        | Increment indent size by 1                                                                                                                                                           |
+//! | `Custom($TREE_CMD_INDENT_DEC)`         | `None`                        | Decrement indent size by 1                                                                                                                                                           |
+//! | `Custom($TREE_CMD_NEXT_BOOKMARK)`      | `Changed | None`              | Select the next bookmarked node, in the order nodes appear in the tree, opening its ancestors; wraps around                                                                          |
+//! | `Custom($TREE_CMD_PREV_BOOKMARK)`      | `Changed | None`              | Select the previous bookmarked node, opening its ancestors; wraps around                                                                                                             |
+//! | `Custom($TREE_CMD_SEARCH)`             | `Changed | None`              | Search the nodes matching `TREE_SEARCH_QUERY` (see `search`) and select the first match from the selected node                                                                       |
+//! | `Custom($TREE_CMD_SEARCH_NEXT)`        | `Changed | None`              | Select the next match of the last search; wraps around                                                                                                                               |
+//! | `Custom($TREE_CMD_SEARCH_PREV)`        | `Changed | None`              | Select the previous match of the last search; wraps around                                                                                                                           |
+//! | `Custom($TREE_CMD_SELECT_ALL_VISIBLE)` | `None`                        | Set `Flag::MARKED` on all the visible nodes                                                                                                                                          |
+//! | `Custom($TREE_CMD_SELECT_NONE)`        | `None`                        | Remove `Flag::MARKED` from all the nodes                                                                                                                                             |
+//! | `Custom($TREE_CMD_TOGGLE_MARK)`        | `None`                        | Set `Flag::MARKED` on the selected node, or remove it if it is set                                                                                                                   |
+//! | `Custom($TREE_CMD_MARK_ALL_SIBLINGS)`  | `None`                        | Set `Flag::MARKED` on the selected node and on all of its siblings                                                                                                                   |
+//! | `Custom($TREE_CMD_UNDO)`               | `Changed | None`              | Restore the selected node and the open nodes before the last change, if `undo_depth` is set                                                                                          |
+//! | `Custom($TREE_CMD_REDO)`               | `Changed | None`              | Restore the state changed by the last `TREE_CMD_UNDO`                                                                                                                                |
+//! | `Custom($TREE_CMD_OPEN)`               | `Custom | None`               | Open selected node; if it is an empty branch, its children are loaded by the `child_loader`, if set, or `Custom($TREE_CMD_OPEN, state)` is returned so that they can be loaded       |
+//! | `Custom($TREE_CMD_OPEN_ALL)`           | `None`                        | Open selected node and all of its descendants which are branches, reloading the unloaded ones                                                                                        |
+//! | `Custom($TREE_CMD_REORDER)`            | `None`                        | Start moving the selected node among its siblings: `Move` moves the landing position, shown with `TREE_DROP_STYLE`, and `Submit` commits the move                                    |
+//! | `Custom($TREE_CMD_SORT_COLUMN[n])`     | `None`                        | Sort siblings by column `n` (see `TREE_COLUMNS`), cycling ascending, descending and tree order; the tree structure is kept                                                           |
+//! | `Custom($TREE_CMD_VIEW_CENTER)`        | `None`                        | Scroll the view to put the selected node in the middle                                                                                                                               |
+//! | `Custom($TREE_CMD_VIEW_TOP)`           | `None`                        | Scroll the view to put the selected node on top                                                                                                                                      |
+//! | `Custom($TREE_CMD_VIEW_BOTTOM)`        | `None`                        | Scroll the view to put the selected node at the bottom                                                                                                                               |
+//! | `Cancel`                               | `Changed | None`              | Abort the move started with `TREE_CMD_REORDER`                                                                                                                                       |
+//! | `GoTo(Begin)`                          | `Changed | None`              | Move cursor to the top of the current tree node                                                                                                                                      |
+//! | `GoTo(End)`                            | `Changed | None`              | Move cursor to the bottom of the current tree node                                                                                                                                   |
+//! | `Move(Down)`                           | `Changed | None`              | Go to next element                                                                                                                                                                   |
+//! | `Move(Up)`                             | `Changed | None`              | Go to previous element                                                                                                                                                               |
+//! | `Scroll(Down)`                         | `Changed | None`              | Move cursor down by defined max steps or end of node                                                                                                                                 |
+//! | `Scroll(Up)`                           | `Changed | None`              | Move cursor up by defined max steps or begin of node                                                                                                                                 |
+//! | `Submit`                               | `Submit \| Changed \| Custom` | Returns submit result with current state. On a "more" row (see `TREE_MAX_CHILDREN`), shows more children and returns `Changed`. On a lazy branch, behaves according to `lazy_submit` |
+//! | `Tick`                                 | `Changed | None`              | Open the selected node if `TREE_EXPAND_ON_SELECT` ticks have elapsed; scroll the view while dragging on its edges; ease the view with `TREE_SMOOTH_SCROLL`                           |
+//!
+//! Custom commands are also available as the `TreeCmd` enum, which can be converted into `Cmd` (e.g. `TreeCmd::Open.into()`).
 //!
-//! **State**: the state returned is a `One(String)` containing the id of the selected node. If no node is selected `None` is returned.
+//! **State**: the state returned is a `One(String)` containing the id of the selected node (the committed one if `TREE_SOFT_SELECTION` is set). If no node is selected `None` is returned. In multi-select mode (see `TREE_MULTI_SELECT`), a `Vec` of the ids of the marked nodes is returned instead, if any node is marked. If `TREE_STATE_MAP` is set, a `Map` of the selected node and of the open nodes is returned instead.
 //!
 //! **Properties**:
 //!
 //! - `Background(Color)`: background color. The background color will be used as background for unselected entry, but will be used as foreground for the selected entry when focus is true
 //! - `Borders(Borders)`: set borders properties for component
-//! - `Custom($TREE_IDENT_SIZE, Size)`: Set space to render for each each depth level
+//! - `Custom($TREE_ARROW_FIRST, Flag)`: If true, the open/closed arrow is rendered before the label instead of after it. Leaves get a blank column of the same width, so labels stay aligned
+//! - `Custom($TREE_ASCII_ONLY, Flag)`: If true, arrows and symbols are rendered with ASCII characters only (`v`, `>`, `<`), for terminals which can't render unicode (e.g. serial consoles or CI logs). Default is false
+//! - `Custom($TREE_CLOSE_TO_PARENT, Flag)`: If true, `TREE_CMD_CLOSE` performed on a leaf or on an already closed node moves the selection to its parent
+//! - `Custom($TREE_COLUMNS, Payload(Linked(Tup2(Str, U16))))`: If set, the tree is rendered as a table: each entry is the title and the width of a column after the tree column, whose cells are provided by `NodeValue::cell`. Titles are rendered in a header row, with an indicator on the column sorted with `TREE_CMD_SORT_COLUMN`. Set it with `columns`
+//! - `Custom($TREE_DROP_STYLE, Style)`: Style patched on the row above the position where the node moved with `TREE_CMD_REORDER` will land (i.e. an insertion line below it). On `Submit` the move is committed and `Custom($TREE_CMD_REORDER, id)` is returned, so that the new order can be saved. Default is underlined
+//! - `Custom($TREE_ELLIPSIS, String)`: Set the string (e.g. `…`) ending the rows which don't fit the tree area, instead of cutting them at the edge; the arrow after the label is kept visible at the end of the row
+//! - `Custom($TREE_EXPAND_ON_SELECT, Length)`: If set, closed branches are opened once they have been selected for the provided amount of ticks (`Cmd::Tick`). If 0, they are opened as soon as they get selected
+//! - `Custom($TREE_FILL_INNER, Flag)`: If true, the background and foreground colors are painted only inside the borders, so that borders and title keep their own style
+//! - `Custom($TREE_FOOTER, String)`: Set a line to render at the bottom of the tree area, inside the block (e.g. key hints)
+//! - `Custom($TREE_GUIDES, Flag)`: If true, tree guide lines (`│`, `├─`, `└─`) connecting each node to its parent and to its next siblings are rendered instead of plain indentation (with ASCII characters if `TREE_ASCII_ONLY` is set). Default is false
+//! - `Custom($TREE_GUIDES_STYLE, Style)`: Style patched on the guide lines, if `TREE_GUIDES` is set
+//! - `Custom($TREE_ICON_STYLE, Style)`: Style patched on the icons of nodes (see `NodeValue::icon`)
+//! - `Custom($TREE_INDENT_SIZE, Size)`: Set space to render for each each depth level. It can be changed at runtime, also with `TREE_CMD_INDENT_INC` and `TREE_CMD_INDENT_DEC`. It is ignored if the indent size is computed from the depth and the width with `auto_indent(min, max)`
 //! - `Custom($TREE_INITIAL_NODE, String)`: Select initial node in the tree. This option has priority over `keep_state`
+//! - `Custom($TREE_INITIAL_OPEN, Payload(Vec(Str)))`: Ids of the nodes to open (with their ancestors) at the first render, against whatever tree is set by then (e.g. to restore the layout of the last session). Ids which don't exist in the tree are ignored
+//! - `Custom($TREE_MATCH_QUERY, String)`: If set, the occurrences of this text (case-insensitive, as for `matching_ids`) in labels are highlighted, so that users can see why nodes matched a filter or search. Set it to an empty string to disable it
+//! - `Custom($TREE_MATCH_STYLE, Style)`: Style patched on the occurrences of `TREE_MATCH_QUERY`. Default is bold and underlined
+//! - `Custom($TREE_MARK_STYLE, Style)`: Style patched on the symbol and on the label of marked nodes, in multi-select mode. Default is bold
+//! - `Custom($TREE_MARK_SYMBOL, String)`: Symbol rendered before the label of marked nodes, in multi-select mode; unmarked nodes get a blank of the same width. Default is "✓ " ("* " with `TREE_ASCII_ONLY`)
+//! - `Custom($TREE_MAX_CHILDREN, Length)`: If set, only the first N children of each node are rendered, followed by a "… 4,982 more (press Enter to expand)" row. Submitting that row shows N more children of the node
+//! - `Custom($TREE_MULTI_SELECT, Flag)`: If true, marked nodes (i.e. with `Flag::MARKED`) are rendered with `TREE_MARK_SYMBOL` and `TREE_MARK_STYLE`, and `state()` returns their ids as `State::Vec`, in the order they appear in the tree, if any node is marked
 //! - `Custom($TREE_PRESERVE_STATE, Flag)`: If true, the selected entry will be kept after an update of the tree (obviously if the entry still exists in the tree).
+//! - `Custom($TREE_PRESERVE_SUBTREE, String)`: If set, the state is preserved after an update of the tree only inside the subtree with this id: its open nodes (and its ancestors) are kept open and its selected node is kept, while the rest of the tree is reset. It has priority over `TREE_PRESERVE_STATE`
+//! - `Custom($TREE_REVEAL_INITIAL_NODE, Flag)`: If false, `TREE_INITIAL_NODE` doesn't open the ancestors of the initial node: the node is selected only if it's already visible, otherwise its closest visible ancestor is selected. Default is true
+//! - `Custom($TREE_PREVIEW_STYLE, Style)`: Style for the cursor when `TREE_SOFT_SELECTION` is set. Default is the highlighted color, underlined
+//! - `Custom($TREE_RTL, Flag)`: If true, the tree is rendered from right to left: indentation, arrows and highlight symbol start from the right edge and labels are right-aligned
+//! - `Custom($TREE_SCROLL_JUMP, Length)`: Amount of rows to scroll at once when the selected node leaves the view. Default is 1. When scrolling all the rows change, so jumping by many rows (e.g. half the height) sends far fewer cells to slow remote terminals (SSH, mosh) while moving through a large tree. Only changed cells are sent anyway, since ratatui diffs the frames
+//! - `Custom($TREE_SCROLLBAR, Flag)`: If true, a vertical scrollbar showing the position of the visible rows in the whole tree is rendered on the last column, when the rows don't fit the area (on the first column with `TREE_RTL`). Default is false
+//! - `Custom($TREE_SEARCH_FUZZY, Flag)`: If true, `search` and `TREE_CMD_SEARCH` match queries fuzzily (all the characters in order, see `FuzzyMatcher`), instead of as substrings. Default is false
+//! - `Custom($TREE_SEARCH_QUERY, String)`: Query searched by `TREE_CMD_SEARCH`
+//! - `Custom($TREE_SMOOTH_SCROLL, Flag)`: If true, jumps larger than the view (e.g. `GoTo(End)`) are animated: the view eases toward the selected node on each `Cmd::Tick`, which returns `Changed` while the animation is in progress, so that the view is rendered again. Default is false
+//! - `Custom($TREE_SOFT_SELECTION, Flag)`: If true, moving the cursor only updates a preview of the selection, rendered with `TREE_PREVIEW_STYLE`. `Submit` and `TREE_CMD_COMMIT` promote it to the committed selection, which is the one reported by `state()` and rendered with the highlight style. `Changed` results still report the cursor
+//! - `Custom($TREE_STATE_MAP, Flag)`: If true, `state()` returns a `State::Map` with the id of the selected node at `selected` (`None` if no node is selected) and the ids of the open nodes at `open.0`, `open.1`, ..., whose amount is at `open` as `Usize`, so that the whole view state can be persisted or mirrored through the state of the component. It can be decoded with `PersistedState::from_state`. It takes precedence over the state of `TREE_MULTI_SELECT`
+//! - `Custom($TREE_STYLE_PER_DEPTH, Payload(Vec(Style)))`: Styles patched on the rows which aren't highlighted, cycled by depth (e.g. a slightly different background for each nesting level), to make deep hierarchies readable without guide lines
+//! - `Custom($TREE_SUBMIT_FLAG, String)`: If set, `Submit` reports the ids of all the nodes with the flag with this name as `State::Vec`, if any
+//! - `Custom($TREE_SUBMIT_PAYLOAD, Flag)`: If true, `Submit` reports the `NodeValue::submit_value()` of the selected node instead of its id (the id is still used if the value returns `None`)
+//! - `Custom($TREE_TAB_WIDTH, Length)`: Amount of columns between two tab stops, used to expand tabs in labels. Default is 4; if 0, tabs are removed. Other control characters are always removed from labels
 //! - `FocusStyle(Style)`: inactive style
 //! - `Foreground(Color)`: foreground color. The foreground will be used as foreground for the selected item, when focus is false, otherwise as background
 //! - `HighlightedColor(Color)`: The provided color will be used to highlight the selected node. `Foreground` will be used if unset.
 //! - `HighlightedStr(String)`: The provided string will be displayed on the left side of the selected entry in the tree
+//! - `Custom($TREE_HL_SYMBOL_FOCUS_ONLY, Flag)`: If true, the highlighted string is displayed only when the component has focus
+//! - `Custom($TREE_HL_LABEL_STYLE, Style)`: If set, style for the selected row, instead of the one built from `HighlightedColor`
+//! - `Custom($TREE_HL_SYMBOL_STYLE, Style)`: If set, style for the highlighted string, independently from the selected row (e.g. a red arrow before a normal-colored label)
 //! - `ScrollStep(Length)`: Defines the maximum amount of rows to scroll
+//! - `ScrollStep(Size)`: Defines the maximum amount of rows to scroll as a percentage of the viewport height (e.g. 50 for half a page), so that scrolling feels the same whatever the size of the tree area
 //! - `TextProps(TextModifiers)`: set text modifiers
 //! - `Title(Title)`: Set box title
 //!
@@ -67,9 +160,40 @@
 //! When you implement `Component` for your treeview, you have a mutable reference to the component, and so here you can call these methods to operate on the tree:
 //!
 //! - `pub fn tree(&self) -> &Tree`: returns a reference to the tree
-//! - `pub fn tree_mut(&mut self) -> &mut Tree`: returns a mutable reference to the tree; which allows you to operate on it
+//! - `pub fn tree_mut(&mut self) -> &mut Tree`: returns a mutable reference to the tree; which allows you to operate on it. Don't remove the selected or open nodes this way: use `set_tree` instead. In debug builds, `perform` panics if the state refers to removed nodes
 //! - `pub fn set_tree(&mut self, tree: Tree)`: update the current tree with another
+//! - `pub fn swap_tree(&mut self, tree: Tree) -> Tree`: update the current tree with another, getting back the previous one. With `remember_positions`, the selected node and the scroll offset are remembered by root id, and restored when a tree with the same root is set again (e.g. when navigating into a directory and back)
+//! - `pub fn matching_ids(&self, query: &str) -> Vec<String>`: get the ids of the nodes whose search text (see `NodeValue::search_text`) contains `query`
+//! - `pub fn matching_ids_with<M: Matcher + ?Sized>(&self, matcher: &M) -> Vec<String>`: as `matching_ids`, with other matching semantics: `SubstringMatcher`, `CaseInsensitiveMatcher`, `FuzzyMatcher`, `RegexMatcher` or your own `Matcher`
+//! - `pub fn search(&mut self, query: &str) -> CmdResult`, `search_with`: find the nodes whose search text matches `query`, open the ancestors of the first match from the selected node and select it. Cycle through the matches with `search_next` and `search_prev`; get them with `search_matches` and forget them with `clear_search`
+//! - `pub fn set_matcher<M: Matcher + 'static>(&mut self, matcher: M)`, `clear_matcher`: highlight the ranges matched by `matcher` in labels, instead of the occurrences of `TREE_MATCH_QUERY`
+//! - `pub fn set_display_map<F: Fn(&V) -> Cow<str>>(&mut self, map: F)`, `clear_display_map`: override how values are rendered without changing the tree nor the `NodeValue` implementation (e.g. to toggle between full paths and file names, or to localize labels). The initial map can be set with `display_map`
+//! - `pub fn sort_tree(&mut self, compare)`: sort the children of all the nodes in the tree, keeping the selected node anchored in the view
+//! - `pub fn sort_column(&mut self, column: usize, order: Option<SortOrder>)`, `sorted_column`: sort the siblings by a column of the table (see `TREE_COLUMNS`), comparing them with `NodeValue::cmp_column`, or restore the tree order with `None`. The sort is kept when the tree or the children of a node are set
 //! - `pub fn tree_state(&self) -> &TreeState`: get a reference to the current tree state. (See tree state docs)
+//! - `pub fn tree_state_mut(&mut self) -> &mut TreeState`: get a mutable reference to the current tree state, e.g. to clear the selection, to open or close nodes or to reset it
+//! - `pub fn selected_node(&self) -> Option<&Node>`: get a reference to the selected node (e.g. to update a preview on `CmdResult::Changed`)
+//! - `pub fn children_ids<S: AsRef<str>>(&self, id: S) -> Option<Vec<&str>>`, `child_count`: get the ids or the amount of children of a node (e.g. to tell whether a directory has already been loaded)
+//! - `pub fn selected_description(&self) -> String`: get a readable description of the selected node for screen readers (e.g. "readme.md, file, inside home slash omar, item 3 of 7"). Its components are set with `description_parts`, and the kind is provided by `NodeValue::kind`
+//! - `pub fn click(&mut self, column: u16, row: u16, now: Instant) -> CmdResult`: select the node at the clicked position; submit it on double click if `double_click_submit` is set
+//! - `pub fn on_mouse(&mut self, event: MouseEvent, now: Instant) -> CmdResult`: select the clicked node, opening or closing it when its arrow is clicked or on double click; scroll the tree with the wheel
+//! - `pub fn drag(&mut self, column: u16, row: u16) -> CmdResult`, `nudge_drag`, `end_drag`: select the node under the drag cursor, scrolling the view on each tick while the cursor is on its top or bottom edge
+//! - `pub fn set_flag(&mut self, id, flag: Flag)`, `unset_flag`, `toggle_flag`, `has_flag`: operate on the flags of a node
+//! - `pub fn ids_with_flag(&self, flag: Flag) -> Vec<String>`: get the ids of all the nodes with a flag, to act on all of them at once
+//! - `pub fn selected_ids(&self) -> Vec<String>`: get the ids of the nodes selected for bulk operations, which are the nodes with `Flag::MARKED`
+//! - `pub fn add_decorator<D: NodeDecorator<V> + 'static>(&mut self, decorator: D)`: register a decorator (e.g. git status, bookmarks, search matches). Decorators run in order and their decorations are merged: prefixes and suffixes are concatenated and styles overlaid
+//! - `pub fn recently_submitted(&self) -> &[String]`: get the ids of the recently submitted nodes, from the most recent (e.g. for "recent locations"). Use `recent_limit` to set how many are kept and `recent_decoration` to mark them in the tree
+//! - `pub fn lock_navigation<S: AsRef<str>>(&mut self, id: S) -> bool`, `unlock_navigation`: restrict `Move`, `Scroll` and `GoTo` to the subtree of a node, without hiding the rest of the tree (e.g. for modal workflows where the user must pick a node inside a specific branch)
+//! - `pub fn mark_dirty<S: AsRef<str>>(&mut self, id: S)`, `clear_dirty`, `is_dirty`: mark a node as dirty (e.g. unread or changed). A badge (see `dirty_badge`) is rendered on each collapsed ancestor containing it, until the node is revealed or selected
+//! - `pub fn bookmark<S: AsRef<str>>(&mut self, id: S) -> bool`, `remove_bookmark`, `is_bookmarked`, `bookmarks`: bookmark nodes, which are reached with `TREE_CMD_NEXT_BOOKMARK` and `TREE_CMD_PREV_BOOKMARK` and rendered with `bookmark_decoration`
+//! - `pub fn persisted_state(&self) -> PersistedState`, `restore_persisted_state`: save and restore the open nodes, the selected node and the bookmarks between sessions
+//! - `pub fn batch<F: FnOnce(&mut Self)>(&mut self, updates: F) -> CmdResult`, `begin_batch`, `end_batch`: perform multi-step updates (e.g. set tree, reveal node, set flags) atomically: intermediate `Changed` results, selection watchers, undo records and state validation are suppressed, and a single `Changed` is returned when the batch ends
+//! - `pub fn flash_message<S: AsRef<str>>(&mut self, text: S, renders: usize)`, `clear_flash_message`: render a transient message (e.g. "copied path" or "permission denied") at the bottom of the tree area instead of the footer, for the provided amount of renders
+//! - `pub fn set_leaf(&mut self, id: &str, leaf: bool)`: treat a node as a leaf even if it has children, which are then never shown nor selected (e.g. archives or directories which can't be browsed)
+//! - `pub fn watch_selection(&mut self) -> SelectionReceiver`: get a `tokio::sync::watch` receiver of the changes of the selected node, which can be moved to a background thread or task (requires the `watch` feature)
+//! - `pub fn unload_closed_branches(&mut self, now: Instant) -> Vec<String>`: drop the children of branches closed for longer than the interval set with `unload_closed`; they're provided back by the reload callback when the branch is opened again
+//! - `pub fn set_children<S: AsRef<str>>(&mut self, id: S, children: Vec<Node>) -> bool`, `is_loading`: provide the children of a lazy branch loaded in background after `child_loader` has been called for it, keeping the state
+//! - `pub fn prune<S: AsRef<str>>(&mut self, id: S) -> bool`: remove a subtree (e.g. a deleted directory), even if it contains the selected or open nodes: the selection is moved to the parent of the removed node and the removed nodes are closed
 //!
 //! You can access these methods from the `on()` method as said before. So these methods can be handy when you update the tree after a certain events or maybe even better, you can set the tree if you receive it from a `UserEvent` produced by a **Port**.
 //!
@@ -88,7 +212,7 @@
 //!     Component, MockComponent, NoUserEvent, State, StateValue,
 //! };
 //! // treeview
-//! use tui_realm_treeview::{Node, Tree, TreeView, TREE_CMD_CLOSE, TREE_CMD_OPEN};
+//! use tui_realm_treeview::{Node, Tree, TreeCmd, TreeView};
 //!
 //! #[derive(Debug, PartialEq)]
 //! pub enum Msg {
@@ -135,11 +259,11 @@
 //!             Event::Keyboard(KeyEvent {
 //!                 code: Key::Left,
 //!                 modifiers: KeyModifiers::NONE,
-//!             }) => self.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+//!             }) => self.perform(TreeCmd::Close.into()),
 //!             Event::Keyboard(KeyEvent {
 //!                 code: Key::Right,
 //!                 modifiers: KeyModifiers::NONE,
-//!             }) => self.perform(Cmd::Custom(TREE_CMD_OPEN)),
+//!             }) => self.perform(TreeCmd::Open.into()),
 //!             Event::Keyboard(KeyEvent {
 //!                 code: Key::PageDown,
 //!                 modifiers: KeyModifiers::NONE,
@@ -185,12 +309,47 @@
 //!
 //! ---
 //!
+//! ## Node ids and values
+//!
+//! When node ids are built from paths, use `ids::encode_path` instead of `Path::to_string_lossy`: the ids of paths
+//! with unusual file names (e.g. non UTF-8 or with newlines) don't collide, and `ids::decode_path` gives back the path.
+//! When labels are not unique (e.g. song titles), add nodes with `ids::AutoId::push_child_auto_id`, which generates
+//! a unique id from the parent id and returns it for later selection.
+//!
+//! For config, JSON or TOML inspectors, use `KeyValueNode` as node value: keys are rendered left-aligned and values
+//! are aligned to the same column across siblings. Other values can opt in to alignment with `NodeValue::key_parts`.
+//!
+//! Lazily loaded branches can show how many children they have before loading them, with `NodeValue::child_count_hint`
+//! (e.g. `▶ (12)` for a directory with 12 entries).
+//!
+//! Each node can carry its own style and icon, e.g. colors by file type resolved from the current theme:
+//! `NodeValue::base_style` is patched on the row when the node isn't highlighted, `NodeValue::selected_style` on the
+//! highlighted row and `NodeValue::icon` is rendered before the label.
+//!
+//! To test the routines which update the tree, compare the tree before and after the update with `tree_diff`: it
+//! reports the ids which have been added, removed, changed or moved, e.g. `[+ /src/main.rs at /src[0], ~ /src]`.
+//!
+//! ---
+//!
 //! ## Tree widget
 //!
 //! If you want, you can also implement your own version of a tree view mock component using the `TreeWidget`
 //! in order to render a tree.
 //! Keep in mind that if you want to create a stateful tree (with highlighted item), you'll need to render it
 //! as a stateful widget, passing to it a `TreeState`, which is provided by this library.
+//! A state with some nodes already open and selected can be built with `TreeStateBuilder`.
+//! Rows can also be drawn on your own with `TreeWidget::row_renderer`, while the widget keeps handling layout,
+//! scrolling and selection.
+//! Companion ratatui widgets working with indices (e.g. a `Scrollbar`) can be fed with `TreeState::as_list_state`.
+//! Custom indicators (e.g. "line N of M") can be drawn with `TreeState::selected_index` and `visible_len`, while
+//! `offset` and `viewport_height` report the scroll position written back by the widget at each render.
+//! Tick-driven apps can animate the rows which appear and disappear (e.g. fading new nodes in over a few frames) with
+//! `TreeState::track_row_changes`: the rows added and removed at each render are then reported by `rows_added` and `rows_removed`.
+//! Labels whose render parts are expensive to compute can be cached in the state with `TreeWidget::cache_labels`;
+//! call `TreeState::invalidate_labels` after changing node values without calling `tree_changed`.
+//! The same tree and state can also be rendered as ranger-style columns (parent, current and preview) with
+//! `MillerColumnsWidget`, moving with `TreeState::select_prev_sibling`, `select_next_sibling`, `select_parent`
+//! and `select_first_child`.
 //!
 
 #![doc(html_playground_url = "https://play.rust-lang.org")]
@@ -201,22 +360,81 @@
     html_logo_url = "https://raw.githubusercontent.com/veeso/tui-realm-treeview/main/docs/images/cargo/tui-realm-treeview-512.png"
 )]
 
+// -- log
+/// Log a state transition with `log::debug!`, when the `debug` feature is enabled
+macro_rules! debug_log {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "debug")]
+        log::debug!(target: "tui_realm_treeview", $($arg)+);
+    };
+}
+
 // -- mock
 #[cfg(test)]
 pub(crate) mod mock;
 // -- modules
+mod cmd;
+mod decoration;
+mod defaults;
+mod description;
+mod diff;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+mod flag;
+#[cfg(any(test, feature = "fs"))]
+mod fs;
+pub mod ids;
+#[cfg(feature = "json")]
+mod json;
+mod key_value;
+mod loader;
+mod matcher;
+mod miller;
+mod persist;
+mod table;
+mod theme;
 mod tree_state;
+#[cfg(any(test, feature = "watch"))]
+pub mod watch;
 mod widget;
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::iter;
+use std::time::{Duration, Instant};
 // internal
-pub use tree_state::TreeState;
-pub use widget::TreeWidget;
+pub use cmd::{LazySubmit, TreeCmd};
+pub use decoration::{Decoration, NodeDecorator};
+pub use defaults::TreeDefaults;
+pub use description::DescriptionPart;
+pub use diff::{tree_diff, DiffEntry};
+pub use flag::Flag;
+#[cfg(any(test, feature = "fs"))]
+pub use fs::{FsKind, FsNodeValue, FsTreeBuilder, SymlinkPolicy};
+#[cfg(feature = "json")]
+pub use json::{FromJson, JsonIdStrategy, JsonKind, JsonNodeValue};
+pub use key_value::KeyValueNode;
+pub use loader::ChildLoader;
+#[cfg(feature = "regex")]
+pub use matcher::RegexMatcher;
+pub use matcher::{CaseInsensitiveMatcher, FuzzyMatcher, Matcher, SubstringMatcher};
+pub use miller::MillerColumnsWidget;
+pub use persist::PersistedState;
+use table::ColumnSort;
+pub use table::SortOrder;
+pub use theme::TreeTheme;
+use tree_state::StateSnapshot;
+pub use tree_state::{TreeState, TreeStateBuilder};
+use widget::DisplayMapFn;
+pub use widget::{BackgroundFill, RowCtx, TreeWidget};
 // deps
 pub use orange_trees::{Node as OrangeNode, Tree as OrangeTree};
 use tuirealm::command::{Cmd, CmdResult, Direction, Position};
+use tuirealm::event::{MouseButton, MouseEvent, MouseEventKind};
 use tuirealm::props::{
-    Alignment, AttrValue, Attribute, Borders, Color, Props, Style, TextModifiers, TextSpan,
+    Alignment, AttrValue, Attribute, Borders, Color, PropPayload, PropValue, Props, Style,
+    TextModifiers, TextSpan,
 };
 use tuirealm::ratatui::{layout::Rect, widgets::Block};
 use tuirealm::{Frame, MockComponent, State, StateValue};
@@ -226,12 +444,94 @@ pub trait NodeValue: Default {
     /// Return iterator over render parts - text with it style.
     /// If style is `None`, then it will be inherited from widget style.
     fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)>;
+
+    /// Return the value to report in `CmdResult::Submit` instead of the node id (e.g. a path or a numeric id).
+    /// This is used only if `TREE_SUBMIT_PAYLOAD` is enabled; if `None` is returned, the node id is reported.
+    fn submit_value(&self) -> Option<StateValue> {
+        None
+    }
+
+    /// Return whether the node should be treated as a branch even if it has no children (e.g. a directory whose
+    /// content hasn't been loaded yet). Such nodes are rendered with a closed arrow and can be opened.
+    fn is_branch_hint(&self) -> bool {
+        false
+    }
+
+    /// Return the amount of children the node is expected to have, before they're loaded (e.g. the amount of entries of
+    /// a directory). The count is rendered next to the arrow of closed branches (see `is_branch_hint`) without children,
+    /// and disappears once their children are loaded.
+    fn child_count_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// Return the amount of leading render parts which make the key of the node (e.g. see `KeyValueNode`).
+    /// The parts after the key are aligned to the same column across the siblings which have a key,
+    /// one space after the widest key. By default nodes have no key.
+    fn key_parts(&self) -> Option<usize> {
+        None
+    }
+
+    /// Return the text to match when searching nodes (e.g. a full path or some tags, instead of the label).
+    /// By default the rendered label is used; return `None` to exclude the node from matching.
+    fn search_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(
+            self.render_parts_iter().map(|(text, _)| text).collect(),
+        ))
+    }
+
+    /// Return the kind of the node to announce in `TreeView::selected_description` (e.g. "file" or "directory").
+    /// If `None` is returned, the node is described as a "branch" or a "leaf".
+    fn kind(&self) -> Option<&str> {
+        None
+    }
+
+    /// Return the style of the row of the node when it isn't highlighted (e.g. a color by file type or a dimmed
+    /// style for ignored files). It is patched on top of the widget style.
+    fn base_style(&self) -> Option<Style> {
+        None
+    }
+
+    /// Return the style of the row of the node when it is highlighted. It is patched on top of the highlight style.
+    fn selected_style(&self) -> Option<Style> {
+        None
+    }
+
+    /// Return the icon to render before the label of the node (e.g. a file type glyph), followed by a space.
+    fn icon(&self) -> Option<&str> {
+        None
+    }
+
+    /// Return the text of the cell of the node in the column `column` of the table (see `TREE_COLUMNS`), e.g. the size
+    /// or the modification date of a file. Column 0 is the tree column, which renders the label, so cells are asked
+    /// for the other columns only. By default nodes have no cells.
+    fn cell(&self, column: usize) -> Option<Cow<'_, str>> {
+        let _ = column;
+        None
+    }
+
+    /// Compare the node with one of its siblings by the column `column`, to sort them with `TREE_CMD_SORT_COLUMN`.
+    /// By default labels are compared for the tree column and cells as text for the other columns; override it to
+    /// compare e.g. sizes or dates by their value.
+    fn cmp_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => {
+                let label =
+                    |x: &Self| -> String { x.render_parts_iter().map(|(x, _)| x).collect() };
+                label(self).cmp(&label(other))
+            }
+            column => self.cell(column).cmp(&other.cell(column)),
+        }
+    }
 }
 
 impl NodeValue for String {
     fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
         iter::once((self.as_str(), None))
     }
+
+    fn search_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.as_str()))
+    }
 }
 
 impl NodeValue for Vec<TextSpan> {
@@ -256,14 +556,85 @@ pub type Tree<V> = OrangeTree<String, V>;
 
 // -- props
 
+pub const TREE_ARROW_FIRST: &str = "arrow-first";
+pub const TREE_ASCII_ONLY: &str = "ascii-only";
+pub const TREE_CLOSE_TO_PARENT: &str = "close-to-parent";
+pub const TREE_COLUMNS: &str = "columns";
+pub const TREE_DROP_STYLE: &str = "drop-style";
+pub const TREE_ELLIPSIS: &str = "ellipsis";
+pub const TREE_EXPAND_ON_SELECT: &str = "expand-on-select";
+pub const TREE_FILL_INNER: &str = "fill-inner";
+pub const TREE_FOOTER: &str = "footer";
+pub const TREE_GUIDES: &str = "guides";
+pub const TREE_GUIDES_STYLE: &str = "guides-style";
+pub const TREE_HL_LABEL_STYLE: &str = "hl-label-style";
+pub const TREE_HL_SYMBOL_FOCUS_ONLY: &str = "hl-symbol-focus-only";
+pub const TREE_HL_SYMBOL_STYLE: &str = "hl-symbol-style";
+pub const TREE_ICON_STYLE: &str = "icon-style";
 pub const TREE_INDENT_SIZE: &str = "indent-size";
 pub const TREE_INITIAL_NODE: &str = "initial-mode";
+pub const TREE_INITIAL_OPEN: &str = "initial-open";
+pub const TREE_MATCH_QUERY: &str = "match-query";
+pub const TREE_MATCH_STYLE: &str = "match-style";
+pub const TREE_MARK_STYLE: &str = "mark-style";
+pub const TREE_MARK_SYMBOL: &str = "mark-symbol";
+pub const TREE_MAX_CHILDREN: &str = "max-children";
+pub const TREE_MULTI_SELECT: &str = "multi-select";
 pub const TREE_PRESERVE_STATE: &str = "preserve-state";
+pub const TREE_PRESERVE_SUBTREE: &str = "preserve-subtree";
+pub const TREE_PREVIEW_STYLE: &str = "preview-style";
+pub const TREE_REVEAL_INITIAL_NODE: &str = "reveal-initial-node";
+pub const TREE_RTL: &str = "rtl";
+pub const TREE_SCROLL_JUMP: &str = "scroll-jump";
+pub const TREE_SCROLLBAR: &str = "scrollbar";
+pub const TREE_SEARCH_FUZZY: &str = "search-fuzzy";
+pub const TREE_SEARCH_QUERY: &str = "search-query";
+pub const TREE_SMOOTH_SCROLL: &str = "smooth-scroll";
+pub const TREE_SOFT_SELECTION: &str = "soft-selection";
+pub const TREE_STATE_MAP: &str = "state-map";
+pub const TREE_STYLE_PER_DEPTH: &str = "style-per-depth";
+pub const TREE_SUBMIT_FLAG: &str = "submit-flag";
+pub const TREE_SUBMIT_PAYLOAD: &str = "submit-payload";
+pub const TREE_TAB_WIDTH: &str = "tab-width";
 
 // -- Cmd
 
 pub const TREE_CMD_OPEN: &str = "o";
+pub const TREE_CMD_OPEN_ALL: &str = "open-all";
 pub const TREE_CMD_CLOSE: &str = "c";
+pub const TREE_CMD_CLOSE_ALL: &str = "close-all";
+pub const TREE_CMD_CLOSE_PARENT: &str = "close-parent";
+pub const TREE_CMD_CLEAR_FLAGS: &str = "clear-flags";
+pub const TREE_CMD_COMMIT: &str = "commit";
+pub const TREE_CMD_GOTO_PARENT: &str = "goto-parent";
+pub const TREE_CMD_INDENT_INC: &str = "indent-inc";
+pub const TREE_CMD_INDENT_DEC: &str = "indent-dec";
+pub const TREE_CMD_MARK_ALL_SIBLINGS: &str = "mark-all-siblings";
+pub const TREE_CMD_NEXT_BOOKMARK: &str = "next-bookmark";
+pub const TREE_CMD_PREV_BOOKMARK: &str = "prev-bookmark";
+pub const TREE_CMD_SEARCH: &str = "search";
+pub const TREE_CMD_SEARCH_NEXT: &str = "search-next";
+pub const TREE_CMD_SEARCH_PREV: &str = "search-prev";
+pub const TREE_CMD_SELECT_ALL_VISIBLE: &str = "select-all-visible";
+pub const TREE_CMD_SELECT_NONE: &str = "select-none";
+pub const TREE_CMD_TOGGLE_MARK: &str = "toggle-mark";
+pub const TREE_CMD_UNDO: &str = "undo";
+pub const TREE_CMD_REDO: &str = "redo";
+pub const TREE_CMD_REORDER: &str = "reorder";
+/// Commands sorting the siblings by the column at the index (e.g. `Cmd::Custom(TREE_CMD_SORT_COLUMN[1])`)
+pub const TREE_CMD_SORT_COLUMN: [&str; 8] = [
+    "sort-column-0",
+    "sort-column-1",
+    "sort-column-2",
+    "sort-column-3",
+    "sort-column-4",
+    "sort-column-5",
+    "sort-column-6",
+    "sort-column-7",
+];
+pub const TREE_CMD_VIEW_CENTER: &str = "zz";
+pub const TREE_CMD_VIEW_TOP: &str = "zt";
+pub const TREE_CMD_VIEW_BOTTOM: &str = "zb";
 
 // -- component
 
@@ -276,14 +647,176 @@ pub struct TreeView<V: NodeValue> {
     /// The actual Tree data structure. You can access this from your Component to operate on it
     /// for example after a certain events.
     tree: Tree<V>,
+    /// Latest tree queued with `queue_tree`, waiting to be applied
+    queued_tree: Option<Tree<V>>,
+    /// Minimum interval between two applications of a queued tree
+    debounce: Duration,
+    /// Last time a queued tree has been applied
+    last_queued_apply: Option<Instant>,
+    /// Decorations resolved by the application for nodes
+    decorations: HashMap<String, Decoration>,
+    /// Decoration rendered for nodes which haven't been resolved yet
+    decoration_placeholder: Option<Decoration>,
+    /// Decorators run in order when rendering nodes
+    decorators: Vec<Box<dyn NodeDecorator<V>>>,
+    /// Matcher whose matches are highlighted in labels, instead of `TREE_MATCH_QUERY`
+    matcher: Option<Box<dyn Matcher>>,
+    /// Label rendered for values, instead of their render parts
+    display_map: Option<Box<DisplayMapFn<'static, V>>>,
+    /// Cache the render parts of labels until the tree is changed
+    cache_labels: bool,
+    /// Ids of the nodes matched by the last search, in the order they appear in the tree
+    search_matches: Vec<String>,
+    /// Ticks elapsed since the selected node has been selected
+    selected_ticks: usize,
+    /// Flags set on nodes
+    flags: HashMap<String, HashSet<Flag>>,
+    /// Interval after which children of closed branches are unloaded
+    unload_after: Option<Duration>,
+    /// Last time each closed branch was open
+    last_open: HashMap<String, Instant>,
+    /// Callback to provide back children of unloaded branches on open
+    reload: Option<Box<ReloadFn<V>>>,
+    /// Loader of the children of lazy branches opened without children
+    loader: Option<Box<dyn ChildLoader<V>>>,
+    /// Nodes whose children are being loaded by the loader
+    loading: HashSet<String>,
+    /// Text rendered after the spinner of the nodes whose children are being loaded
+    loading_text: String,
+    /// Frame of the spinner of the nodes whose children are being loaded
+    loading_frame: usize,
+    /// Maximum interval between two clicks on the same row to submit it
+    double_click: Option<Duration>,
+    /// Last clicked node and when it was clicked
+    last_click: Option<(String, Instant)>,
+    /// Position (column, row) of the cursor while dragging
+    drag: Option<(u16, u16)>,
+    /// Rows from the top and bottom edges where the view is scrolled while dragging
+    drag_edge: u16,
+    /// Committed selection, reported by `state()` when `TREE_SOFT_SELECTION` is set
+    committed: Option<String>,
+    /// Channel publishing the selected node, once somebody watches it
+    #[cfg(any(test, feature = "watch"))]
+    selection_sender: Option<watch::SelectionSender>,
+    /// Nodes marked as dirty, not revealed yet
+    dirty: HashSet<String>,
+    /// Badge rendered on the collapsed ancestors of dirty nodes, with its style
+    dirty_badge: (String, Style),
+    /// Node whose subtree navigation is restricted to
+    navigation_lock: Option<String>,
+    /// Ids of the submitted nodes, from the most recent
+    recent: Vec<String>,
+    /// Maximum amount of recently submitted nodes to keep
+    recent_limit: usize,
+    /// Decoration rendered for recently submitted nodes
+    recent_decoration: Option<Decoration>,
+    /// Ids of the bookmarked nodes, in the order they have been bookmarked
+    bookmarks: Vec<String>,
+    /// Decoration merged on top of the decoration of bookmarked nodes
+    bookmark_decoration: Decoration,
+    /// Components of the description of the selected node
+    description_parts: Vec<DescriptionPart>,
+    /// What `Submit` does on lazy branches
+    lazy_submit: LazySubmit,
+    /// Maximum amount of state changes which can be undone; 0 if undo is disabled
+    undo_depth: usize,
+    /// States to restore on undo, from the oldest to the latest
+    undo: Vec<StateSnapshot>,
+    /// States to restore on redo, from the oldest to the latest
+    redo: Vec<StateSnapshot>,
+    /// Selected node and scroll offset of the last trees shown, by root id, from the most recent
+    root_positions: Vec<(String, Option<String>, usize)>,
+    /// Maximum amount of roots whose position is remembered; 0 if disabled
+    root_positions_limit: usize,
+    /// Bounds of the indent size, if it is computed on each render from the depth and the width
+    auto_indent: Option<(u16, u16)>,
+    /// Message rendered instead of the footer, with the amount of renders it is still rendered for
+    flash: Option<(String, usize)>,
+    /// Node being moved among its siblings with `TREE_CMD_REORDER`, with the position it will land at
+    pending_move: Option<(String, usize)>,
+    /// Column the siblings are sorted by with `TREE_CMD_SORT_COLUMN`
+    column_sort: ColumnSort,
+    /// Whether `TREE_INITIAL_OPEN` has been set and not applied yet
+    initial_open_pending: bool,
+    /// Nesting level of the batches in progress; 0 if no batch is in progress
+    batch_depth: usize,
+    /// State when the outermost batch began
+    batch_start: Option<StateSnapshot>,
+    /// Last operation which may have changed the tree; reported if the state is found inconsistent
+    #[cfg(debug_assertions)]
+    last_mutation: &'static str,
 }
 
+/// Maximum interval between two clicks on the same row to toggle it, if `double_click_submit` is not set
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Frames of the spinner rendered for the nodes whose children are being loaded
+const LOADING_FRAMES: &[&str] = &[
+    "\u{280b}", "\u{2819}", "\u{2839}", "\u{2838}", "\u{283c}", "\u{2834}", "\u{2826}", "\u{2827}",
+    "\u{2807}", "\u{280f}",
+];
+/// Frames of the spinner, with `TREE_ASCII_ONLY`
+const LOADING_FRAMES_ASCII: &[&str] = &["|", "/", "-", "\\"];
+
+/// Callback which provides back the children of an unloaded node
+type ReloadFn<V> = dyn FnMut(&Node<V>) -> Vec<Node<V>>;
+
 impl<V: NodeValue> Default for TreeView<V> {
     fn default() -> Self {
         Self {
             props: Props::default(),
             states: TreeState::default(),
             tree: Tree::new(Node::new(String::new(), V::default())),
+            queued_tree: None,
+            debounce: Duration::from_millis(100),
+            last_queued_apply: None,
+            decorations: HashMap::new(),
+            decoration_placeholder: None,
+            decorators: Vec::new(),
+            matcher: None,
+            display_map: None,
+            cache_labels: false,
+            search_matches: Vec::new(),
+            selected_ticks: 0,
+            flags: HashMap::new(),
+            unload_after: None,
+            last_open: HashMap::new(),
+            reload: None,
+            loader: None,
+            loading: HashSet::new(),
+            loading_text: String::from("loading\u{2026}"),
+            loading_frame: 0,
+            double_click: None,
+            last_click: None,
+            drag: None,
+            drag_edge: 1,
+            committed: None,
+            #[cfg(any(test, feature = "watch"))]
+            selection_sender: None,
+            dirty: HashSet::new(),
+            dirty_badge: (String::from("\u{25cf}"), Style::default()),
+            navigation_lock: None,
+            recent: Vec::new(),
+            recent_limit: 16,
+            recent_decoration: None,
+            bookmarks: Vec::new(),
+            bookmark_decoration: Decoration::default().prefix("\u{2605} "),
+            description_parts: DescriptionPart::defaults(),
+            lazy_submit: LazySubmit::default(),
+            undo_depth: 0,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            root_positions: Vec::new(),
+            root_positions_limit: 0,
+            auto_indent: None,
+            flash: None,
+            pending_move: None,
+            column_sort: ColumnSort::default(),
+            initial_open_pending: false,
+            batch_depth: 0,
+            batch_start: None,
+            #[cfg(debug_assertions)]
+            last_mutation: "none",
         }
     }
 }
@@ -291,6 +824,44 @@ impl<V: NodeValue> Default for TreeView<V> {
 impl<V: NodeValue> TreeView<V> {
     // -- constructors
 
+    /// ### with_defaults
+    ///
+    /// Apply the properties set in `defaults`, shared by many tree views.
+    /// Builder methods called afterwards override them for this component
+    pub fn with_defaults(mut self, defaults: &TreeDefaults) -> Self {
+        if let Some(fg) = defaults.foreground {
+            self = self.foreground(fg);
+        }
+        if let Some(bg) = defaults.background {
+            self = self.background(bg);
+        }
+        if let Some(color) = defaults.highlighted_color {
+            self = self.highlighted_color(color);
+        }
+        if let Some(style) = defaults.inactive {
+            self = self.inactive(style);
+        }
+        if let Some(borders) = defaults.borders.clone() {
+            self = self.borders(borders);
+        }
+        if let Some(modifiers) = defaults.modifiers {
+            self = self.modifiers(modifiers);
+        }
+        if let Some(symbol) = defaults.highlight_symbol.as_deref() {
+            self = self.highlight_symbol(symbol);
+        }
+        if let Some(size) = defaults.indent_size {
+            self = self.indent_size(size);
+        }
+        if let Some(step) = defaults.scroll_step {
+            self = self.scroll_step(step);
+        }
+        if let Some(close_to_parent) = defaults.close_to_parent {
+            self = self.close_to_parent(close_to_parent);
+        }
+        self
+    }
+
     /// ### foreground
     ///
     /// Set widget foreground
@@ -353,6 +924,39 @@ impl<V: NodeValue> TreeView<V> {
         self
     }
 
+    /// ### highlight_symbol_focus_only
+    ///
+    /// Set whether the highlight symbol should be displayed only when the component has focus
+    pub fn highlight_symbol_focus_only(mut self, focus_only: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_HL_SYMBOL_FOCUS_ONLY),
+            AttrValue::Flag(focus_only),
+        );
+        self
+    }
+
+    /// ### highlight_symbol_style
+    ///
+    /// Set style for the highlight symbol, independently from the highlighted label
+    pub fn highlight_symbol_style(mut self, style: Style) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_HL_SYMBOL_STYLE),
+            AttrValue::Style(style),
+        );
+        self
+    }
+
+    /// ### highlight_label_style
+    ///
+    /// Set style for the highlighted row, instead of the one built from the highlighted color
+    pub fn highlight_label_style(mut self, style: Style) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_HL_LABEL_STYLE),
+            AttrValue::Style(style),
+        );
+        self
+    }
+
     /// ### highlighted_color
     ///
     /// Set color to apply to highlighted item
@@ -373,6 +977,34 @@ impl<V: NodeValue> TreeView<V> {
         self
     }
 
+    /// ### initial_open
+    ///
+    /// Set the ids of the nodes to open at the first render.
+    /// Unlike `initial_node`, it can be specified before `with_tree`
+    pub fn initial_open<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.attr(
+            Attribute::Custom(TREE_INITIAL_OPEN),
+            AttrValue::Payload(PropPayload::Vec(
+                ids.into_iter()
+                    .map(|x| PropValue::Str(x.as_ref().to_string()))
+                    .collect(),
+            )),
+        );
+        self
+    }
+
+    /// ### drop_style
+    ///
+    /// Set style patched on the row above the landing position of the node moved with `TREE_CMD_REORDER`
+    pub fn drop_style(mut self, style: Style) -> Self {
+        self.attr(Attribute::Custom(TREE_DROP_STYLE), AttrValue::Style(style));
+        self
+    }
+
     /// ### preserve_state
     ///
     /// Set whether to preserve state on tree change
@@ -384,442 +1016,5267 @@ impl<V: NodeValue> TreeView<V> {
         self
     }
 
-    /// ### indent_size
+    /// ### preserve_subtree
     ///
-    /// Set indent size for widget for each level of depth
-    pub fn indent_size(mut self, sz: u16) -> Self {
-        self.attr(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(sz));
+    /// Preserve the state on tree change only inside the subtree with the provided id, resetting it elsewhere.
+    /// Has priority over `preserve_state`
+    pub fn preserve_subtree<S: AsRef<str>>(mut self, id: S) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_PRESERVE_SUBTREE),
+            AttrValue::String(id.as_ref().to_string()),
+        );
         self
     }
 
-    /// ### scroll_step
+    /// ### columns
     ///
-    /// Set scroll step for scrolling command
-    pub fn scroll_step(mut self, step: usize) -> Self {
-        self.attr(Attribute::ScrollStep, AttrValue::Length(step));
+    /// Set the titles and the widths of the columns of the table mode. The first column is the tree column, which takes
+    /// the width left by the other columns, whose cells are provided by `NodeValue::cell`
+    pub fn columns<S: AsRef<str>>(mut self, columns: &[(S, u16)]) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_COLUMNS),
+            AttrValue::Payload(PropPayload::Linked(
+                columns
+                    .iter()
+                    .map(|(title, width)| {
+                        PropPayload::Tup2((
+                            PropValue::Str(title.as_ref().to_string()),
+                            PropValue::U16(*width),
+                        ))
+                    })
+                    .collect(),
+            )),
+        );
         self
     }
 
-    /// ### with_tree
+    /// ### footer
     ///
-    /// Set tree to use as data
-    pub fn with_tree(mut self, tree: Tree<V>) -> Self {
-        self.tree = tree;
+    /// Set a line to render at the bottom of the tree area, inside the block (e.g. key hints)
+    pub fn footer<S: AsRef<str>>(mut self, footer: S) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_FOOTER),
+            AttrValue::String(footer.as_ref().to_string()),
+        );
         self
     }
 
-    // -- tree methods
+    /// ### match_style
+    ///
+    /// Set the style patched on the occurrences of `TREE_MATCH_QUERY` in labels
+    pub fn match_style(mut self, style: Style) -> Self {
+        self.attr(Attribute::Custom(TREE_MATCH_STYLE), AttrValue::Style(style));
+        self
+    }
 
-    /// ### tree
+    /// ### multi_select
     ///
-    /// Get a reference to tree
-    pub fn tree(&self) -> &Tree<V> {
-        &self.tree
+    /// Set whether the tree view is in multi-select mode: marked nodes (i.e. with `Flag::MARKED`) are rendered with
+    /// `TREE_MARK_SYMBOL` and `TREE_MARK_STYLE`, and `state()` reports their ids as `State::Vec`, if any
+    pub fn multi_select(mut self, multi: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_MULTI_SELECT), AttrValue::Flag(multi));
+        self
     }
 
-    /// ### tree_mut
+    /// ### state_map
     ///
-    /// Get mutable reference to tree
-    pub fn tree_mut(&mut self) -> &mut Tree<V> {
-        &mut self.tree
+    /// Set whether `state()` reports the selected node and the open nodes as `State::Map` (see `TREE_STATE_MAP`)
+    pub fn state_map(mut self, map: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_STATE_MAP), AttrValue::Flag(map));
+        self
     }
 
-    /// ### set_tree
+    /// ### mark_symbol
     ///
-    /// Set new tree in component.
-    /// Current state is preserved if `PRESERVE_STATE` is set to `AttrValue::Flag(true)`
-    pub fn set_tree(&mut self, tree: Tree<V>) {
-        self.tree = tree;
-        self.states.tree_changed(
-            self.tree.root(),
-            self.props
-                .get_or(
-                    Attribute::Custom(TREE_PRESERVE_STATE),
-                    AttrValue::Flag(false),
-                )
-                .unwrap_flag(),
+    /// Set the symbol rendered before the label of marked nodes, in multi-select mode
+    pub fn mark_symbol<S: AsRef<str>>(mut self, symbol: S) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_MARK_SYMBOL),
+            AttrValue::String(symbol.as_ref().to_string()),
         );
+        self
     }
 
-    /// ### tree_state
+    /// ### mark_style
     ///
-    /// Get a reference to the current tree state
-    pub fn tree_state(&self) -> &TreeState {
-        &self.states
+    /// Set the style patched on the symbol and on the label of marked nodes, in multi-select mode
+    pub fn mark_style(mut self, style: Style) -> Self {
+        self.attr(Attribute::Custom(TREE_MARK_STYLE), AttrValue::Style(style));
+        self
     }
 
-    // -- private
-
-    /// ### changed
+    /// ### fuzzy_search
     ///
-    /// Returns whether selectd node has changed
-    fn changed(&self, prev: Option<&str>) -> CmdResult {
-        match self.states.selected() {
-            None => CmdResult::None,
-            id if id != prev => CmdResult::Changed(self.state()),
-            _ => CmdResult::None,
-        }
+    /// Set whether `search` and `TREE_CMD_SEARCH` match queries fuzzily, instead of as substrings
+    pub fn fuzzy_search(mut self, fuzzy: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_SEARCH_FUZZY), AttrValue::Flag(fuzzy));
+        self
     }
 
-    fn get_block<'a>(
-        props: Borders,
-        title: Option<(String, Alignment)>,
-        focus: bool,
-        inactive_style: Option<Style>,
-    ) -> Block<'a> {
-        let title = title.unwrap_or((String::default(), Alignment::Left));
-        Block::default()
-            .borders(props.sides)
-            .border_style(match focus {
-                true => props.style(),
-                false => inactive_style
-                    .unwrap_or_else(|| Style::default().fg(Color::Reset).bg(Color::Reset)),
-            })
-            .border_type(props.modifiers)
-            .title(title.0)
-            .title_alignment(title.1)
+    /// ### arrow_first
+    ///
+    /// Set whether to render the open/closed arrow before the label, instead of after it
+    pub fn arrow_first(mut self, arrow_first: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_ARROW_FIRST),
+            AttrValue::Flag(arrow_first),
+        );
+        self
     }
-}
 
-// -- mock
+    /// ### rtl
+    ///
+    /// Set whether to render the tree from right to left
+    pub fn rtl(mut self, rtl: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_RTL), AttrValue::Flag(rtl));
+        self
+    }
 
-impl<V: NodeValue> MockComponent for TreeView<V> {
-    fn view(&mut self, frame: &mut Frame, area: Rect) {
-        if self.props.get_or(Attribute::Display, AttrValue::Flag(true)) == AttrValue::Flag(true) {
-            let foreground = self
-                .props
-                .get_or(Attribute::Foreground, AttrValue::Color(Color::Reset))
-                .unwrap_color();
-            let background = self
-                .props
-                .get_or(Attribute::Background, AttrValue::Color(Color::Reset))
-                .unwrap_color();
-            let modifiers = self
-                .props
-                .get_or(
-                    Attribute::TextProps,
-                    AttrValue::TextModifiers(TextModifiers::empty()),
-                )
-                .unwrap_text_modifiers();
-            let title = self
-                .props
-                .get_or(
-                    Attribute::Title,
-                    AttrValue::Title((String::default(), Alignment::Center)),
-                )
-                .unwrap_title();
-            let borders = self
-                .props
-                .get_or(Attribute::Borders, AttrValue::Borders(Borders::default()))
-                .unwrap_borders();
-            let focus = self
-                .props
-                .get_or(Attribute::Focus, AttrValue::Flag(false))
-                .unwrap_flag();
-            let inactive_style = self
-                .props
-                .get(Attribute::FocusStyle)
-                .map(|x| x.unwrap_style());
-            let indent_size = self
-                .props
-                .get_or(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(4))
-                .unwrap_size();
-            let hg_color = self
-                .props
-                .get_or(Attribute::HighlightedColor, AttrValue::Color(foreground))
-                .unwrap_color();
-            let hg_style = match focus {
-                true => Style::default().bg(hg_color).fg(Color::Black),
-                false => Style::default().fg(hg_color),
-            }
-            .add_modifier(modifiers);
-            let hg_str = self
-                .props
-                .get(Attribute::HighlightedStr)
-                .map(|x| x.unwrap_string());
-            let div = Self::get_block(borders, Some(title), focus, inactive_style);
-            // Make widget
-            let mut tree = TreeWidget::new(self.tree())
-                .block(div)
-                .highlight_style(hg_style)
-                .indent_size(indent_size.into())
-                .style(
-                    Style::default()
-                        .fg(foreground)
-                        .bg(background)
-                        .add_modifier(modifiers),
-                );
-            if let Some(hg_str) = hg_str {
-                tree = tree.highlight_symbol(hg_str);
-            }
-            let mut state = self.states.clone();
-            frame.render_stateful_widget(tree, area, &mut state);
-        }
+    /// ### close_to_parent
+    ///
+    /// Set whether closing a leaf or an already closed node should move the selection to its parent
+    pub fn close_to_parent(mut self, close_to_parent: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_CLOSE_TO_PARENT),
+            AttrValue::Flag(close_to_parent),
+        );
+        self
     }
 
-    fn query(&self, attr: Attribute) -> Option<AttrValue> {
-        self.props.get(attr)
+    /// ### expand_on_select
+    ///
+    /// Automatically open closed branches once they have been selected for `ticks` ticks (`Cmd::Tick`).
+    /// If `ticks` is 0, branches are opened as soon as they get selected
+    pub fn expand_on_select(mut self, ticks: usize) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_EXPAND_ON_SELECT),
+            AttrValue::Length(ticks),
+        );
+        self
     }
 
-    fn attr(&mut self, attr: Attribute, value: AttrValue) {
-        // Initial node
-        if matches!(attr, Attribute::Custom(TREE_INITIAL_NODE)) {
-            // Select node if exists
-            if let Some(node) = self.tree.root().query(&value.unwrap_string()) {
-                self.states.select(self.tree.root(), node);
-            }
-        } else {
-            self.props.set(attr, value);
-        }
+    /// ### submit_flag
+    ///
+    /// Set the flag whose nodes are reported on `Submit` as `State::Vec` of ids, when at least a node has it
+    pub fn submit_flag(mut self, flag: Flag) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_SUBMIT_FLAG),
+            AttrValue::String(flag.name().to_string()),
+        );
+        self
+    }
+
+    /// ### reveal_initial_node
+    ///
+    /// Set whether the ancestors of the initial node should be opened to reveal it (default).
+    /// If false, the initial node is selected only if it's already visible; otherwise its closest visible ancestor
+    /// is selected. Must be set before `initial_node`
+    pub fn reveal_initial_node(mut self, reveal: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_REVEAL_INITIAL_NODE),
+            AttrValue::Flag(reveal),
+        );
+        self
+    }
+
+    /// ### submit_payload
+    ///
+    /// Set whether `Submit` should report the `NodeValue::submit_value` of the selected node, instead of its id
+    pub fn submit_payload(mut self, payload: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_SUBMIT_PAYLOAD),
+            AttrValue::Flag(payload),
+        );
+        self
+    }
+
+    /// ### indent_size
+    ///
+    /// Set indent size for widget for each level of depth
+    pub fn indent_size(mut self, sz: u16) -> Self {
+        self.attr(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(sz));
+        self
+    }
+
+    /// ### auto_indent
+    ///
+    /// Compute the indent size on each render from the deepest visible node and the width of the area, between `min`
+    /// and `max`, so that very deep trees stay readable on narrow panes (see `TreeWidget::auto_indent`).
+    /// While set, `TREE_INDENT_SIZE` is ignored
+    pub fn auto_indent(mut self, min: u16, max: u16) -> Self {
+        self.auto_indent = Some((min, max));
+        self
+    }
+
+    /// ### scrollbar
+    ///
+    /// Set whether to render a vertical scrollbar when the rows don't fit the area
+    pub fn scrollbar(mut self, scrollbar: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_SCROLLBAR),
+            AttrValue::Flag(scrollbar),
+        );
+        self
+    }
+
+    /// ### guides
+    ///
+    /// Set whether to render tree guide lines (`│`, `├─`, `└─`) instead of plain indentation
+    pub fn guides(mut self, guides: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_GUIDES), AttrValue::Flag(guides));
+        self
+    }
+
+    /// ### guides_style
+    ///
+    /// Set style patched on the guide lines
+    pub fn guides_style(mut self, style: Style) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_GUIDES_STYLE),
+            AttrValue::Style(style),
+        );
+        self
+    }
+
+    /// ### icon_style
+    ///
+    /// Set style patched on the icons of nodes
+    pub fn icon_style(mut self, style: Style) -> Self {
+        self.attr(Attribute::Custom(TREE_ICON_STYLE), AttrValue::Style(style));
+        self
+    }
+
+    /// ### ellipsis
+    ///
+    /// Set the string (e.g. `…`) ending the rows which don't fit the tree area, instead of cutting them at the edge
+    pub fn ellipsis<S: AsRef<str>>(mut self, ellipsis: S) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_ELLIPSIS),
+            AttrValue::String(ellipsis.as_ref().to_string()),
+        );
+        self
+    }
+
+    /// ### style_per_depth
+    ///
+    /// Set styles patched on the rows which aren't highlighted, cycled by depth
+    pub fn style_per_depth(mut self, styles: Vec<Style>) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_STYLE_PER_DEPTH),
+            AttrValue::Payload(PropPayload::Vec(
+                styles.into_iter().map(PropValue::Style).collect(),
+            )),
+        );
+        self
+    }
+
+    /// ### track_row_changes
+    ///
+    /// Set whether to report the rows added and removed at each render, with `TreeState::rows_added` and
+    /// `TreeState::rows_removed`, so that they can be animated (see `TreeState::track_row_changes`)
+    pub fn track_row_changes(mut self, track: bool) -> Self {
+        self.states.track_row_changes(track);
+        self
+    }
+
+    /// ### scroll_step
+    ///
+    /// Set scroll step for scrolling command
+    pub fn scroll_step(mut self, step: usize) -> Self {
+        self.attr(Attribute::ScrollStep, AttrValue::Length(step));
+        self
+    }
+
+    /// ### scroll_step_percent
+    ///
+    /// Set scroll step for scrolling command as a percentage of the viewport height
+    pub fn scroll_step_percent(mut self, percent: u16) -> Self {
+        self.attr(Attribute::ScrollStep, AttrValue::Size(percent));
+        self
+    }
+
+    /// ### debounce
+    ///
+    /// Set the minimum interval between two applications of trees queued with `queue_tree`.
+    /// Default is 100ms
+    pub fn debounce(mut self, interval: Duration) -> Self {
+        self.debounce = interval;
+        self
+    }
+
+    /// ### unload_closed
+    ///
+    /// Unload the children of branches which have been closed for at least `after`, to keep memory bounded
+    /// for huge trees. When an unloaded branch is opened again, its children are provided back by `reload`.
+    /// Branches are unloaded on `Cmd::Tick` or calling `unload_closed_branches`
+    pub fn unload_closed<F>(mut self, after: Duration, reload: F) -> Self
+    where
+        F: FnMut(&Node<V>) -> Vec<Node<V>> + 'static,
+    {
+        self.unload_after = Some(after);
+        self.reload = Some(Box::new(reload));
+        self
+    }
+
+    /// ### display_map
+    ///
+    /// Set a callback which maps node values to the rendered label, without changing the tree nor the `NodeValue`
+    /// implementation (e.g. to toggle between full paths and file names, or to localize labels at display time).
+    /// Search and descriptions still use the values. It can be changed later with `set_display_map`
+    pub fn display_map<F>(mut self, map: F) -> Self
+    where
+        F: Fn(&V) -> Cow<'_, str> + 'static,
+    {
+        self.display_map = Some(Box::new(map));
+        self
+    }
+
+    /// ### cache_labels
+    ///
+    /// Set whether to cache the render parts of labels, so that values whose formatting is costly (e.g. dates or
+    /// humanized sizes) are formatted once instead of on each render. The cache is dropped whenever the tree is
+    /// changed (e.g. through `tree_mut`)
+    pub fn cache_labels(mut self, cache: bool) -> Self {
+        self.cache_labels = cache;
+        self
+    }
+
+    /// ### child_loader
+    ///
+    /// Set the loader of the children of lazy branches (see `NodeValue::is_branch_hint`), called when they're opened
+    /// without children. Until the loader provides them, or they're set with `set_children`, the node is rendered
+    /// with a spinner followed by `loading_text`, animated on `Cmd::Tick`
+    pub fn child_loader<L: ChildLoader<V> + 'static>(mut self, loader: L) -> Self {
+        self.loader = Some(Box::new(loader));
+        self
+    }
+
+    /// ### loading_text
+    ///
+    /// Set the text rendered after the spinner of the nodes whose children are being loaded. Default is "loading…"
+    pub fn loading_text<S: AsRef<str>>(mut self, text: S) -> Self {
+        self.loading_text = text.as_ref().to_string();
+        self
+    }
+
+    /// ### double_click_submit
+    ///
+    /// Submit the clicked node, when it's clicked twice within `interval` (see `click`)
+    pub fn double_click_submit(mut self, interval: Duration) -> Self {
+        self.double_click = Some(interval);
+        self
+    }
+
+    /// ### soft_selection
+    ///
+    /// Set whether the cursor is just a preview of the selection, which is committed on `Submit` or `TREE_CMD_COMMIT`
+    pub fn soft_selection(mut self, soft: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_SOFT_SELECTION),
+            AttrValue::Flag(soft),
+        );
+        self
+    }
+
+    /// ### preview_style
+    ///
+    /// Set style for the cursor when `soft_selection` is set
+    pub fn preview_style(mut self, style: Style) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_PREVIEW_STYLE),
+            AttrValue::Style(style),
+        );
+        self
+    }
+
+    /// ### scroll_jump
+    ///
+    /// Set the amount of rows to scroll at once when the selected node leaves the view
+    pub fn scroll_jump(mut self, rows: usize) -> Self {
+        self.attr(Attribute::Custom(TREE_SCROLL_JUMP), AttrValue::Length(rows));
+        self
+    }
+
+    /// ### ascii_only
+    ///
+    /// Set whether arrows and symbols are rendered with ASCII characters only
+    pub fn ascii_only(mut self, ascii: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_ASCII_ONLY), AttrValue::Flag(ascii));
+        self
+    }
+
+    /// ### smooth_scroll
+    ///
+    /// Set whether jumps larger than the view are animated over the following ticks
+    pub fn smooth_scroll(mut self, smooth: bool) -> Self {
+        self.attr(
+            Attribute::Custom(TREE_SMOOTH_SCROLL),
+            AttrValue::Flag(smooth),
+        );
+        self
+    }
+
+    /// ### tab_width
+    ///
+    /// Set the amount of columns between two tab stops in labels
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.attr(Attribute::Custom(TREE_TAB_WIDTH), AttrValue::Length(width));
+        self
+    }
+
+    /// ### fill_inner
+    ///
+    /// Set whether the tree style is painted only inside the borders
+    pub fn fill_inner(mut self, inner: bool) -> Self {
+        self.attr(Attribute::Custom(TREE_FILL_INNER), AttrValue::Flag(inner));
+        self
+    }
+
+    /// ### max_children
+    ///
+    /// Render only the first `max` children of each node, followed by a row which shows `max` more when submitted
+    pub fn max_children(mut self, max: usize) -> Self {
+        self.attr(Attribute::Custom(TREE_MAX_CHILDREN), AttrValue::Length(max));
+        self
+    }
+
+    /// ### drag_edge
+    ///
+    /// Set the amount of rows from the top and bottom edges of the view where the tree is scrolled while dragging
+    /// (see `drag`). Default is 1
+    pub fn drag_edge(mut self, rows: u16) -> Self {
+        self.drag_edge = rows;
+        self
+    }
+
+    /// ### recent_limit
+    ///
+    /// Set the maximum amount of recently submitted nodes to keep (see `recently_submitted`). Default is 16
+    pub fn recent_limit(mut self, limit: usize) -> Self {
+        self.recent_limit = limit;
+        self.recent.truncate(limit);
+        self
+    }
+
+    /// ### recent_decoration
+    ///
+    /// Set decoration to render for the recently submitted nodes which have no decoration of their own
+    pub fn recent_decoration(mut self, decoration: Decoration) -> Self {
+        self.recent_decoration = Some(decoration);
+        self
+    }
+
+    /// ### bookmark_decoration
+    ///
+    /// Set decoration to merge on top of the decoration of bookmarked nodes. Default is a "★ " prefix
+    pub fn bookmark_decoration(mut self, decoration: Decoration) -> Self {
+        self.bookmark_decoration = decoration;
+        self
+    }
+
+    /// ### description_parts
+    ///
+    /// Set the components of the description returned by `selected_description`, in order.
+    /// Default is label, kind, path and position
+    pub fn description_parts(mut self, parts: &[DescriptionPart]) -> Self {
+        self.description_parts = parts.to_vec();
+        self
+    }
+
+    /// ### dirty_badge
+    ///
+    /// Set the badge rendered on the collapsed ancestors of dirty nodes (see `mark_dirty`). Default is `●`
+    pub fn dirty_badge<S: AsRef<str>>(mut self, badge: S, style: Style) -> Self {
+        self.dirty_badge = (badge.as_ref().to_string(), style);
+        self
+    }
+
+    /// ### lazy_submit
+    ///
+    /// Set what `Submit` does on lazy branches, whose children haven't been loaded yet. Default is `LazySubmit::PassThrough`
+    pub fn lazy_submit(mut self, policy: LazySubmit) -> Self {
+        self.lazy_submit = policy;
+        self
+    }
+
+    /// ### remember_positions
+    ///
+    /// Remember the selected node and the scroll offset of up to `limit` trees, by the id of their root. When a tree with
+    /// a remembered root is set again (e.g. when navigating into a directory and back, setting a tree rooted on each
+    /// directory), its selection and scroll offset are restored. Default is 0 (disabled)
+    pub fn remember_positions(mut self, limit: usize) -> Self {
+        self.root_positions_limit = limit;
+        self.root_positions.truncate(limit);
+        self
+    }
+
+    /// ### undo_depth
+    ///
+    /// Enable the undo of state changes (selected node and open nodes) with `TREE_CMD_UNDO` and `TREE_CMD_REDO`,
+    /// keeping up to `depth` changes. Default is 0 (disabled)
+    pub fn undo_depth(mut self, depth: usize) -> Self {
+        self.undo_depth = depth;
+        self
+    }
+
+    /// ### decoration_placeholder
+    ///
+    /// Set decoration to render for nodes whose decoration hasn't been resolved yet with `set_decoration`
+    pub fn decoration_placeholder(mut self, decoration: Decoration) -> Self {
+        self.decoration_placeholder = Some(decoration);
+        self
+    }
+
+    /// ### with_tree
+    ///
+    /// Set tree to use as data
+    pub fn with_tree(mut self, tree: Tree<V>) -> Self {
+        #[cfg(all(debug_assertions, feature = "strict"))]
+        Self::check_unique_ids(tree.root());
+        self.tree = tree;
+        // Apply initial node, if set before the tree
+        if let Some(id) = self
+            .props
+            .get(Attribute::Custom(TREE_INITIAL_NODE))
+            .map(|x| x.unwrap_string())
+        {
+            self.select_initial_node(&id);
+        }
+        self
+    }
+
+    // -- tree methods
+
+    /// ### tree
+    ///
+    /// Get a reference to tree
+    pub fn tree(&self) -> &Tree<V> {
+        &self.tree
+    }
+
+    /// ### tree_mut
+    ///
+    /// Get mutable reference to tree
+    pub fn tree_mut(&mut self) -> &mut Tree<V> {
+        self.record_mutation("tree_mut");
+        self.states.invalidate_rows();
+        &mut self.tree
+    }
+
+    /// ### set_tree
+    ///
+    /// Set new tree in component.
+    /// Current state is preserved if `PRESERVE_STATE` is set to `AttrValue::Flag(true)`
+    pub fn set_tree(&mut self, tree: Tree<V>) {
+        self.swap_tree(tree);
+    }
+
+    /// ### swap_tree
+    ///
+    /// Set new tree in component, as `set_tree` does, and return the previous one,
+    /// so that it can be reused or compared with the new one
+    pub fn swap_tree(&mut self, tree: Tree<V>) -> Tree<V> {
+        self.record_mutation("set_tree");
+        #[cfg(all(debug_assertions, feature = "strict"))]
+        Self::check_unique_ids(tree.root());
+        let position = (
+            self.tree.root().id().to_string(),
+            self.states.selected().map(|x| x.to_string()),
+            self.states.offset(),
+        );
+        let prev = std::mem::replace(&mut self.tree, tree);
+        self.column_sort.tree_changed(self.tree.root_mut());
+        self.retain_existing_ids();
+        match self
+            .props
+            .get(Attribute::Custom(TREE_PRESERVE_SUBTREE))
+            .map(|x| x.unwrap_string())
+        {
+            Some(subtree) => self.states.subtree_changed(self.tree.root(), &subtree),
+            None => self.states.tree_changed(
+                self.tree.root(),
+                self.props
+                    .get_or(
+                        Attribute::Custom(TREE_PRESERVE_STATE),
+                        AttrValue::Flag(false),
+                    )
+                    .unwrap_flag(),
+            ),
+        }
+        if self.root_positions_limit > 0 && position.0 != *self.tree.root().id() {
+            self.restore_root_position(position);
+        }
+        self.publish_selection();
+        prev
+    }
+
+    /// ### retain_existing_ids
+    ///
+    /// Prune decorations, flags and the other data kept by node id, for the nodes which don't exist anymore
+    fn retain_existing_ids(&mut self) {
+        let root = self.tree.root();
+        self.decorations.retain(|id, _| root.query(id).is_some());
+        self.flags.retain(|id, _| root.query(id).is_some());
+        self.last_open.retain(|id, _| root.query(id).is_some());
+        self.committed = self.committed.take().filter(|id| root.query(id).is_some());
+        self.recent.retain(|id| root.query(id).is_some());
+        self.bookmarks.retain(|id| root.query(id).is_some());
+        self.search_matches.retain(|id| root.query(id).is_some());
+        self.dirty.retain(|id| root.query(id).is_some());
+        self.loading
+            .retain(|id| root.query(id).is_some_and(|x| x.is_leaf()));
+        self.navigation_lock = self
+            .navigation_lock
+            .take()
+            .filter(|id| root.query(id).is_some());
+        self.pending_move = self
+            .pending_move
+            .take()
+            .filter(|(id, _)| root.query(id).is_some());
+    }
+
+    /// ### restore_root_position
+    ///
+    /// Remember the position (selected node and scroll offset) of the previous tree, then restore the position of the
+    /// current tree, if its root has been shown before
+    fn restore_root_position(&mut self, prev: (String, Option<String>, usize)) {
+        self.root_positions.retain(|(id, _, _)| *id != prev.0);
+        self.root_positions.insert(0, prev);
+        self.root_positions.truncate(self.root_positions_limit);
+        let root = self.tree.root();
+        let Some(index) = self
+            .root_positions
+            .iter()
+            .position(|(id, _, _)| id == root.id())
+        else {
+            return;
+        };
+        let (_, selected, offset) = self.root_positions.remove(index);
+        if let Some(node) = selected.and_then(|x| root.query(&x)) {
+            self.states.select(root, node);
+            self.states
+                .set_viewport(offset, self.states.viewport_height());
+        }
+    }
+
+    /// ### sort_tree
+    ///
+    /// Sort the children of all the nodes in the tree with `compare`.
+    /// The selected node is kept, and so is its position in the viewport
+    pub fn sort_tree<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&Node<V>, &Node<V>) -> Ordering,
+    {
+        /// ### sort_tree_r
+        ///
+        /// Sort children of `node` and of all its descendants
+        fn sort_tree_r<V, F>(node: &mut Node<V>, compare: &mut F)
+        where
+            F: FnMut(&Node<V>, &Node<V>) -> Ordering,
+        {
+            node.sort(&mut *compare);
+            node.iter_mut().for_each(|x| sort_tree_r(x, compare));
+        }
+        // Row of the selected node in the viewport
+        let anchor = self
+            .states
+            .selected_index(self.tree.root())
+            .map(|x| x.saturating_sub(self.states.offset()));
+        self.record_mutation("sort_tree");
+        sort_tree_r(self.tree.root_mut(), &mut compare);
+        self.states.invalidate_rows();
+        if let (Some(anchor), Some(index)) = (anchor, self.states.selected_index(self.tree.root()))
+        {
+            self.states
+                .set_viewport(index.saturating_sub(anchor), self.states.viewport_height());
+        }
+    }
+
+    /// ### sort_column
+    ///
+    /// Sort the siblings by the column with index `column` (see `TREE_COLUMNS`) in `order`, keeping the tree structure.
+    /// If `order` is `None`, the tree order is restored
+    pub fn sort_column(&mut self, column: usize, order: Option<SortOrder>) {
+        self.column_sort
+            .set(self.tree.root(), order.map(|order| (column, order)));
+        let column_sort = std::mem::take(&mut self.column_sort);
+        self.sort_tree(|a, b| column_sort.compare(a, b));
+        self.column_sort = column_sort;
+    }
+
+    /// ### sorted_column
+    ///
+    /// Get the column the siblings are sorted by, with its order, if any
+    pub fn sorted_column(&self) -> Option<(usize, SortOrder)> {
+        self.column_sort.sorted()
+    }
+
+    /// ### matching_ids
+    ///
+    /// Get the ids of the nodes whose `NodeValue::search_text` contains `query` (case-insensitive),
+    /// in the order they appear in the tree
+    pub fn matching_ids(&self, query: &str) -> Vec<String> {
+        self.matching_ids_with(&CaseInsensitiveMatcher::new(query))
+    }
+
+    /// ### matching_ids_with
+    ///
+    /// Get the ids of the nodes whose `NodeValue::search_text` matches `matcher`, in the order they appear in the tree
+    pub fn matching_ids_with<M: Matcher + ?Sized>(&self, matcher: &M) -> Vec<String> {
+        /// ### matching_ids_r
+        ///
+        /// Push to `ids` the ids of `node` and its descendants which match `matcher`
+        fn matching_ids_r<V: NodeValue, M: Matcher + ?Sized>(
+            node: &Node<V>,
+            matcher: &M,
+            ids: &mut Vec<String>,
+        ) {
+            if node
+                .value()
+                .search_text()
+                .map(|x| matcher.is_match(&x))
+                .unwrap_or(false)
+            {
+                ids.push(node.id().to_string());
+            }
+            node.iter().for_each(|x| matching_ids_r(x, matcher, ids));
+        }
+        let mut ids = Vec::new();
+        matching_ids_r(self.tree.root(), matcher, &mut ids);
+        ids
+    }
+
+    /// ### set_matcher
+    ///
+    /// Set the matcher whose matches are highlighted in labels with `TREE_MATCH_STYLE` (e.g. a `RegexMatcher`
+    /// or a `FuzzyMatcher`). It takes precedence over `TREE_MATCH_QUERY`
+    pub fn set_matcher<M: Matcher + 'static>(&mut self, matcher: M) {
+        self.matcher = Some(Box::new(matcher));
+    }
+
+    /// ### clear_matcher
+    ///
+    /// Remove the matcher set with `set_matcher`; `TREE_MATCH_QUERY` is highlighted again, if set
+    pub fn clear_matcher(&mut self) {
+        self.matcher = None;
+    }
+
+    /// ### set_display_map
+    ///
+    /// Set the callback which maps node values to the rendered label (see `display_map`)
+    pub fn set_display_map<F>(&mut self, map: F)
+    where
+        F: Fn(&V) -> Cow<'_, str> + 'static,
+    {
+        self.display_map = Some(Box::new(map));
+    }
+
+    /// ### clear_display_map
+    ///
+    /// Remove the callback set with `display_map`; values are rendered with their render parts again
+    pub fn clear_display_map(&mut self) {
+        self.display_map = None;
+    }
+
+    // -- search
+
+    /// ### search
+    ///
+    /// Search the nodes whose `NodeValue::search_text` (the rendered label by default) contains `query`, ignoring case,
+    /// or matches it fuzzily if `TREE_SEARCH_FUZZY` is set. The first match from the selected node, in the order nodes
+    /// appear in the tree, gets selected and its ancestors are opened; typing ahead keeps the selection as long as it still matches.
+    /// Returns `Changed` if the selection has changed
+    pub fn search(&mut self, query: &str) -> CmdResult {
+        match self.is_fuzzy_search() {
+            true => self.search_with(&FuzzyMatcher::new(query)),
+            false => self.search_with(&CaseInsensitiveMatcher::new(query)),
+        }
+    }
+
+    /// ### search_with
+    ///
+    /// As `search`, with the matching semantics of `matcher`
+    pub fn search_with<M: Matcher + ?Sized>(&mut self, matcher: &M) -> CmdResult {
+        self.search_matches = self.matching_ids_with(matcher);
+        self.cycle_to_match(true, true)
+    }
+
+    /// ### search_next
+    ///
+    /// Select the next match of the last search after the selected node; wraps around the tree
+    pub fn search_next(&mut self) -> CmdResult {
+        self.cycle_to_match(true, false)
+    }
+
+    /// ### search_prev
+    ///
+    /// Select the previous match of the last search before the selected node; wraps around the tree
+    pub fn search_prev(&mut self) -> CmdResult {
+        self.cycle_to_match(false, false)
+    }
+
+    /// ### search_matches
+    ///
+    /// Get the ids of the nodes matched by the last search, in the order they appear in the tree
+    pub fn search_matches(&self) -> &[String] {
+        &self.search_matches
+    }
+
+    /// ### clear_search
+    ///
+    /// Forget the matches of the last search
+    pub fn clear_search(&mut self) {
+        self.search_matches.clear();
+    }
+
+    /// ### queue_tree
+    ///
+    /// Queue a new tree to be applied with `apply_queued`.
+    /// If another tree was already queued, it gets replaced, so only the latest one is applied
+    pub fn queue_tree(&mut self, tree: Tree<V>) {
+        self.queued_tree = Some(tree);
+    }
+
+    /// ### has_queued_tree
+    ///
+    /// Returns whether there is a tree waiting to be applied
+    pub fn has_queued_tree(&self) -> bool {
+        self.queued_tree.is_some()
+    }
+
+    /// ### apply_queued
+    ///
+    /// Apply the queued tree, as `set_tree` would do, if the debounce interval has elapsed since the
+    /// last application. Returns whether the tree has been applied
+    pub fn apply_queued(&mut self, now: Instant) -> bool {
+        let elapsed = self
+            .last_queued_apply
+            .map(|last| now.saturating_duration_since(last) >= self.debounce)
+            .unwrap_or(true);
+        if !elapsed {
+            return false;
+        }
+        match self.queued_tree.take() {
+            Some(tree) => {
+                self.set_tree(tree);
+                self.last_queued_apply = Some(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ### unload_closed_branches
+    ///
+    /// Unload the children of branches which have been closed for the interval set with `unload_closed`.
+    /// Returns the ids of the unloaded branches
+    pub fn unload_closed_branches(&mut self, now: Instant) -> Vec<String> {
+        let Some(after) = self.unload_after else {
+            return Vec::new();
+        };
+        let expired: Vec<String> = self
+            .last_open
+            .iter()
+            .filter(|(_, last)| now.saturating_duration_since(**last) >= after)
+            .map(|(id, _)| id.to_string())
+            .collect();
+        let mut unloaded = Vec::new();
+        for id in expired {
+            self.last_open.remove(&id);
+            // Node must be still closed and must not contain the selected node
+            let closed = self
+                .tree
+                .root()
+                .query(&id)
+                .filter(|node| !node.is_leaf() && self.states.is_closed(node))
+                .map(|node| match self.states.selected() {
+                    Some(selected) => selected == id || node.query(&selected.to_string()).is_none(),
+                    None => true,
+                })
+                .unwrap_or(false);
+            if !closed {
+                continue;
+            }
+            if let Some(node) = self.tree.root_mut().query_mut(&id) {
+                debug_log!("unload {:?}", id);
+                node.clear();
+                #[cfg(debug_assertions)]
+                {
+                    self.last_mutation = "unload_closed_branches";
+                }
+                self.states.set_unloaded(&id, true);
+                unloaded.push(id);
+            }
+        }
+        unloaded
+    }
+
+    /// ### click
+    ///
+    /// Handle a mouse click at the provided screen position (e.g. from `MouseEventKind::Down`).
+    /// The clicked node gets selected; if `double_click_submit` is set and the node has been clicked twice
+    /// within the interval, `CmdResult::Submit` is returned
+    pub fn click(&mut self, column: u16, row: u16, now: Instant) -> CmdResult {
+        let Some(node) = self
+            .states
+            .row_at(column, row)
+            .and_then(|x| self.states.node_at_row(self.tree.root(), x))
+        else {
+            return CmdResult::None;
+        };
+        let prev = self.states.selected().map(|x| x.to_string());
+        let id = node.id().to_string();
+        let double_click = match (self.double_click, self.last_click.take()) {
+            (Some(interval), Some((last_id, last))) => {
+                last_id == id && now.saturating_duration_since(last) <= interval
+            }
+            _ => false,
+        };
+        if double_click {
+            return self.submit();
+        }
+        self.states.select(self.tree.root(), node);
+        self.last_click = Some((id, now));
+        self.changed(prev.as_deref())
+    }
+
+    /// ### on_mouse
+    ///
+    /// Handle a mouse event, at a screen position of the last render:
+    /// a left click selects the clicked node (see `click`); clicking the arrow of a branch, or clicking a node twice
+    /// within 500ms if `double_click_submit` is not set, also opens or closes it.
+    /// The scroll wheel scrolls the tree, as `Cmd::Scroll` does
+    pub fn on_mouse(&mut self, event: MouseEvent, now: Instant) -> CmdResult {
+        let (column, row) = (event.column, event.row);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let double_click = self.double_click.is_none()
+                    && self
+                        .states
+                        .row_at(column, row)
+                        .and_then(|x| self.states.node_at_row(self.tree.root(), x))
+                        .zip(self.last_click.as_ref())
+                        .is_some_and(|(node, (last_id, last))| {
+                            node.id() == last_id
+                                && now.saturating_duration_since(*last) <= DOUBLE_CLICK_INTERVAL
+                        });
+                let toggle = double_click || self.states.is_arrow_at(column, row);
+                let result = self.click(column, row, now);
+                if !toggle || matches!(result, CmdResult::Submit(_)) {
+                    return result;
+                }
+                // A third click is not another double click
+                if double_click {
+                    self.last_click = None;
+                }
+                match self.toggle_selected() {
+                    CmdResult::None => result,
+                    toggled => toggled,
+                }
+            }
+            MouseEventKind::ScrollUp => self.perform(Cmd::Scroll(Direction::Up)),
+            MouseEventKind::ScrollDown => self.perform(Cmd::Scroll(Direction::Down)),
+            _ => CmdResult::None,
+        }
+    }
+
+    /// ### drag
+    ///
+    /// Move the drag cursor to the provided position, selecting the node under it (e.g. the drop target).
+    /// While the cursor lingers in the rows at the top or bottom edge of the view (see `drag_edge`),
+    /// the tree is scrolled by one row on each `nudge_drag` (or `Cmd::Tick`) until `end_drag` is called
+    pub fn drag(&mut self, column: u16, row: u16) -> CmdResult {
+        self.drag = Some((column, row));
+        let Some(node) = self
+            .states
+            .row_at(column, row)
+            .and_then(|x| self.states.node_at_row(self.tree.root(), x))
+        else {
+            return CmdResult::None;
+        };
+        let prev = self.states.selected().map(|x| x.to_string());
+        self.states.select(self.tree.root(), node);
+        self.changed(prev.as_deref())
+    }
+
+    /// ### nudge_drag
+    ///
+    /// If a drag is in progress and the cursor is on the top or bottom edge of the view (or beyond it),
+    /// scroll the tree by one row in that direction, selecting the row under the cursor
+    pub fn nudge_drag(&mut self) -> CmdResult {
+        let Some((_, row)) = self.drag else {
+            return CmdResult::None;
+        };
+        let area = self.states.viewport_area();
+        if area.height == 0 {
+            return CmdResult::None;
+        }
+        let prev = self.states.selected().map(|x| x.to_string());
+        if row < area.y.saturating_add(self.drag_edge) {
+            self.states.move_up(self.tree.root());
+        } else if row >= area.bottom().saturating_sub(self.drag_edge) {
+            self.states.move_down(self.tree.root());
+        }
+        self.changed(prev.as_deref())
+    }
+
+    /// ### end_drag
+    ///
+    /// Stop dragging; returns the id of the selected node (i.e. the drop target), if a drag was in progress
+    pub fn end_drag(&mut self) -> Option<String> {
+        self.drag
+            .take()
+            .and_then(|_| self.states.selected().map(|x| x.to_string()))
+    }
+
+    /// ### set_children
+    ///
+    /// Replace the children of the node with `id` (e.g. once they have been loaded in background after the node has
+    /// been opened, see `child_loader`), keeping the state. If the selected node was among the removed descendants,
+    /// the node is selected. Returns whether the node exists
+    pub fn set_children<S: AsRef<str>>(&mut self, id: S, children: Vec<Node<V>>) -> bool {
+        let id = id.as_ref().to_string();
+        self.record_mutation("set_children");
+        let Some(node) = self.tree.root_mut().query_mut(&id) else {
+            return false;
+        };
+        node.clear();
+        children.into_iter().for_each(|x| node.add_child(x));
+        self.column_sort.children_changed(node);
+        #[cfg(all(debug_assertions, feature = "strict"))]
+        Self::check_unique_ids(self.tree.root());
+        self.loading.remove(&id);
+        self.states.invalidate_rows();
+        let root = self.tree.root();
+        if self
+            .states
+            .selected()
+            .is_some_and(|x| root.query(&x.to_string()).is_none())
+        {
+            if let Some(node) = root.query(&id) {
+                self.states.select(root, node);
+            }
+        }
+        self.states.tree_changed(root, true);
+        self.publish_selection();
+        true
+    }
+
+    /// ### prune
+    ///
+    /// Remove the node with `id` and its descendants, repairing the state: if the selected node was in the removed
+    /// subtree, the parent of the removed node is selected, and the removed nodes are dropped from the open ones.
+    /// Returns whether the node has been removed; root can't be removed
+    pub fn prune<S: AsRef<str>>(&mut self, id: S) -> bool {
+        let id = id.as_ref().to_string();
+        let root = self.tree.root();
+        let Some(parent) = root.parent(&id).map(|x| x.id().to_string()) else {
+            return false;
+        };
+        let selection_removed = self.states.selected().is_some_and(|selected| {
+            root.query(&id)
+                .is_some_and(|x| x.query(&selected.to_string()).is_some())
+        });
+        self.record_mutation("prune");
+        if let Some(parent) = self.tree.root_mut().query_mut(&parent) {
+            parent.remove_child(&id);
+        }
+        self.retain_existing_ids();
+        let root = self.tree.root();
+        self.states.tree_changed(root, true);
+        if selection_removed {
+            if let Some(parent) = root.query(&parent) {
+                self.states.select(root, parent);
+            }
+            self.publish_selection();
+        }
+        true
+    }
+
+    /// ### is_loading
+    ///
+    /// Returns whether the children of the node with `id` are being loaded by the `child_loader`
+    pub fn is_loading<S: AsRef<str>>(&self, id: S) -> bool {
+        self.loading.contains(id.as_ref())
+    }
+
+    /// ### tree_state
+    ///
+    /// Get a reference to the current tree state
+    pub fn tree_state(&self) -> &TreeState {
+        &self.states
+    }
+
+    /// ### tree_state_mut
+    ///
+    /// Get a mutable reference to the current tree state, e.g. to clear the selection, to open or close nodes,
+    /// or to reset the whole state with `TreeState::default()`.
+    /// Changes made through it are not published to `watch_selection` and are not recorded for undo
+    pub fn tree_state_mut(&mut self) -> &mut TreeState {
+        &mut self.states
+    }
+
+    /// ### selected_node
+    ///
+    /// Get a reference to the selected node, if any
+    pub fn selected_node(&self) -> Option<&Node<V>> {
+        self.states
+            .selected()
+            .and_then(|id| self.tree.root().query(&id.to_string()))
+    }
+
+    /// ### children_ids
+    ///
+    /// Get the ids of the children of node with `id`, in order. Returns `None` if the node doesn't exist
+    pub fn children_ids<S: AsRef<str>>(&self, id: S) -> Option<Vec<&str>> {
+        self.tree
+            .root()
+            .query(&id.as_ref().to_string())
+            .map(|node| node.iter().map(|x| x.id().as_str()).collect())
+    }
+
+    /// ### child_count
+    ///
+    /// Get the amount of children of node with `id` (e.g. to tell whether a directory has already been loaded).
+    /// Returns `None` if the node doesn't exist
+    pub fn child_count<S: AsRef<str>>(&self, id: S) -> Option<usize> {
+        self.tree
+            .root()
+            .query(&id.as_ref().to_string())
+            .map(|node| node.children().len())
+    }
+
+    /// ### selected_description
+    ///
+    /// Get a readable description of the selected node, to be announced by screen readers
+    /// (e.g. "readme.md, file, inside home slash omar, item 3 of 7"). Returns an empty string if no node is selected
+    pub fn selected_description(&self) -> String {
+        self.selected_node()
+            .map(|node| {
+                description::describe(
+                    self.tree.root(),
+                    &self.states,
+                    node,
+                    &self.description_parts,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// ### selected_subtree
+    ///
+    /// Returns a standalone tree made up of the selected node and all its descendants.
+    /// Returns `None` if no node is selected
+    pub fn selected_subtree(&self) -> Option<Tree<V>>
+    where
+        V: Clone,
+    {
+        self.selected_node().map(|node| Tree::new(node.clone()))
+    }
+
+    // -- decorations
+
+    /// ### set_decoration
+    ///
+    /// Set the resolved decoration for node with provided `id`.
+    /// Decorations of nodes which don't exist anymore are pruned when the tree changes
+    pub fn set_decoration<S: AsRef<str>>(&mut self, id: S, decoration: Decoration) {
+        self.decorations.insert(id.as_ref().to_string(), decoration);
+    }
+
+    /// ### add_decorator
+    ///
+    /// Register a decorator, run after the ones already registered.
+    /// Its decorations are merged on top of the node decoration and of the previous decorators (see `Decoration::merge`)
+    pub fn add_decorator<D: NodeDecorator<V> + 'static>(&mut self, decorator: D) {
+        self.decorators.push(Box::new(decorator));
+    }
+
+    /// ### clear_decoration
+    ///
+    /// Remove decoration for node with provided `id`; the placeholder will be rendered again
+    pub fn clear_decoration<S: AsRef<str>>(&mut self, id: S) {
+        self.decorations.remove(id.as_ref());
+    }
+
+    /// ### decoration
+    ///
+    /// Get the resolved decoration for node with provided `id`
+    pub fn decoration<S: AsRef<str>>(&self, id: S) -> Option<&Decoration> {
+        self.decorations.get(id.as_ref())
+    }
+
+    // -- flags
+
+    /// ### set_flag
+    ///
+    /// Set `flag` on node with provided `id`.
+    /// Flags of nodes which don't exist anymore are pruned when the tree changes
+    pub fn set_flag<S: AsRef<str>>(&mut self, id: S, flag: Flag) {
+        self.flags
+            .entry(id.as_ref().to_string())
+            .or_default()
+            .insert(flag);
+    }
+
+    /// ### unset_flag
+    ///
+    /// Remove `flag` from node with provided `id`
+    pub fn unset_flag<S: AsRef<str>>(&mut self, id: S, flag: Flag) {
+        if let Some(flags) = self.flags.get_mut(id.as_ref()) {
+            flags.remove(&flag);
+            if flags.is_empty() {
+                self.flags.remove(id.as_ref());
+            }
+        }
+    }
+
+    /// ### toggle_flag
+    ///
+    /// Toggle `flag` on node with provided `id`. Returns whether the flag is now set
+    pub fn toggle_flag<S: AsRef<str>>(&mut self, id: S, flag: Flag) -> bool {
+        match self.has_flag(id.as_ref(), flag) {
+            true => {
+                self.unset_flag(id, flag);
+                false
+            }
+            false => {
+                self.set_flag(id, flag);
+                true
+            }
+        }
+    }
+
+    /// ### has_flag
+    ///
+    /// Returns whether node with provided `id` has `flag` set
+    pub fn has_flag<S: AsRef<str>>(&self, id: S, flag: Flag) -> bool {
+        self.flags
+            .get(id.as_ref())
+            .map(|x| x.contains(&flag))
+            .unwrap_or(false)
+    }
+
+    /// ### ids_with_flag
+    ///
+    /// Get the ids of all the nodes with `flag` set, in the order they appear in the tree
+    pub fn ids_with_flag(&self, flag: Flag) -> Vec<String> {
+        self.ids_with_flag_name(flag.name())
+    }
+
+    /// ### selected_ids
+    ///
+    /// Get the ids of the nodes selected for bulk operations (i.e. with `Flag::MARKED` set), in the order they
+    /// appear in the tree
+    pub fn selected_ids(&self) -> Vec<String> {
+        self.ids_with_flag(Flag::MARKED)
+    }
+
+    /// ### recently_submitted
+    ///
+    /// Get the ids of the recently submitted nodes, from the most recent
+    pub fn recently_submitted(&self) -> &[String] {
+        &self.recent
+    }
+
+    /// ### set_leaf
+    ///
+    /// Set whether the node with `id` is treated as a leaf, even if it has children: its children are never shown
+    /// nor selected (e.g. archives or directories which can't be browsed)
+    pub fn set_leaf(&mut self, id: &str, leaf: bool) {
+        self.states.set_forced_leaf(self.tree.root(), id, leaf);
+        self.publish_selection();
+    }
+
+    /// ### mark_dirty
+    ///
+    /// Mark node with `id` as dirty (e.g. unread or changed): a badge is rendered on each collapsed ancestor
+    /// containing it, until the node is revealed or selected
+    pub fn mark_dirty<S: AsRef<str>>(&mut self, id: S) {
+        self.dirty.insert(id.as_ref().to_string());
+        self.clear_revealed_dirty();
+    }
+
+    /// ### clear_dirty
+    ///
+    /// Remove the dirty mark from node with `id`
+    pub fn clear_dirty<S: AsRef<str>>(&mut self, id: S) {
+        self.dirty.remove(id.as_ref());
+    }
+
+    /// ### is_dirty
+    ///
+    /// Returns whether node with `id` is marked as dirty and hasn't been revealed yet
+    pub fn is_dirty<S: AsRef<str>>(&self, id: S) -> bool {
+        self.dirty.contains(id.as_ref())
+    }
+
+    /// ### bookmark
+    ///
+    /// Bookmark node with `id`, so that it can be reached with `TREE_CMD_NEXT_BOOKMARK` and `TREE_CMD_PREV_BOOKMARK`.
+    /// Returns whether the node exists
+    pub fn bookmark<S: AsRef<str>>(&mut self, id: S) -> bool {
+        let id = id.as_ref().to_string();
+        if self.tree.root().query(&id).is_none() {
+            return false;
+        }
+        if !self.bookmarks.contains(&id) {
+            self.bookmarks.push(id);
+        }
+        true
+    }
+
+    /// ### remove_bookmark
+    ///
+    /// Remove the bookmark of node with `id`
+    pub fn remove_bookmark<S: AsRef<str>>(&mut self, id: S) {
+        self.bookmarks.retain(|x| x != id.as_ref());
+    }
+
+    /// ### is_bookmarked
+    ///
+    /// Returns whether node with `id` is bookmarked
+    pub fn is_bookmarked<S: AsRef<str>>(&self, id: S) -> bool {
+        self.bookmarks.iter().any(|x| x == id.as_ref())
+    }
+
+    /// ### bookmarks
+    ///
+    /// Get the ids of the bookmarked nodes, in the order they have been bookmarked
+    pub fn bookmarks(&self) -> &[String] {
+        &self.bookmarks
+    }
+
+    /// ### persisted_state
+    ///
+    /// Get the open nodes, the selected node and the bookmarks, to save them between sessions
+    /// (see `restore_persisted_state`)
+    pub fn persisted_state(&self) -> PersistedState {
+        PersistedState::new(self.states.snapshot(), self.bookmarks.clone())
+    }
+
+    /// ### restore_persisted_state
+    ///
+    /// Restore the open nodes, the selected node and the bookmarks saved with `persisted_state`.
+    /// Nodes which don't exist anymore in the tree are ignored
+    pub fn restore_persisted_state(&mut self, state: PersistedState) {
+        let (snapshot, bookmarks) = state.into_parts();
+        self.states.restore(self.tree.root(), snapshot);
+        let root = self.tree.root();
+        self.bookmarks = bookmarks
+            .into_iter()
+            .filter(|id| root.query(id).is_some())
+            .collect();
+        self.commit();
+        self.publish_selection();
+    }
+
+    /// ### lock_navigation
+    ///
+    /// Restrict navigation (`Move`, `Scroll` and `GoTo`) to the subtree of node with `id`, e.g. when the user
+    /// must pick a node inside a specific branch. The rest of the tree is still rendered.
+    /// If the selected node is outside of the subtree, the node with `id` is selected.
+    /// Returns whether the node exists
+    pub fn lock_navigation<S: AsRef<str>>(&mut self, id: S) -> bool {
+        let id = id.as_ref().to_string();
+        let Some(node) = self.tree.root().query(&id) else {
+            return false;
+        };
+        let inside = self
+            .states
+            .selected()
+            .map(|x| node.query(&x.to_string()).is_some())
+            .unwrap_or(false);
+        if !inside {
+            self.states.select(self.tree.root(), node);
+            self.publish_selection();
+        }
+        self.navigation_lock = Some(id);
+        true
+    }
+
+    /// ### unlock_navigation
+    ///
+    /// Allow navigation on the whole tree again
+    pub fn unlock_navigation(&mut self) {
+        self.navigation_lock = None;
+    }
+
+    /// ### navigation_lock
+    ///
+    /// Get the id of the node whose subtree navigation is restricted to, if any
+    pub fn navigation_lock(&self) -> Option<&str> {
+        self.navigation_lock.as_deref()
+    }
+
+    /// ### flash_message
+    ///
+    /// Render `text` at the bottom of the tree area (e.g. "copied path" or "permission denied") for the next `renders`
+    /// renders, instead of the footer
+    pub fn flash_message<S: AsRef<str>>(&mut self, text: S, renders: usize) {
+        self.flash = Some((text.as_ref().to_string(), renders)).filter(|_| renders > 0);
+    }
+
+    /// ### clear_flash_message
+    ///
+    /// Remove the message set with `flash_message` before it expires
+    pub fn clear_flash_message(&mut self) {
+        self.flash = None;
+    }
+
+    /// ### begin_batch
+    ///
+    /// Begin a batch of updates (e.g. set tree, reveal node, set flags), which must appear atomic to the application:
+    /// until `end_batch` is called, commands don't return `Changed`, the selection is not published to watchers,
+    /// state changes are not recorded for undo and the state is not validated. Batches can be nested
+    pub fn begin_batch(&mut self) {
+        if self.batch_depth == 0 {
+            self.batch_start = Some(self.states.snapshot());
+        }
+        self.batch_depth += 1;
+    }
+
+    /// ### end_batch
+    ///
+    /// End the batch begun with `begin_batch`. When the outermost batch ends, returns `Changed` if the selected
+    /// node has changed since the batch began, `None` otherwise
+    pub fn end_batch(&mut self) -> CmdResult {
+        self.batch_depth = self.batch_depth.saturating_sub(1);
+        if self.batch_depth > 0 {
+            return CmdResult::None;
+        }
+        let Some(start) = self.batch_start.take() else {
+            return CmdResult::None;
+        };
+        #[cfg(debug_assertions)]
+        self.check_consistency();
+        let prev = start.selected.clone();
+        if self.undo_depth > 0 {
+            self.record_undo(start);
+        }
+        self.clear_revealed_dirty();
+        match self.states.selected() {
+            Some(id) if Some(id) != prev.as_deref() => {
+                self.publish_selection();
+                self.selected_ticks = 0;
+                CmdResult::Changed(self.cursor_state())
+            }
+            _ => CmdResult::None,
+        }
+    }
+
+    /// ### batch
+    ///
+    /// Run `updates` as a batch (see `begin_batch`) and return the result of `end_batch`
+    pub fn batch<F>(&mut self, updates: F) -> CmdResult
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.begin_batch();
+        updates(self);
+        self.end_batch()
+    }
+
+    /// ### watch_selection
+    ///
+    /// Get a `tokio::sync::watch` receiver of the changes of the selected node, e.g. to be moved to a background task.
+    /// Requires the `watch` feature
+    #[cfg(any(test, feature = "watch"))]
+    pub fn watch_selection(&mut self) -> watch::SelectionReceiver {
+        let selected = self.states.selected().map(|x| x.to_string());
+        self.selection_sender
+            .get_or_insert_with(|| watch::SelectionSender::new(selected))
+            .subscribe()
+    }
+
+    /// ### clear_flags
+    ///
+    /// Remove all flags from all nodes
+    pub fn clear_flags(&mut self) {
+        self.flags.clear();
+    }
+
+    // -- theme
+
+    /// ### apply_theme
+    ///
+    /// Apply all the colors and styles of `theme` at once
+    pub fn apply_theme(&mut self, theme: TreeTheme) {
+        let borders = self
+            .props
+            .get_or(Attribute::Borders, AttrValue::Borders(Borders::default()))
+            .unwrap_borders()
+            .color(theme.borders);
+        self.attr(Attribute::Foreground, AttrValue::Color(theme.foreground));
+        self.attr(Attribute::Background, AttrValue::Color(theme.background));
+        self.attr(
+            Attribute::HighlightedColor,
+            AttrValue::Color(theme.highlighted_color.unwrap_or(theme.foreground)),
+        );
+        self.attr(Attribute::FocusStyle, AttrValue::Style(theme.inactive));
+        self.attr(Attribute::Borders, AttrValue::Borders(borders));
+        self.attr(
+            Attribute::TextProps,
+            AttrValue::TextModifiers(theme.modifiers),
+        );
+        self.attr(
+            Attribute::Custom(TREE_GUIDES_STYLE),
+            AttrValue::Style(theme.guides),
+        );
+        self.attr(
+            Attribute::Custom(TREE_ICON_STYLE),
+            AttrValue::Style(theme.icons),
+        );
+    }
+
+    // -- private
+
+    /// ### changed
+    ///
+    /// Returns whether selectd node has changed.
+    /// If the selection has changed, the expand on select policy is applied
+    fn changed(&mut self, prev: Option<&str>) -> CmdResult {
+        match self.states.selected() {
+            None => CmdResult::None,
+            id if id != prev => {
+                self.publish_selection();
+                self.selected_ticks = 0;
+                if self.expand_on_select_ticks() == Some(0) {
+                    self.open_selected();
+                }
+                CmdResult::Changed(self.cursor_state())
+            }
+            _ => CmdResult::None,
+        }
+    }
+
+    /// ### clear_revealed_dirty
+    ///
+    /// Remove the dirty mark from the nodes which are selected or whose ancestors are all open
+    fn clear_revealed_dirty(&mut self) {
+        if self.dirty.is_empty() {
+            return;
+        }
+        let root = self.tree.root();
+        let states = &self.states;
+        self.dirty.retain(|id| {
+            let Some(node) = root.query(id) else {
+                return false;
+            };
+            if states.is_selected(node) {
+                return false;
+            }
+            let mut current = node;
+            while let Some(parent) = root.parent(current.id()) {
+                if states.is_closed(parent) {
+                    return true;
+                }
+                current = parent;
+            }
+            false
+        });
+    }
+
+    /// ### dirty_ancestors
+    ///
+    /// Get the ids of the collapsed ancestors of the dirty nodes
+    fn dirty_ancestors(&self) -> HashSet<String> {
+        let root = self.tree.root();
+        let mut ancestors = HashSet::new();
+        for id in self.dirty.iter() {
+            let mut current = id.as_str();
+            while let Some(parent) = root.parent(&current.to_string()) {
+                if self.states.is_closed(parent) {
+                    ancestors.insert(parent.id().to_string());
+                }
+                current = parent.id();
+            }
+        }
+        ancestors
+    }
+
+    /// ### cycle_bookmark
+    ///
+    /// Select the next bookmarked node after the selected one, in the order nodes appear in the tree,
+    /// or the previous one if `forward` is false. Wraps around the tree; its ancestors are opened
+    fn cycle_bookmark(&mut self, forward: bool) -> CmdResult {
+        let target = self.next_in_tree_order(&self.bookmarks, forward, false);
+        self.select_target(target)
+    }
+
+    /// ### cycle_to_match
+    ///
+    /// Select the next match of the last search from the selected one, or the previous one if `forward` is false;
+    /// the selected node is a candidate itself if `inclusive`. Wraps around the tree; its ancestors are opened
+    fn cycle_to_match(&mut self, forward: bool, inclusive: bool) -> CmdResult {
+        let target = self.next_in_tree_order(&self.search_matches, forward, inclusive);
+        self.select_target(target)
+    }
+
+    /// ### next_in_tree_order
+    ///
+    /// Get the first of `targets` after the selected node, in the order nodes appear in the tree,
+    /// or in the reverse order if `forward` is false. The selected node is a candidate only if `inclusive`.
+    /// Wraps around the tree
+    fn next_in_tree_order(
+        &self,
+        targets: &[String],
+        forward: bool,
+        inclusive: bool,
+    ) -> Option<String> {
+        /// ### preorder_r
+        ///
+        /// Push to `ids` the ids of `node` and its descendants, in the order they appear in the tree
+        fn preorder_r<'a, V>(node: &'a Node<V>, ids: &mut Vec<&'a String>) {
+            ids.push(node.id());
+            node.iter().for_each(|x| preorder_r(x, ids));
+        }
+        if targets.is_empty() {
+            return None;
+        }
+        let targets: HashSet<&String> = targets.iter().collect();
+        let mut ids = Vec::new();
+        preorder_r(self.tree.root(), &mut ids);
+        let len = ids.len();
+        let start = self
+            .states
+            .selected()
+            .and_then(|selected| ids.iter().position(|x| *x == selected))
+            .unwrap_or(0);
+        let offsets = match inclusive {
+            true => 0..len,
+            false => 1..len + 1,
+        };
+        offsets
+            .map(|i| match forward {
+                true => (start + i) % len,
+                false => (start + len - i) % len,
+            })
+            .map(|i| ids[i])
+            .find(|id| targets.contains(id))
+            .cloned()
+    }
+
+    /// ### select_target
+    ///
+    /// Select the node with `id`, if any, opening its ancestors; returns `Changed` if the selection has changed
+    fn select_target(&mut self, id: Option<String>) -> CmdResult {
+        let prev = self.states.selected().map(|x| x.to_string());
+        if let Some(node) = id.and_then(|id| self.tree.root().query(&id)) {
+            self.states.select(self.tree.root(), node);
+        }
+        self.changed(prev.as_deref())
+    }
+
+    /// ### clamp_navigation
+    ///
+    /// Move the selection back inside the subtree navigation is locked to, if it has left it:
+    /// to the root of the subtree if the selection went above it, to its last visible node otherwise
+    fn clamp_navigation(&mut self) {
+        let root = self.tree.root();
+        let Some(lock) = self.navigation_lock.as_ref().and_then(|x| root.query(x)) else {
+            return;
+        };
+        let selected = self.states.selected().map(|x| x.to_string());
+        if selected.as_ref().and_then(|x| lock.query(x)).is_some() {
+            return;
+        }
+        let visible = self.states.visible_ids(root);
+        let start = visible.iter().position(|x| x == lock.id());
+        let index = visible.iter().position(|x| Some(x) == selected.as_ref());
+        let target = match (start, index) {
+            (Some(start), Some(index)) if index > start => visible[start..]
+                .iter()
+                .take_while(|x| lock.query(x).is_some())
+                .last()
+                .and_then(|x| root.query(x)),
+            _ => None,
+        };
+        self.states.select(root, target.unwrap_or(lock));
+    }
+
+    /// ### scroll_rows
+    ///
+    /// Get the amount of rows to scroll by `ScrollStep`; a percentage is relative to the viewport height of the last
+    /// render, and it always scrolls at least one row
+    fn scroll_rows(&self) -> usize {
+        match self
+            .props
+            .get_or(Attribute::ScrollStep, AttrValue::Length(8))
+        {
+            AttrValue::Size(percent) => {
+                (self.states.viewport_height() * usize::from(percent) / 100).max(1)
+            }
+            step => step.unwrap_length(),
+        }
+    }
+
+    /// ### record_mutation
+    ///
+    /// Track the last operation which may have changed the tree, for the consistency check in debug builds.
+    /// Cached labels are dropped, since values may have changed
+    fn record_mutation(&mut self, _mutation: &'static str) {
+        self.states.invalidate_labels();
+        #[cfg(debug_assertions)]
+        {
+            self.last_mutation = _mutation;
+        }
+    }
+
+    /// ### check_consistency
+    ///
+    /// Panic if the state refers to nodes which don't exist in the tree anymore, naming the stale id and the last
+    /// mutation of the tree. This usually means the tree has been changed through `tree_mut` without updating the state
+    #[cfg(debug_assertions)]
+    fn check_consistency(&self) {
+        if let Some((id, role)) = self.states.stale_id(self.tree.root()) {
+            panic!(
+                "tree state is inconsistent: {role} node {id:?} doesn't exist in the tree; last tree mutation: `{}`. Use `set_tree` to replace nodes which are open or selected",
+                self.last_mutation
+            );
+        }
+        #[cfg(feature = "strict")]
+        Self::check_unique_ids(self.tree.root());
+    }
+
+    /// ### check_unique_ids
+    ///
+    /// Panic if two nodes of the tree with root `root` have the same id, since nodes are looked up by id
+    #[cfg(all(debug_assertions, feature = "strict"))]
+    fn check_unique_ids(root: &Node<V>) {
+        /// ### duplicate_id_r
+        ///
+        /// Get the first id of `node` and of its descendants which is already in `ids`
+        fn duplicate_id_r<'n, V>(node: &'n Node<V>, ids: &mut HashSet<&'n str>) -> Option<&'n str> {
+            if !ids.insert(node.id().as_str()) {
+                return Some(node.id().as_str());
+            }
+            node.iter().find_map(|x| duplicate_id_r(x, ids))
+        }
+        if let Some(id) = duplicate_id_r(root, &mut HashSet::new()) {
+            panic!("tui-realm-treeview strict mode: node id {id:?} is used by more than one node; node ids must be unique");
+        }
+    }
+
+    /// ### open_selected
+    ///
+    /// Open the selected node, reloading its children if they have been unloaded
+    fn open_selected(&mut self) {
+        let Some(id) = self.states.selected().map(|x| x.to_string()) else {
+            return;
+        };
+        self.reload_unloaded(&id);
+        if self
+            .selected_node()
+            .is_some_and(|x| self.states.is_branch(x))
+        {
+            self.states.open(self.tree.root());
+        }
+    }
+
+    /// ### open_selected_recursive
+    ///
+    /// Open the selected node and all of its descendants, reloading the children of those which have been unloaded
+    fn open_selected_recursive(&mut self) {
+        /// ### collect_ids
+        ///
+        /// Collect ids of `node` and of its descendants
+        fn collect_ids<V>(node: &Node<V>, acc: &mut Vec<String>) {
+            acc.push(node.id().to_string());
+            node.iter().for_each(|x| collect_ids(x, acc));
+        }
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        let mut ids = Vec::new();
+        collect_ids(node, &mut ids);
+        // Reloaded children may have unloaded descendants of their own: reload them too
+        while let Some(id) = ids.pop() {
+            if self.reload_unloaded(&id) {
+                if let Some(node) = self.tree.root().query(&id) {
+                    node.iter().for_each(|x| collect_ids(x, &mut ids));
+                }
+            }
+        }
+        self.states.open_recursive(self.tree.root());
+    }
+
+    /// ### reload_unloaded
+    ///
+    /// Reload the children of node with `id` if they have been unloaded. Returns whether they have been reloaded
+    fn reload_unloaded(&mut self, id: &String) -> bool {
+        self.last_open.remove(id);
+        let unloaded = self
+            .tree
+            .root()
+            .query(id)
+            .map(|x| self.states.is_unloaded(x))
+            .unwrap_or(false);
+        if !unloaded {
+            return false;
+        }
+        if let (Some(reload), Some(node)) =
+            (self.reload.as_mut(), self.tree.root_mut().query_mut(id))
+        {
+            debug_log!("reload {:?}", id);
+            for child in reload(node) {
+                node.add_child(child);
+            }
+            #[cfg(debug_assertions)]
+            {
+                self.last_mutation = "reload";
+            }
+        }
+        self.states.set_unloaded(id, false);
+        true
+    }
+
+    /// ### open_lazy
+    ///
+    /// Open the selected node; if it is an empty branch, its children are requested to the loader, if any;
+    /// otherwise returns `Custom($TREE_CMD_OPEN, state)` so that the application can load its children
+    fn open_lazy(&mut self) -> CmdResult {
+        self.open_selected();
+        let states = &self.states;
+        let empty = states
+            .selected()
+            .and_then(|id| self.tree.root().query(&id.to_string()))
+            .filter(|x| x.is_leaf() && states.is_open(x));
+        let (Some(node), Some(loader)) = (empty, self.loader.as_mut()) else {
+            return match empty {
+                Some(_) => CmdResult::Custom(TREE_CMD_OPEN, self.cursor_state()),
+                None => CmdResult::None,
+            };
+        };
+        let id = node.id().to_string();
+        if self.loading.contains(&id) {
+            return CmdResult::None;
+        }
+        debug_log!("load {:?}", id);
+        match loader.load(node) {
+            Some(children) => {
+                self.set_children(&id, children);
+            }
+            None => {
+                self.loading.insert(id);
+            }
+        }
+        CmdResult::None
+    }
+
+    /// ### begin_reorder
+    ///
+    /// Start moving the selected node among its siblings, from its current position
+    fn begin_reorder(&mut self) {
+        let root = self.tree.root();
+        let pending = self.selected_node().and_then(|node| {
+            let parent = root.parent(node.id())?;
+            let index = parent.iter().position(|x| x.id() == node.id())?;
+            Some((node.id().to_string(), index))
+        });
+        self.pending_move = pending;
+    }
+
+    /// ### move_drop_position
+    ///
+    /// Move the landing position of the node being moved by one sibling in `direction`
+    fn move_drop_position(&mut self, direction: Direction) -> CmdResult {
+        let Some((id, index)) = self.pending_move.as_mut() else {
+            return CmdResult::None;
+        };
+        let siblings = self
+            .tree
+            .root()
+            .parent(id)
+            .map(|x| x.children().len())
+            .unwrap_or(1);
+        let position = match direction {
+            Direction::Up => index.saturating_sub(1),
+            Direction::Down => (*index + 1).min(siblings - 1),
+            _ => *index,
+        };
+        if position == *index {
+            return CmdResult::None;
+        }
+        *index = position;
+        // The drop indicator has moved: the view must be rendered again
+        CmdResult::Changed(self.cursor_state())
+    }
+
+    /// ### drop_target
+    ///
+    /// Get the id of the row above the landing position of the node being moved, if any
+    fn drop_target(&self) -> Option<&str> {
+        let (id, index) = self.pending_move.as_ref()?;
+        let parent = self.tree.root().parent(id)?;
+        // Right below the parent, if it lands as first child
+        let Some(prev) = index.checked_sub(1) else {
+            return Some(parent.id().as_str());
+        };
+        let mut row = parent.iter().filter(|x| x.id() != id).nth(prev)?;
+        // The node lands after the visible descendants of its previous sibling
+        while self.states.is_open(row) {
+            match self.states.visible_children(row).last() {
+                Some((child, more)) => {
+                    row = child;
+                    if more {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        Some(row.id().as_str())
+    }
+
+    /// ### commit_reorder
+    ///
+    /// Move the node being moved to its landing position among its siblings
+    fn commit_reorder(&mut self) -> CmdResult {
+        let Some((id, index)) = self.pending_move.take() else {
+            return CmdResult::None;
+        };
+        self.record_mutation("reorder");
+        let Some(parent) = self.tree.root_mut().parent_mut(&id) else {
+            return CmdResult::None;
+        };
+        let mut order: Vec<String> = parent
+            .iter()
+            .map(|x| x.id().to_string())
+            .filter(|x| *x != id)
+            .collect();
+        order.insert(index.min(order.len()), id.clone());
+        let positions: HashMap<String, usize> =
+            order.into_iter().enumerate().map(|(i, x)| (x, i)).collect();
+        parent.sort(|a, b| positions[a.id()].cmp(&positions[b.id()]));
+        self.states.invalidate_rows();
+        CmdResult::Custom(TREE_CMD_REORDER, State::One(StateValue::String(id)))
+    }
+
+    /// ### toggle_selected
+    ///
+    /// Open the selected node if it's a closed branch, or close it if it's open
+    fn toggle_selected(&mut self) -> CmdResult {
+        match self.selected_node() {
+            Some(node) if self.states.is_open(node) => self.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            Some(node) if self.states.is_branch(node) => self.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            _ => CmdResult::None,
+        }
+    }
+
+    /// ### close_selected
+    ///
+    /// Close the selected node, tracking when it was last open
+    fn close_selected(&mut self) {
+        let open = self
+            .states
+            .selected()
+            .and_then(|x| self.tree.root().query(&x.to_string()))
+            .filter(|x| self.states.is_open(x))
+            .map(|x| x.id().to_string());
+        if let Some(id) = open {
+            if self.unload_after.is_some() {
+                self.last_open.insert(id, Instant::now());
+            }
+            self.states.close(self.tree.root());
+        }
+    }
+
+    /// ### select_initial_node
+    ///
+    /// Select node with provided `id`, if exists, according to the reveal policy
+    fn select_initial_node(&mut self, id: &str) {
+        let reveal = self
+            .props
+            .get_or(
+                Attribute::Custom(TREE_REVEAL_INITIAL_NODE),
+                AttrValue::Flag(true),
+            )
+            .unwrap_flag();
+        if let Some(node) = self.tree.root().query(&id.to_string()) {
+            match reveal {
+                true => self.states.select(self.tree.root(), node),
+                false => self.states.select_visible(self.tree.root(), node),
+            };
+            self.commit();
+            self.publish_selection();
+        }
+    }
+
+    /// ### apply_initial_open
+    ///
+    /// Open the nodes of `TREE_INITIAL_OPEN`, if it has been set since the last render
+    fn apply_initial_open(&mut self) {
+        if !std::mem::take(&mut self.initial_open_pending) {
+            return;
+        }
+        let ids: Vec<String> = match self.props.get(Attribute::Custom(TREE_INITIAL_OPEN)) {
+            Some(AttrValue::Payload(PropPayload::Vec(ids))) => ids
+                .into_iter()
+                .filter_map(|x| match x {
+                    PropValue::Str(id) => Some(id),
+                    _ => None,
+                })
+                .collect(),
+            _ => return,
+        };
+        self.states.open_ids(self.tree.root(), &ids);
+    }
+
+    /// ### perform_tree_cmd
+    ///
+    /// Perform a custom command of the tree view
+    fn perform_tree_cmd(&mut self, cmd: TreeCmd) -> CmdResult {
+        match cmd {
+            TreeCmd::Close => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                let to_parent = self
+                    .props
+                    .get_or(
+                        Attribute::Custom(TREE_CLOSE_TO_PARENT),
+                        AttrValue::Flag(false),
+                    )
+                    .unwrap_flag();
+                let selected = prev
+                    .as_ref()
+                    .and_then(|x| self.tree.root().query(x))
+                    .filter(|x| self.states.is_closed(x));
+                match (to_parent, selected) {
+                    (true, Some(node)) => {
+                        // jump to parent of leaf or closed node
+                        if let Some(parent) = self.tree.root().parent(node.id()) {
+                            self.states.select(self.tree.root(), parent);
+                        }
+                        self.changed(prev.as_deref())
+                    }
+                    _ => {
+                        // close selected node
+                        self.close_selected();
+                        CmdResult::None
+                    }
+                }
+            }
+            TreeCmd::CloseParent => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                // The parent of the selected node is its nearest open ancestor
+                let parent = prev.as_ref().and_then(|x| self.tree.root().parent(x));
+                match parent {
+                    Some(parent) => {
+                        self.states.select(self.tree.root(), parent);
+                        // Changed first, so that the parent is not opened again on select
+                        let result = self.changed(prev.as_deref());
+                        self.close_selected();
+                        result
+                    }
+                    None => CmdResult::None,
+                }
+            }
+            TreeCmd::GotoParent => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                match self.states.select_parent(self.tree.root()) {
+                    true => self.changed(prev.as_deref()),
+                    false => CmdResult::None,
+                }
+            }
+            TreeCmd::ClearFlags => {
+                self.clear_flags();
+                CmdResult::None
+            }
+            TreeCmd::SortColumn(column) => {
+                let order = self.column_sort.next(column).map(|(_, order)| order);
+                self.sort_column(column, order);
+                CmdResult::None
+            }
+            TreeCmd::SelectAllVisible => {
+                for id in self.states.visible_ids(self.tree.root()) {
+                    self.set_flag(id, Flag::MARKED);
+                }
+                CmdResult::None
+            }
+            TreeCmd::ToggleMark => {
+                if let Some(id) = self.states.selected().map(|x| x.to_string()) {
+                    self.toggle_flag(id, Flag::MARKED);
+                }
+                CmdResult::None
+            }
+            TreeCmd::MarkAllSiblings => {
+                let root = self.tree.root();
+                let siblings: Vec<String> = match self.selected_node() {
+                    Some(node) => match root.parent(node.id()) {
+                        Some(parent) => parent.iter().map(|x| x.id().to_string()).collect(),
+                        None => vec![node.id().to_string()],
+                    },
+                    None => Vec::new(),
+                };
+                for id in siblings {
+                    self.set_flag(id, Flag::MARKED);
+                }
+                CmdResult::None
+            }
+            TreeCmd::SelectNone => {
+                self.flags.retain(|_, flags| {
+                    flags.remove(&Flag::MARKED);
+                    !flags.is_empty()
+                });
+                CmdResult::None
+            }
+            TreeCmd::Commit => match self.is_soft_selection() && self.commit() {
+                true => CmdResult::Changed(self.state()),
+                false => CmdResult::None,
+            },
+            TreeCmd::Undo => match self.undo.pop() {
+                Some(snapshot) => {
+                    self.redo.push(self.states.snapshot());
+                    self.states.restore(self.tree.root(), snapshot);
+                    CmdResult::Changed(self.cursor_state())
+                }
+                None => CmdResult::None,
+            },
+            TreeCmd::Redo => match self.redo.pop() {
+                Some(snapshot) => {
+                    self.undo.push(self.states.snapshot());
+                    self.states.restore(self.tree.root(), snapshot);
+                    CmdResult::Changed(self.cursor_state())
+                }
+                None => CmdResult::None,
+            },
+            TreeCmd::IndentInc => {
+                let size = self.get_indent_size().saturating_add(1);
+                self.attr(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(size));
+                CmdResult::None
+            }
+            TreeCmd::IndentDec => {
+                let size = self.get_indent_size().saturating_sub(1);
+                self.attr(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(size));
+                CmdResult::None
+            }
+            TreeCmd::Open => self.open_lazy(),
+            TreeCmd::Reorder => {
+                self.begin_reorder();
+                CmdResult::None
+            }
+            TreeCmd::OpenAll => {
+                self.open_selected_recursive();
+                CmdResult::None
+            }
+            TreeCmd::CloseAll => {
+                self.close_selected();
+                self.states.close_recursive(self.tree.root());
+                CmdResult::None
+            }
+            TreeCmd::Search => {
+                let query = self
+                    .props
+                    .get(Attribute::Custom(TREE_SEARCH_QUERY))
+                    .map(|x| x.unwrap_string())
+                    .unwrap_or_default();
+                self.search(&query)
+            }
+            TreeCmd::SearchNext => self.search_next(),
+            TreeCmd::SearchPrev => self.search_prev(),
+            TreeCmd::NextBookmark => self.cycle_bookmark(true),
+            TreeCmd::PrevBookmark => self.cycle_bookmark(false),
+            TreeCmd::ViewCenter => {
+                self.states.view_center(self.tree.root());
+                CmdResult::None
+            }
+            TreeCmd::ViewTop => {
+                self.states.view_top(self.tree.root());
+                CmdResult::None
+            }
+            TreeCmd::ViewBottom => {
+                self.states.view_bottom(self.tree.root());
+                CmdResult::None
+            }
+        }
+    }
+
+    /// ### expand_on_select_ticks
+    ///
+    /// Get the ticks after which the selected node is opened, if expand on select is enabled
+    fn expand_on_select_ticks(&self) -> Option<usize> {
+        self.props
+            .get(Attribute::Custom(TREE_EXPAND_ON_SELECT))
+            .map(|x| x.unwrap_length())
+    }
+
+    /// ### tick
+    ///
+    /// Count a tick for the selected node and open it if it has been selected for enough ticks.
+    /// Returns `Changed` if the node has been opened
+    fn tick(&mut self) -> CmdResult {
+        let ticks = match self.expand_on_select_ticks() {
+            Some(ticks) if ticks > 0 && self.selected_ticks < ticks => ticks,
+            _ => return CmdResult::None,
+        };
+        self.selected_ticks += 1;
+        if self.selected_ticks < ticks {
+            return CmdResult::None;
+        }
+        let rows = self.states.visible_len(self.tree.root());
+        self.open_selected();
+        match self.states.visible_len(self.tree.root()) != rows {
+            true => CmdResult::Changed(self.cursor_state()),
+            false => CmdResult::None,
+        }
+    }
+
+    /// ### step_loading
+    ///
+    /// Advance the spinner of the nodes whose children are being loaded; returns whether any is rendered.
+    /// Nodes which got their children through `tree_mut` are not loading anymore
+    fn step_loading(&mut self) -> bool {
+        let root = self.tree.root();
+        self.loading
+            .retain(|id| root.query(id).is_some_and(|x| x.is_leaf()));
+        if self.loading.is_empty() {
+            return false;
+        }
+        self.loading_frame = self.loading_frame.wrapping_add(1);
+        true
+    }
+
+    /// ### publish_selection
+    ///
+    /// Publish the selected node to the receivers of `watch_selection`, unless a batch is in progress
+    fn publish_selection(&self) {
+        #[cfg(any(test, feature = "watch"))]
+        if let Some(sender) = self
+            .selection_sender
+            .as_ref()
+            .filter(|_| self.batch_depth == 0)
+        {
+            sender.send(self.states.selected());
+        }
+    }
+
+    /// ### record_undo
+    ///
+    /// Record `prev` as state to restore on undo, if the state has changed since then.
+    /// Changing the state discards the states to redo
+    fn record_undo(&mut self, prev: StateSnapshot) {
+        if prev == self.states.snapshot() {
+            return;
+        }
+        self.undo.push(prev);
+        if self.undo.len() > self.undo_depth {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// ### commit
+    ///
+    /// Promote the selected node to the committed selection. Returns whether the committed selection has changed
+    fn commit(&mut self) -> bool {
+        let selected = self.states.selected().map(|x| x.to_string());
+        let changed = selected != self.committed;
+        self.committed = selected;
+        changed
+    }
+
+    /// ### is_soft_selection
+    ///
+    /// Returns whether `TREE_SOFT_SELECTION` is set
+    fn is_soft_selection(&self) -> bool {
+        self.props
+            .get_or(
+                Attribute::Custom(TREE_SOFT_SELECTION),
+                AttrValue::Flag(false),
+            )
+            .unwrap_flag()
+    }
+
+    /// ### is_multi_select
+    ///
+    /// Returns whether `TREE_MULTI_SELECT` is set
+    fn is_multi_select(&self) -> bool {
+        self.props
+            .get_or(Attribute::Custom(TREE_MULTI_SELECT), AttrValue::Flag(false))
+            .unwrap_flag()
+    }
+
+    /// ### is_fuzzy_search
+    ///
+    /// Returns whether `TREE_SEARCH_FUZZY` is set
+    fn is_fuzzy_search(&self) -> bool {
+        self.props
+            .get_or(Attribute::Custom(TREE_SEARCH_FUZZY), AttrValue::Flag(false))
+            .unwrap_flag()
+    }
+
+    /// ### cursor_state
+    ///
+    /// Get the state of the cursor, which is the selected node of the tree state
+    fn cursor_state(&self) -> State {
+        match self.states.selected() {
+            None => State::None,
+            Some(id) => State::One(StateValue::String(id.to_string())),
+        }
+    }
+
+    /// ### submit
+    ///
+    /// Submit the selected node, committing it if `TREE_SOFT_SELECTION` is set; "more" rows show more children instead
+    fn submit(&mut self) -> CmdResult {
+        if self.show_more_children() {
+            return CmdResult::Changed(self.cursor_state());
+        }
+        // Lazy branches are branches without children yet
+        if let Some(open) = self
+            .selected_node()
+            .filter(|x| x.is_leaf() && self.states.is_branch(x))
+            .map(|x| self.states.is_open(x))
+        {
+            match (self.lazy_submit, open) {
+                (LazySubmit::PassThrough, _) => {}
+                (LazySubmit::Toggle, true) => {
+                    self.close_selected();
+                    return CmdResult::None;
+                }
+                (LazySubmit::Load | LazySubmit::Toggle, _) => return self.open_lazy(),
+            }
+        }
+        if self.is_soft_selection() {
+            self.commit();
+        }
+        if let Some(id) = self.states.selected().map(|x| x.to_string()) {
+            self.push_recent(id);
+        }
+        CmdResult::Submit(self.submit_state())
+    }
+
+    /// ### push_recent
+    ///
+    /// Move `id` to the top of the recently submitted nodes
+    fn push_recent(&mut self, id: String) {
+        self.recent.retain(|x| *x != id);
+        self.recent.insert(0, id);
+        self.recent.truncate(self.recent_limit);
+    }
+
+    /// ### show_more_children
+    ///
+    /// If the selected node is rendered as a "more" row (see `TREE_MAX_CHILDREN`), show more children of its parent.
+    /// Returns whether the limit has been increased
+    fn show_more_children(&mut self) -> bool {
+        let root = self.tree.root();
+        let Some(node) = self
+            .states
+            .selected()
+            .and_then(|x| root.query(&x.to_string()))
+        else {
+            return false;
+        };
+        if !self.states.is_more_row(root, node) {
+            return false;
+        }
+        // NOTE: a "more" row always has a parent
+        if let Some(parent) = root.parent(node.id()) {
+            self.states.show_more_children(parent);
+        }
+        true
+    }
+
+    /// ### submit_state
+    ///
+    /// Get the state to report on submit
+    fn submit_state(&self) -> State {
+        let flagged = self
+            .props
+            .get(Attribute::Custom(TREE_SUBMIT_FLAG))
+            .map(|x| self.ids_with_flag_name(&x.unwrap_string()))
+            .unwrap_or_default();
+        if !flagged.is_empty() {
+            return State::Vec(flagged.into_iter().map(StateValue::String).collect());
+        }
+        let payload = self
+            .props
+            .get_or(
+                Attribute::Custom(TREE_SUBMIT_PAYLOAD),
+                AttrValue::Flag(false),
+            )
+            .unwrap_flag();
+        match payload {
+            true => self
+                .states
+                .selected()
+                .and_then(|id| self.tree.root().query(&id.to_string()))
+                .and_then(|node| node.value().submit_value())
+                .map(State::One)
+                .unwrap_or_else(|| self.state()),
+            false => self.state(),
+        }
+    }
+
+    /// ### marks
+    ///
+    /// Get the marked nodes, with the symbol and the style to render them with, in multi-select mode
+    fn marks(&self) -> (HashSet<String>, String, Style) {
+        let marked = self
+            .flags
+            .iter()
+            .filter(|(_, flags)| flags.contains(&Flag::MARKED))
+            .map(|(id, _)| id.clone())
+            .collect();
+        let ascii_only = self
+            .props
+            .get_or(Attribute::Custom(TREE_ASCII_ONLY), AttrValue::Flag(false))
+            .unwrap_flag();
+        let symbol = self
+            .props
+            .get(Attribute::Custom(TREE_MARK_SYMBOL))
+            .map(|x| x.unwrap_string())
+            .unwrap_or_else(|| match ascii_only {
+                true => String::from("* "),
+                false => String::from("\u{2713} "),
+            });
+        let style = self
+            .props
+            .get_or(
+                Attribute::Custom(TREE_MARK_STYLE),
+                AttrValue::Style(Style::default().add_modifier(TextModifiers::BOLD)),
+            )
+            .unwrap_style();
+        (marked, symbol, style)
+    }
+
+    /// ### ids_with_flag_name
+    ///
+    /// Get the ids of all the nodes with flag named `name` set, in the order they appear in the tree
+    fn ids_with_flag_name(&self, name: &str) -> Vec<String> {
+        /// ### ids_with_flag_name_r
+        ///
+        /// Push to `ids` the ids of `node` and its descendants which have the flag set
+        fn ids_with_flag_name_r<V>(
+            flags: &HashMap<String, HashSet<Flag>>,
+            node: &Node<V>,
+            name: &str,
+            ids: &mut Vec<String>,
+        ) {
+            if flags
+                .get(node.id().as_str())
+                .map(|x| x.iter().any(|flag| flag.name() == name))
+                .unwrap_or(false)
+            {
+                ids.push(node.id().to_string());
+            }
+            node.iter()
+                .for_each(|x| ids_with_flag_name_r(flags, x, name, ids));
+        }
+        let mut ids = Vec::new();
+        if !self.flags.is_empty() {
+            ids_with_flag_name_r(&self.flags, self.tree.root(), name, &mut ids);
+        }
+        ids
+    }
+
+    /// ### get_indent_size
+    ///
+    /// Get the spaces to render for each depth level
+    fn get_indent_size(&self) -> u16 {
+        self.props
+            .get_or(Attribute::Custom(TREE_INDENT_SIZE), AttrValue::Size(4))
+            .unwrap_size()
+    }
+
+    /// ### get_highlight_symbol
+    ///
+    /// Get the highlight symbol to render, based on the focus state
+    fn get_highlight_symbol(&self, focus: bool) -> Option<String> {
+        let focus_only = self
+            .props
+            .get_or(
+                Attribute::Custom(TREE_HL_SYMBOL_FOCUS_ONLY),
+                AttrValue::Flag(false),
+            )
+            .unwrap_flag();
+        match focus || !focus_only {
+            true => self
+                .props
+                .get(Attribute::HighlightedStr)
+                .map(|x| x.unwrap_string()),
+            false => None,
+        }
+    }
+
+    fn get_block<'a>(
+        props: Borders,
+        title: Option<(String, Alignment)>,
+        focus: bool,
+        inactive_style: Option<Style>,
+    ) -> Block<'a> {
+        let title = title.unwrap_or((String::default(), Alignment::Left));
+        Block::default()
+            .borders(props.sides)
+            .border_style(match focus {
+                true => props.style(),
+                false => inactive_style
+                    .unwrap_or_else(|| Style::default().fg(Color::Reset).bg(Color::Reset)),
+            })
+            .border_type(props.modifiers)
+            .title(title.0)
+            .title_alignment(title.1)
+    }
+}
+
+// -- mock
+
+impl<V: NodeValue> MockComponent for TreeView<V> {
+    fn view(&mut self, frame: &mut Frame, area: Rect) {
+        // Misuses of `tree_mut` are reported as soon as possible
+        #[cfg(all(debug_assertions, feature = "strict"))]
+        self.check_consistency();
+        if self.props.get_or(Attribute::Display, AttrValue::Flag(true)) == AttrValue::Flag(true) {
+            self.apply_initial_open();
+            self.clear_revealed_dirty();
+            let dirty = self.dirty_ancestors();
+            let marks = match self.is_multi_select() {
+                true => Some(self.marks()),
+                false => None,
+            };
+            let foreground = self
+                .props
+                .get_or(Attribute::Foreground, AttrValue::Color(Color::Reset))
+                .unwrap_color();
+            let background = self
+                .props
+                .get_or(Attribute::Background, AttrValue::Color(Color::Reset))
+                .unwrap_color();
+            let modifiers = self
+                .props
+                .get_or(
+                    Attribute::TextProps,
+                    AttrValue::TextModifiers(TextModifiers::empty()),
+                )
+                .unwrap_text_modifiers();
+            let title = self
+                .props
+                .get_or(
+                    Attribute::Title,
+                    AttrValue::Title((String::default(), Alignment::Center)),
+                )
+                .unwrap_title();
+            let borders = self
+                .props
+                .get_or(Attribute::Borders, AttrValue::Borders(Borders::default()))
+                .unwrap_borders();
+            let focus = self
+                .props
+                .get_or(Attribute::Focus, AttrValue::Flag(false))
+                .unwrap_flag();
+            let inactive_style = self
+                .props
+                .get(Attribute::FocusStyle)
+                .map(|x| x.unwrap_style());
+            let indent_size = self.get_indent_size();
+            let hg_color = self
+                .props
+                .get_or(Attribute::HighlightedColor, AttrValue::Color(foreground))
+                .unwrap_color();
+            let hg_style = match self.props.get(Attribute::Custom(TREE_HL_LABEL_STYLE)) {
+                Some(style) => style.unwrap_style(),
+                None => match focus {
+                    true => Style::default().bg(hg_color).fg(Color::Black),
+                    false => Style::default().fg(hg_color),
+                }
+                .add_modifier(modifiers),
+            };
+            let hg_symbol_style = self
+                .props
+                .get(Attribute::Custom(TREE_HL_SYMBOL_STYLE))
+                .map(|x| x.unwrap_style());
+            let guides_style = self
+                .props
+                .get(Attribute::Custom(TREE_GUIDES_STYLE))
+                .map(|x| x.unwrap_style());
+            let icon_style = self
+                .props
+                .get(Attribute::Custom(TREE_ICON_STYLE))
+                .map(|x| x.unwrap_style());
+            let hg_str = self.get_highlight_symbol(focus);
+            // The flash message replaces the footer, until it expires
+            let footer = match self.flash.take() {
+                Some((text, renders)) => {
+                    self.flash = Some((text.clone(), renders - 1)).filter(|_| renders > 1);
+                    Some(text)
+                }
+                None => self
+                    .props
+                    .get(Attribute::Custom(TREE_FOOTER))
+                    .map(|x| x.unwrap_string()),
+            };
+            let background_fill = match self
+                .props
+                .get_or(Attribute::Custom(TREE_FILL_INNER), AttrValue::Flag(false))
+                .unwrap_flag()
+            {
+                true => BackgroundFill::Inner,
+                false => BackgroundFill::Area,
+            };
+            let rtl = self
+                .props
+                .get_or(Attribute::Custom(TREE_RTL), AttrValue::Flag(false))
+                .unwrap_flag();
+            let scroll_jump = self
+                .props
+                .get_or(Attribute::Custom(TREE_SCROLL_JUMP), AttrValue::Length(1))
+                .unwrap_length();
+            let ascii_only = self
+                .props
+                .get_or(Attribute::Custom(TREE_ASCII_ONLY), AttrValue::Flag(false))
+                .unwrap_flag();
+            let smooth_scroll = self
+                .props
+                .get_or(
+                    Attribute::Custom(TREE_SMOOTH_SCROLL),
+                    AttrValue::Flag(false),
+                )
+                .unwrap_flag();
+            let tab_width = self
+                .props
+                .get_or(Attribute::Custom(TREE_TAB_WIDTH), AttrValue::Length(4))
+                .unwrap_length();
+            let arrow_first = self
+                .props
+                .get_or(Attribute::Custom(TREE_ARROW_FIRST), AttrValue::Flag(false))
+                .unwrap_flag();
+            let scrollbar = self
+                .props
+                .get_or(Attribute::Custom(TREE_SCROLLBAR), AttrValue::Flag(false))
+                .unwrap_flag();
+            let guides = self
+                .props
+                .get_or(Attribute::Custom(TREE_GUIDES), AttrValue::Flag(false))
+                .unwrap_flag();
+            let ellipsis = self
+                .props
+                .get(Attribute::Custom(TREE_ELLIPSIS))
+                .map(|x| x.unwrap_string());
+            let style_per_depth: Vec<Style> =
+                match self.props.get(Attribute::Custom(TREE_STYLE_PER_DEPTH)) {
+                    Some(AttrValue::Payload(PropPayload::Vec(styles))) => styles
+                        .into_iter()
+                        .filter_map(|x| match x {
+                            PropValue::Style(style) => Some(style),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+            let columns: Vec<(String, u16)> = match self.props.get(Attribute::Custom(TREE_COLUMNS))
+            {
+                Some(AttrValue::Payload(PropPayload::Linked(columns))) => columns
+                    .into_iter()
+                    .filter_map(|x| match x {
+                        PropPayload::Tup2((PropValue::Str(title), PropValue::U16(width))) => {
+                            Some((title, width))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let match_query = self
+                .props
+                .get(Attribute::Custom(TREE_MATCH_QUERY))
+                .map(|x| x.unwrap_string());
+            let match_style = self
+                .props
+                .get_or(
+                    Attribute::Custom(TREE_MATCH_STYLE),
+                    AttrValue::Style(
+                        Style::default()
+                            .add_modifier(TextModifiers::BOLD | TextModifiers::UNDERLINED),
+                    ),
+                )
+                .unwrap_style();
+            let drop_style = self
+                .props
+                .get_or(
+                    Attribute::Custom(TREE_DROP_STYLE),
+                    AttrValue::Style(Style::default().add_modifier(TextModifiers::UNDERLINED)),
+                )
+                .unwrap_style();
+            let drop_target = self.drop_target().map(|x| x.to_string());
+            let loading = match self.loading.is_empty() {
+                true => None,
+                false => {
+                    let frames = match ascii_only {
+                        true => LOADING_FRAMES_ASCII,
+                        false => LOADING_FRAMES,
+                    };
+                    let frame = frames[self.loading_frame % frames.len()];
+                    Some(format!("{frame} {}", self.loading_text))
+                }
+            };
+            let div = Self::get_block(borders, Some(title), focus, inactive_style);
+            // Make widget
+            let mut tree = TreeWidget::new(&self.tree)
+                .block(div)
+                .highlight_style(hg_style)
+                .background_fill(background_fill)
+                .indent_size(indent_size.into())
+                .arrow_first(arrow_first)
+                .rtl(rtl)
+                .scroll_jump(scroll_jump)
+                .tab_width(tab_width)
+                .smooth_scroll(smooth_scroll)
+                .ascii(ascii_only)
+                .scrollbar(scrollbar)
+                .guides(guides)
+                .style_per_depth(style_per_depth)
+                .cache_labels(self.cache_labels)
+                .style(
+                    Style::default()
+                        .fg(foreground)
+                        .bg(background)
+                        .add_modifier(modifiers),
+                );
+            if let Some((min, max)) = self.auto_indent {
+                tree = tree.auto_indent(min.into(), max.into());
+            }
+            if let Some(hg_str) = hg_str {
+                tree = tree.highlight_symbol(hg_str);
+            }
+            if let Some(style) = hg_symbol_style {
+                tree = tree.highlight_symbol_style(style);
+            }
+            if let Some(style) = guides_style {
+                tree = tree.guides_style(style);
+            }
+            if let Some(style) = icon_style {
+                tree = tree.icon_style(style);
+            }
+            if let Some(footer) = footer {
+                tree = tree.footer(footer);
+            }
+            if !columns.is_empty() {
+                tree = tree.columns(columns);
+            }
+            if let Some((column, order)) = self.column_sort.sorted() {
+                tree = tree.sorted_column(column, order);
+            }
+            if let Some(ellipsis) = ellipsis.as_deref() {
+                tree = tree.ellipsis(ellipsis);
+            }
+            if let Some(map) = self.display_map.as_ref() {
+                tree = tree.display_map(map.as_ref());
+            }
+            if let Some(matcher) = self.matcher.as_ref() {
+                tree = tree.highlight_matcher(matcher.as_ref(), match_style);
+            } else if let Some(query) = match_query {
+                tree = tree.highlight_matches(&query, match_style);
+            }
+            if self.is_soft_selection() {
+                let preview_style = self
+                    .props
+                    .get_or(
+                        Attribute::Custom(TREE_PREVIEW_STYLE),
+                        AttrValue::Style(
+                            Style::default()
+                                .fg(hg_color)
+                                .add_modifier(modifiers | TextModifiers::UNDERLINED),
+                        ),
+                    )
+                    .unwrap_style();
+                tree = tree.soft_selection(self.committed.as_deref(), preview_style);
+            }
+            if let Some(placeholder) = self.decoration_placeholder.clone() {
+                tree = tree.decoration_placeholder(placeholder);
+            }
+            tree = tree
+                .decorations(&self.decorations)
+                .decorators(&self.decorators);
+            if let Some(decoration) = self.recent_decoration.as_ref() {
+                tree = tree.recent(&self.recent, decoration);
+            }
+            if !self.bookmarks.is_empty() {
+                tree = tree.bookmarks(&self.bookmarks, &self.bookmark_decoration);
+            }
+            if !dirty.is_empty() {
+                tree = tree.dirty_badge(&dirty, &self.dirty_badge.0, self.dirty_badge.1);
+            }
+            if let Some((marked, symbol, style)) = marks.as_ref() {
+                tree = tree.marks(marked, symbol, *style);
+            }
+            if let Some(placeholder) = loading.as_deref() {
+                tree = tree.loading(&self.loading, placeholder);
+            }
+            if let Some(id) = drop_target.as_deref() {
+                tree = tree.drop_indicator(id, drop_style);
+            }
+            frame.render_stateful_widget(tree, area, &mut self.states);
+        }
+    }
+
+    fn query(&self, attr: Attribute) -> Option<AttrValue> {
+        self.props.get(attr)
+    }
+
+    fn attr(&mut self, attr: Attribute, value: AttrValue) {
+        // Initial node
+        if matches!(attr, Attribute::Custom(TREE_INITIAL_NODE)) {
+            // Select node if exists
+            let id = value.clone().unwrap_string();
+            self.props.set(attr, value);
+            self.select_initial_node(&id);
+        } else if matches!(attr, Attribute::Custom(TREE_INITIAL_OPEN)) {
+            // Resolved at the next render, against the tree set by then
+            self.initial_open_pending = true;
+            self.props.set(attr, value);
+        } else if matches!(attr, Attribute::Custom(TREE_MAX_CHILDREN)) {
+            self.states
+                .set_max_children(Some(value.clone().unwrap_length()));
+            self.props.set(attr, value);
+        } else {
+            self.props.set(attr, value);
+        }
     }
 
     fn state(&self) -> State {
-        match self.states.selected() {
-            None => State::None,
-            Some(id) => State::One(StateValue::String(id.to_string())),
+        if self
+            .props
+            .get_or(Attribute::Custom(TREE_STATE_MAP), AttrValue::Flag(false))
+            .unwrap_flag()
+        {
+            let mut state = PersistedState::new(self.states.snapshot(), Vec::new());
+            if self.is_soft_selection() {
+                state.selected = self.committed.clone();
+            }
+            return state.into_state();
+        }
+        if self.is_multi_select() {
+            let marked = self.selected_ids();
+            if !marked.is_empty() {
+                return State::Vec(marked.into_iter().map(StateValue::String).collect());
+            }
+        }
+        match (self.is_soft_selection(), self.committed.as_deref()) {
+            (false, _) => self.cursor_state(),
+            (true, None) => State::None,
+            (true, Some(id)) => State::One(StateValue::String(id.to_string())),
+        }
+    }
+
+    fn perform(&mut self, cmd: Cmd) -> CmdResult {
+        #[cfg(debug_assertions)]
+        if self.batch_depth == 0 {
+            self.check_consistency();
+        }
+        // Take a snapshot to undo state changes
+        let snapshot = match cmd {
+            _ if self.undo_depth == 0 || self.batch_depth > 0 => None,
+            Cmd::Custom(TREE_CMD_UNDO | TREE_CMD_REDO) => None,
+            _ => Some(self.states.snapshot()),
+        };
+        let result = match cmd {
+            Cmd::Move(direction) if self.pending_move.is_some() => {
+                self.move_drop_position(direction)
+            }
+            Cmd::Submit if self.pending_move.is_some() => self.commit_reorder(),
+            Cmd::Cancel => match self.pending_move.take() {
+                Some(_) => CmdResult::Changed(self.cursor_state()),
+                None => CmdResult::None,
+            },
+            Cmd::GoTo(Position::Begin) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                // Get first sibling of current node
+                if let Some(first) = self.states.first_sibling(self.tree.root()) {
+                    self.states.select(self.tree.root(), first);
+                }
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::GoTo(Position::End) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                // Get first sibling of current node
+                if let Some(last) = self.states.last_sibling(self.tree.root()) {
+                    self.states.select(self.tree.root(), last);
+                }
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::Move(Direction::Down) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                self.states.move_down(self.tree.root());
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::Move(Direction::Up) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                self.states.move_up(self.tree.root());
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::Scroll(Direction::Down) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                let step = self.scroll_rows();
+                (0..step).for_each(|_| self.states.move_down(self.tree.root()));
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::Scroll(Direction::Up) => {
+                let prev = self.states.selected().map(|x| x.to_string());
+                let step = self.scroll_rows();
+                (0..step).for_each(|_| self.states.move_up(self.tree.root()));
+                self.clamp_navigation();
+                self.changed(prev.as_deref())
+            }
+            Cmd::Submit => self.submit(),
+            Cmd::Tick => {
+                self.unload_closed_branches(Instant::now());
+                let nudged = self.nudge_drag();
+                let scrolled = self.states.step_scroll() | self.step_loading();
+                match (self.tick(), nudged) {
+                    // The view has moved: it must be rendered again
+                    (CmdResult::None, CmdResult::None) if scrolled => {
+                        CmdResult::Changed(self.cursor_state())
+                    }
+                    (CmdResult::None, nudged) => nudged,
+                    (result, _) => result,
+                }
+            }
+            Cmd::Custom(cmd) => match TreeCmd::try_from(cmd) {
+                Ok(cmd) => self.perform_tree_cmd(cmd),
+                Err(_) => CmdResult::None,
+            },
+            _ => CmdResult::None,
+        };
+        if let Some(snapshot) = snapshot {
+            self.record_undo(snapshot);
+        }
+        self.clear_revealed_dirty();
+        self.publish_selection();
+        match result {
+            // Changes are reported once, when the batch ends
+            CmdResult::Changed(_) if self.batch_depth > 0 => CmdResult::None,
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::mock::mock_tree;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_initialize_component() {
+        let mut component = TreeView::default()
+            .background(Color::White)
+            .foreground(Color::Cyan)
+            .borders(Borders::default())
+            .inactive(Style::default())
+            .indent_size(4)
+            .modifiers(TextModifiers::all())
+            .preserve_state(true)
+            .scroll_step(4)
+            .title("My tree", Alignment::Center)
+            .with_tree(mock_tree())
+            .initial_node("aB1");
+        // Check tree
+        assert_eq!(component.tree_state().selected().unwrap(), "aB1");
+        assert!(component.tree().root().query(&String::from("aB")).is_some());
+        component
+            .tree_mut()
+            .root_mut()
+            .add_child(Node::new(String::from("d"), String::from("d")));
+    }
+
+    #[test]
+    fn should_render_footer() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .footer("Enter: open")
+            .initial_node("a");
+        assert_eq!(
+            component.query(Attribute::Custom(TREE_FOOTER)).unwrap(),
+            AttrValue::String(String::from("Enter: open"))
+        );
+        let mut terminal = Terminal::new(TestBackend::new(16, 6)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        // Footer is on the last row inside the block
+        let footer: String = (1..12).map(|x| buffer[(x, 4)].symbol()).collect();
+        assert_eq!(footer, "Enter: open");
+    }
+
+    #[test]
+    fn should_render_right_to_left() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .rtl(true)
+            .initial_node("a");
+        assert_eq!(
+            component.query(Attribute::Custom(TREE_RTL)).unwrap(),
+            AttrValue::Flag(true)
+        );
+        let mut terminal = Terminal::new(TestBackend::new(16, 4)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        // Indentation starts from the right edge
+        let rows: Vec<String> = (1..3)
+            .map(|y| (1..15).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(rows, vec!["       \u{25bc} /    ", "   \u{25c0} a        "]);
+    }
+
+    #[test]
+    fn should_open_initial_open_nodes_at_first_render() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .initial_open(["aA", "c", "unknown"])
+            .with_tree(mock_tree());
+        assert!(component.tree_state().is_closed(component.tree().root()));
+        let mut terminal = Terminal::new(TestBackend::new(12, 5)).unwrap();
+        let _ = terminal.draw(|f| component.view(f, f.area()));
+        let root = component.tree().root();
+        for id in ["/", "a", "aA", "c"] {
+            assert!(component
+                .tree_state()
+                .is_open(root.query(&String::from(id)).unwrap()));
+        }
+        assert!(component
+            .tree_state()
+            .is_closed(root.query(&String::from("b")).unwrap()));
+        // Applied only once
+        *component.tree_state_mut() = TreeState::default();
+        let _ = terminal.draw(|f| component.view(f, f.area()));
+        assert!(component.tree_state().is_closed(component.tree().root()));
+    }
+
+    #[test]
+    fn should_apply_initial_node_reveal_policy() {
+        // Initial node set before tree
+        let component = TreeView::default()
+            .initial_node("aB1")
+            .with_tree(mock_tree());
+        assert_eq!(component.tree_state().selected().unwrap(), "aB1");
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("aB")).unwrap()));
+        // Don't reveal
+        let component = TreeView::default()
+            .reveal_initial_node(false)
+            .with_tree(mock_tree())
+            .initial_node("aB1");
+        assert_eq!(component.tree_state().selected().unwrap(), "/");
+        assert!(component.tree_state().is_closed(component.tree().root()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "selected node \"aB1\" doesn't exist in the tree; last tree mutation: `tree_mut`"
+    )]
+    fn should_panic_on_stale_state() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aB1");
+        component
+            .tree_mut()
+            .root_mut()
+            .remove_child(&String::from("a"));
+        component.perform(Cmd::Move(Direction::Down));
+    }
+
+    #[test]
+    fn should_preserve_subtree_on_tree_changed() {
+        let mut component = TreeView::default()
+            .preserve_state(true)
+            .preserve_subtree("c")
+            .with_tree(mock_tree())
+            .initial_node("bA0");
+        component.attr(
+            Attribute::Custom(TREE_INITIAL_NODE),
+            AttrValue::String(String::from("cA1")),
+        );
+        component.set_tree(mock_tree());
+        assert_eq!(component.tree_state().selected().unwrap(), "cA1");
+        let root = component.tree().root();
+        assert!(component
+            .tree_state()
+            .is_closed(root.query(&String::from("b")).unwrap()));
+        assert!(component
+            .tree_state()
+            .is_open(root.query(&String::from("cA")).unwrap()));
+    }
+
+    #[test]
+    fn should_prune_subtree() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aB1");
+        component.bookmark("aB0");
+        component.states.select(
+            component.tree.root(),
+            component.tree.root().query(&String::from("aA0")).unwrap(),
+        );
+        component.states.select(
+            component.tree.root(),
+            component.tree.root().query(&String::from("aB1")).unwrap(),
+        );
+        // Root can't be removed
+        assert_eq!(component.prune("/"), false);
+        assert_eq!(component.prune("z"), false);
+        // Selection and open nodes are kept outside of the removed subtree
+        assert_eq!(component.prune("aA"), true);
+        assert!(component.tree().root().query(&String::from("aA")).is_none());
+        assert_eq!(component.tree_state().selected().unwrap(), "aB1");
+        assert!(component
+            .tree_state()
+            .stale_id(component.tree().root())
+            .is_none());
+        // Selection inside the removed subtree is moved to its parent
+        assert_eq!(component.prune("aB"), true);
+        assert_eq!(component.tree_state().selected().unwrap(), "a");
+        assert!(component
+            .tree_state()
+            .stale_id(component.tree().root())
+            .is_none());
+        assert!(component.bookmarks().is_empty());
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aC"))))
+        );
+    }
+
+    #[test]
+    fn should_swap_tree() {
+        let mut component = TreeView::default()
+            .preserve_state(true)
+            .with_tree(mock_tree())
+            .initial_node("aB1");
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("c"));
+        let prev = component.swap_tree(tree);
+        assert!(prev.root().query(&String::from("c")).is_some());
+        assert!(component.tree().root().query(&String::from("c")).is_none());
+        assert_eq!(component.tree_state().selected().unwrap(), "aB1");
+    }
+
+    #[test]
+    fn should_return_consistent_state() {
+        let component = TreeView::default().with_tree(mock_tree());
+        assert_eq!(component.state(), State::None);
+        let component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aA")))
+        );
+    }
+
+    #[test]
+    fn should_perform_go_to_begin() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bB3");
+        // GoTo begin (changed)
+        assert_eq!(
+            component.perform(Cmd::GoTo(Position::Begin)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bB0"))))
+        );
+        // GoTo begin (unchanged)
+        assert_eq!(
+            component.perform(Cmd::GoTo(Position::Begin)),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_perform_go_to_end() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bB1");
+        // GoTo end (changed)
+        assert_eq!(
+            component.perform(Cmd::GoTo(Position::End)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bB5"))))
+        );
+        // GoTo end (unchanged)
+        assert_eq!(component.perform(Cmd::GoTo(Position::End)), CmdResult::None);
+    }
+
+    #[test]
+    fn should_perform_move_down() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("cA1");
+        // Move down (changed)
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("cA2"))))
+        );
+        // Move down (unchanged)
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_perform_move_up() {
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("a");
+        // Move up (changed)
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Up)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("/"))))
+        );
+        // Move up (unchanged)
+        assert_eq!(component.perform(Cmd::Move(Direction::Up)), CmdResult::None);
+    }
+
+    #[test]
+    fn should_perform_scroll_down() {
+        let mut component = TreeView::default()
+            .scroll_step(2)
+            .with_tree(mock_tree())
+            .initial_node("cA0");
+        // Scroll down (changed)
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("cA2"))))
+        );
+        // Scroll down (unchanged)
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Down)),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_perform_scroll_up() {
+        let mut component = TreeView::default()
+            .scroll_step(4)
+            .with_tree(mock_tree())
+            .initial_node("aA1");
+        // Scroll Up (changed)
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Up)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("/"))))
+        );
+        // Scroll Up (unchanged)
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Up)),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_scroll_by_viewport_percentage() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .scroll_step_percent(50)
+            .with_tree(mock_tree())
+            .initial_node("aA0");
+        // No viewport yet: one row
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA1"))))
+        );
+        // Half of 4 rows
+        let mut terminal = Terminal::new(TestBackend::new(12, 6)).unwrap();
+        let _ = terminal.draw(|f| component.view(f, f.area()));
+        assert_eq!(component.tree_state().viewport_height(), 4);
+        assert_eq!(
+            component.perform(Cmd::Scroll(Direction::Up)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+    }
+
+    #[test]
+    fn should_perform_submit() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA1");
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("aA1"))))
+        );
+    }
+
+    #[test]
+    fn should_show_more_children_on_submit() {
+        let mut component = TreeView::default()
+            .max_children(1)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        // 'aB' is the "more" row of 'a'
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        assert_eq!(
+            component
+                .tree_state()
+                .hidden_children(component.tree().root().query(&String::from("a")).unwrap()),
+            1
+        );
+        // 'aB' is now shown
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("aB"))))
+        );
+    }
+
+    #[test]
+    fn should_undo_and_redo_state_changes() {
+        let mut component = TreeView::default()
+            .undo_depth(2)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        // Nothing to undo
+        assert_eq!(component.perform(TreeCmd::Undo.into()), CmdResult::None);
+        component.perform(TreeCmd::Open.into());
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.tree_state().selected().unwrap(), "aA0");
+        component.perform(TreeCmd::Close.into());
+        // Undo move
+        assert_eq!(
+            component.perform(TreeCmd::Undo.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        // Undo open; depth is 2, so nothing else can be undone
+        component.perform(TreeCmd::Undo.into());
+        let aa = component.tree().root().query(&String::from("aA")).unwrap();
+        assert!(component.tree_state().is_closed(aa));
+        assert_eq!(component.perform(TreeCmd::Undo.into()), CmdResult::None);
+        // Redo
+        assert_eq!(
+            component.perform(TreeCmd::Redo.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        let aa = component.tree().root().query(&String::from("aA")).unwrap();
+        assert!(component.tree_state().is_open(aa));
+        // A change discards redo
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(component.perform(TreeCmd::Redo.into()), CmdResult::None);
+    }
+
+    #[test]
+    fn should_commit_soft_selection() {
+        let mut component = TreeView::default()
+            .soft_selection(true)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        // Initial node is committed
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aA")))
+        );
+        // Moving the cursor doesn't change the state
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aA")))
+        );
+        // Commit
+        assert_eq!(
+            component.perform(TreeCmd::Commit.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        assert_eq!(component.perform(TreeCmd::Commit.into()), CmdResult::None);
+        // Submit commits too
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("aC"))))
+        );
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aC")))
+        );
+        // Committed node is dropped when it's removed from the tree
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("a"));
+        component.set_tree(tree);
+        assert_eq!(component.state(), State::None);
+    }
+
+    #[test]
+    fn should_watch_selection() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        let mut receiver = component.watch_selection();
+        assert_eq!(receiver.borrow().as_deref(), Some("aA"));
+        component.perform(Cmd::Move(Direction::Down));
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().as_deref(), Some("aB"));
+        // Commands which don't change the selection are not published
+        component.perform(TreeCmd::Open.into());
+        assert!(!receiver.has_changed().unwrap());
+        // Replacing the children including the selected node selects their parent
+        assert!(
+            component.set_children("a", vec![Node::new(String::from("aZ"), String::from("aZ"))])
+        );
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().as_deref(), Some("a"));
+        // Tree changes are published too
+        component.set_tree(mock_tree());
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().as_deref(), Some("/"));
+    }
+
+    #[test]
+    fn should_report_batch_changes_once() {
+        let mut component = TreeView::default()
+            .undo_depth(8)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        let mut receiver = component.watch_selection();
+        let result = component.batch(|view| {
+            view.set_tree(mock_tree());
+            assert_eq!(view.perform(TreeCmd::NextBookmark.into()), CmdResult::None);
+            view.bookmark("bB1");
+            assert_eq!(view.perform(TreeCmd::NextBookmark.into()), CmdResult::None);
+            view.set_flag("bB1", Flag::MARKED);
+            assert!(!receiver.has_changed().unwrap());
+        });
+        assert_eq!(
+            result,
+            CmdResult::Changed(State::One(StateValue::String(String::from("bB1"))))
+        );
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().as_deref(), Some("bB1"));
+        // The whole batch is undone at once
+        assert_eq!(
+            component.perform(TreeCmd::Undo.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        // Nested batches end with the outermost one
+        component.begin_batch();
+        component.begin_batch();
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.end_batch(), CmdResult::None);
+        assert_eq!(
+            component.end_batch(),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        assert_eq!(component.batch(|_| {}), CmdResult::None);
+    }
+
+    #[test]
+    fn should_track_recently_submitted() {
+        let mut component = TreeView::default()
+            .recent_limit(2)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        assert!(component.recently_submitted().is_empty());
+        component.perform(Cmd::Submit);
+        component.perform(Cmd::Move(Direction::Down));
+        component.perform(Cmd::Submit);
+        assert_eq!(component.recently_submitted(), &["aB", "aA"]);
+        // Submitting again moves the node on top
+        component.perform(Cmd::Move(Direction::Up));
+        component.perform(Cmd::Submit);
+        assert_eq!(component.recently_submitted(), &["aA", "aB"]);
+        component.perform(Cmd::Move(Direction::Up));
+        component.perform(Cmd::Submit);
+        assert_eq!(component.recently_submitted(), &["a", "aA"]);
+    }
+
+    #[test]
+    fn should_cycle_through_bookmarks() {
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("a");
+        assert_eq!(
+            component.perform(TreeCmd::NextBookmark.into()),
+            CmdResult::None
+        );
+        assert!(!component.bookmark("none"));
+        assert!(component.bookmark("cA1"));
+        assert!(component.bookmark("aB2"));
+        assert!(component.bookmark("aB2"));
+        assert_eq!(component.bookmarks(), &["cA1", "aB2"]);
+        // Bookmarks are cycled in the order they appear in the tree, revealing them
+        assert_eq!(
+            component.perform(TreeCmd::NextBookmark.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB2"))))
+        );
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("aB")).unwrap()));
+        component.perform(TreeCmd::NextBookmark.into());
+        assert_eq!(component.tree_state().selected().unwrap(), "cA1");
+        // Wrap around
+        component.perform(TreeCmd::NextBookmark.into());
+        assert_eq!(component.tree_state().selected().unwrap(), "aB2");
+        component.perform(TreeCmd::PrevBookmark.into());
+        assert_eq!(component.tree_state().selected().unwrap(), "cA1");
+        component.remove_bookmark("aB2");
+        assert!(!component.is_bookmarked("aB2"));
+        assert_eq!(
+            component.perform(TreeCmd::PrevBookmark.into()),
+            CmdResult::None
+        );
+        // Bookmarks of removed nodes are pruned
+        component.set_tree(Tree::new(Node::new(String::from("/"), String::from("/"))));
+        assert!(component.bookmarks().is_empty());
+    }
+
+    #[test]
+    fn should_restore_persisted_state() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.bookmark("bB");
+        let persisted = component.persisted_state();
+        assert_eq!(
+            persisted,
+            PersistedState {
+                open: vec![String::from("a"), String::from("/")],
+                selected: Some(String::from("aA")),
+                bookmarks: vec![String::from("bB")],
+            }
+        );
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("/");
+        component.restore_persisted_state(PersistedState {
+            bookmarks: vec![String::from("bB"), String::from("none")],
+            ..persisted
+        });
+        assert_eq!(component.tree_state().selected().unwrap(), "aA");
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("a")).unwrap()));
+        assert_eq!(component.bookmarks(), &["bB"]);
+    }
+
+    #[test]
+    fn should_report_state_map() {
+        let mut component = TreeView::default()
+            .state_map(true)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        let state = component.state();
+        assert_eq!(
+            state,
+            State::Map(HashMap::from([
+                (
+                    String::from("selected"),
+                    StateValue::String(String::from("aA"))
+                ),
+                (String::from("open"), StateValue::Usize(2)),
+                (
+                    String::from("open.0"),
+                    StateValue::String(String::from("a"))
+                ),
+                (
+                    String::from("open.1"),
+                    StateValue::String(String::from("/"))
+                ),
+            ]))
+        );
+        assert_eq!(
+            PersistedState::from_state(state),
+            Some(PersistedState {
+                open: vec![String::from("a"), String::from("/")],
+                selected: Some(String::from("aA")),
+                bookmarks: Vec::new(),
+            })
+        );
+        component.tree_state_mut().clear_selection();
+        assert_eq!(
+            PersistedState::from_state(component.state())
+                .unwrap()
+                .selected,
+            None
+        );
+        assert_eq!(
+            PersistedState::from_state(State::One(StateValue::String(String::from("aA")))),
+            None
+        );
+    }
+
+    #[test]
+    fn should_lock_navigation_to_subtree() {
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("a");
+        assert!(!component.lock_navigation("none"));
+        assert!(component.lock_navigation("aB"));
+        assert_eq!(component.navigation_lock(), Some("aB"));
+        assert_eq!(component.tree_state().selected().unwrap(), "aB");
+        // Can't move above the subtree
+        assert_eq!(component.perform(Cmd::Move(Direction::Up)), CmdResult::None);
+        assert_eq!(component.tree_state().selected().unwrap(), "aB");
+        component.perform(TreeCmd::Open.into());
+        // Can't move below the last visible node of the subtree
+        component.perform(Cmd::Scroll(Direction::Down));
+        assert_eq!(component.tree_state().selected().unwrap(), "aB2");
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::None
+        );
+        assert_eq!(component.tree_state().selected().unwrap(), "aB2");
+        component.perform(Cmd::GoTo(Position::Begin));
+        assert_eq!(component.tree_state().selected().unwrap(), "aB0");
+        component.unlock_navigation();
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(component.tree_state().selected().unwrap(), "aB");
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(component.tree_state().selected().unwrap(), "aA");
+    }
+
+    #[test]
+    fn should_clear_dirty_nodes_when_revealed() {
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("a");
+        component.mark_dirty("bA0");
+        component.mark_dirty("aA");
+        assert!(component.is_dirty("bA0"));
+        assert!(component.is_dirty("aA"));
+        assert_eq!(
+            component.dirty_ancestors(),
+            HashSet::from([String::from("a"), String::from("b"), String::from("bA")])
+        );
+        // Opening "a" reveals "aA"
+        component.perform(TreeCmd::Open.into());
+        assert!(!component.is_dirty("aA"));
+        assert!(component.is_dirty("bA0"));
+        // Selecting clears the mark too
+        component.perform(Cmd::Move(Direction::Down));
+        component.mark_dirty("aA");
+        assert!(!component.is_dirty("aA"));
+        component.clear_dirty("bA0");
+        assert!(!component.is_dirty("bA0"));
+    }
+
+    #[test]
+    fn should_perform_submit_with_payload() {
+        #[derive(Default)]
+        struct Song(String, u32);
+
+        impl NodeValue for Song {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0.as_str(), None))
+            }
+
+            fn submit_value(&self) -> Option<StateValue> {
+                match self.1 {
+                    0 => None,
+                    id => Some(StateValue::U32(id)),
+                }
+            }
         }
+
+        let tree = || {
+            Tree::new(
+                Node::new(String::from("/"), Song::default())
+                    .with_child(Node::new(String::from("a"), Song(String::from("A"), 42))),
+            )
+        };
+        let mut component = TreeView::default().with_tree(tree()).initial_node("a");
+        // Compatibility: id is returned
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("a"))))
+        );
+        component.attr(
+            Attribute::Custom(TREE_SUBMIT_PAYLOAD),
+            AttrValue::Flag(true),
+        );
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::U32(42)))
+        );
+        // Fallback to id
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("/"))))
+        );
+        // Enabled with builder
+        let mut component = TreeView::default()
+            .submit_payload(true)
+            .with_tree(tree())
+            .initial_node("a");
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::U32(42)))
+        );
+    }
+
+    #[test]
+    fn should_perform_close() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA1");
+        component.states.open(component.tree.root());
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            CmdResult::None
+        );
+        assert!(component
+            .tree_state()
+            .is_closed(component.tree().root().query(&String::from("aA1")).unwrap()));
+    }
+
+    #[test]
+    fn should_perform_close_parent() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bA0!");
+        assert_eq!(
+            component.perform(TreeCmd::CloseParent.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bA0"))))
+        );
+        let tree = component.tree().root();
+        assert!(component
+            .tree_state()
+            .is_closed(tree.query(&String::from("bA0")).unwrap()));
+        assert!(component
+            .tree_state()
+            .is_open(tree.query(&String::from("bA")).unwrap()));
+        assert_eq!(
+            component.perform(TreeCmd::CloseParent.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bA"))))
+        );
+        component.perform(TreeCmd::CloseParent.into());
+        component.perform(TreeCmd::CloseParent.into());
+        assert!(component.tree_state().is_closed(component.tree().root()));
+        // Root has no parent
+        assert_eq!(
+            component.perform(TreeCmd::CloseParent.into()),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_perform_goto_parent() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bB4");
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_GOTO_PARENT)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bB"))))
+        );
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("bB")).unwrap()));
+        component.perform(TreeCmd::GotoParent.into());
+        assert_eq!(
+            component.perform(TreeCmd::GotoParent.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("/"))))
+        );
+        // Root has no parent
+        assert_eq!(
+            component.perform(TreeCmd::GotoParent.into()),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_perform_close_to_parent() {
+        let mut component = TreeView::default()
+            .close_to_parent(true)
+            .with_tree(mock_tree())
+            .initial_node("aA1");
+        // Leaf: jump to parent
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        // Open node: close it
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            CmdResult::None
+        );
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aA")))
+        );
+        // Closed node: jump to parent
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("a"))))
+        );
+        component.perform(Cmd::Custom(TREE_CMD_CLOSE));
+        component.perform(Cmd::Custom(TREE_CMD_CLOSE));
+        component.perform(Cmd::Custom(TREE_CMD_CLOSE));
+        // Root has no parent
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            CmdResult::None
+        );
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("/")))
+        );
+    }
+
+    #[test]
+    fn should_perform_open() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            CmdResult::None
+        );
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("aA")).unwrap()));
+    }
+
+    #[test]
+    fn should_apply_defaults() {
+        let defaults = TreeDefaults {
+            foreground: Some(Color::Yellow),
+            highlight_symbol: Some(String::from(">")),
+            indent_size: Some(2),
+            scroll_step: Some(6),
+            ..Default::default()
+        };
+        let component: TreeView<String> =
+            TreeView::default().with_defaults(&defaults).indent_size(3);
+        assert_eq!(
+            component.query(Attribute::Foreground).unwrap(),
+            AttrValue::Color(Color::Yellow)
+        );
+        assert_eq!(
+            component.query(Attribute::HighlightedStr).unwrap(),
+            AttrValue::String(String::from(">"))
+        );
+        assert_eq!(
+            component.query(Attribute::ScrollStep).unwrap(),
+            AttrValue::Length(6)
+        );
+        // Overridden after defaults
+        assert_eq!(component.get_indent_size(), 3);
+        // Unset defaults are not applied
+        assert!(component.query(Attribute::Background).is_none());
+    }
+
+    #[test]
+    fn should_apply_theme() {
+        let mut component = TreeView::default()
+            .borders(Borders::default().modifiers(tuirealm::props::BorderType::Rounded))
+            .with_tree(mock_tree());
+        component.apply_theme(TreeTheme {
+            foreground: Color::Yellow,
+            background: Color::Black,
+            highlighted_color: None,
+            inactive: Style::default().fg(Color::Gray),
+            borders: Color::LightYellow,
+            modifiers: TextModifiers::BOLD,
+            guides: Style::default().fg(Color::DarkGray),
+            icons: Style::default().fg(Color::Cyan),
+        });
+        assert_eq!(
+            component.query(Attribute::Foreground).unwrap(),
+            AttrValue::Color(Color::Yellow)
+        );
+        assert_eq!(
+            component.query(Attribute::Background).unwrap(),
+            AttrValue::Color(Color::Black)
+        );
+        // Highlighted color falls back to foreground
+        assert_eq!(
+            component.query(Attribute::HighlightedColor).unwrap(),
+            AttrValue::Color(Color::Yellow)
+        );
+        assert_eq!(
+            component.query(Attribute::FocusStyle).unwrap(),
+            AttrValue::Style(Style::default().fg(Color::Gray))
+        );
+        // Borders should keep modifiers
+        let borders = component
+            .query(Attribute::Borders)
+            .unwrap()
+            .unwrap_borders();
+        assert_eq!(borders.color, Color::LightYellow);
+        assert_eq!(borders.modifiers, tuirealm::props::BorderType::Rounded);
+        assert_eq!(
+            component.query(Attribute::TextProps).unwrap(),
+            AttrValue::TextModifiers(TextModifiers::BOLD)
+        );
+        assert_eq!(
+            component
+                .query(Attribute::Custom(TREE_GUIDES_STYLE))
+                .unwrap(),
+            AttrValue::Style(Style::default().fg(Color::DarkGray))
+        );
+        assert_eq!(
+            component.query(Attribute::Custom(TREE_ICON_STYLE)).unwrap(),
+            AttrValue::Style(Style::default().fg(Color::Cyan))
+        );
     }
 
-    fn perform(&mut self, cmd: Cmd) -> CmdResult {
-        match cmd {
-            Cmd::GoTo(Position::Begin) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                // Get first sibling of current node
-                if let Some(first) = self.states.first_sibling(self.tree.root()) {
-                    self.states.select(self.tree.root(), first);
-                }
-                self.changed(prev.as_deref())
-            }
-            Cmd::GoTo(Position::End) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                // Get first sibling of current node
-                if let Some(last) = self.states.last_sibling(self.tree.root()) {
-                    self.states.select(self.tree.root(), last);
-                }
-                self.changed(prev.as_deref())
-            }
-            Cmd::Move(Direction::Down) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                self.states.move_down(self.tree.root());
-                self.changed(prev.as_deref())
-            }
-            Cmd::Move(Direction::Up) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                self.states.move_up(self.tree.root());
-                self.changed(prev.as_deref())
-            }
-            Cmd::Scroll(Direction::Down) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                let step = self
-                    .props
-                    .get_or(Attribute::ScrollStep, AttrValue::Length(8))
-                    .unwrap_length();
-                (0..step).for_each(|_| self.states.move_down(self.tree.root()));
-                self.changed(prev.as_deref())
-            }
-            Cmd::Scroll(Direction::Up) => {
-                let prev = self.states.selected().map(|x| x.to_string());
-                let step = self
-                    .props
-                    .get_or(Attribute::ScrollStep, AttrValue::Length(8))
-                    .unwrap_length();
-                (0..step).for_each(|_| self.states.move_up(self.tree.root()));
-                self.changed(prev.as_deref())
-            }
-            Cmd::Submit => CmdResult::Submit(self.state()),
-            Cmd::Custom(TREE_CMD_CLOSE) => {
-                // close selected node
-                self.states.close(self.tree.root());
-                CmdResult::None
-            }
-            Cmd::Custom(TREE_CMD_OPEN) => {
-                // close selected node
-                self.states.open(self.tree.root());
-                CmdResult::None
-            }
-            _ => CmdResult::None,
-        }
+    #[test]
+    fn should_get_selected_node() {
+        let component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bA0");
+        assert_eq!(component.selected_node().unwrap().id().as_str(), "bA0");
+        assert!(TreeView::<String>::default().selected_node().is_none());
     }
-}
 
-#[cfg(test)]
-mod test {
+    #[test]
+    fn should_restore_positions_by_root() {
+        let subtree = || {
+            Tree::new(
+                mock_tree()
+                    .root()
+                    .query(&String::from("b"))
+                    .unwrap()
+                    .clone(),
+            )
+        };
+        let mut component = TreeView::default()
+            .remember_positions(4)
+            .with_tree(mock_tree())
+            .initial_node("aB");
+        component.states.set_viewport(2, 4);
+        // Navigate into 'b'
+        component.set_tree(subtree());
+        assert_eq!(component.tree_state().selected().unwrap(), "b");
+        component.perform(TreeCmd::Open.into());
+        component.perform(Cmd::Move(Direction::Down));
+        // And back
+        component.set_tree(mock_tree());
+        assert_eq!(component.tree_state().selected().unwrap(), "aB");
+        assert_eq!(component.tree_state().offset(), 2);
+        component.set_tree(subtree());
+        assert_eq!(component.tree_state().selected().unwrap(), "bA");
+        // Setting a tree with the same root doesn't restore anything
+        component.set_tree(subtree());
+        assert_eq!(component.tree_state().selected().unwrap(), "b");
+    }
 
-    use super::*;
-    use crate::mock::mock_tree;
+    #[test]
+    #[cfg(all(debug_assertions, feature = "strict"))]
+    #[should_panic(expected = "node id \"aA\" is used by more than one node")]
+    fn should_panic_on_duplicate_ids_in_strict_mode() {
+        let mut tree = mock_tree();
+        tree.root_mut()
+            .query_mut(&String::from("b"))
+            .unwrap()
+            .add_child(Node::new(String::from("aA"), String::from("aA")));
+        let _ = TreeView::default().with_tree(tree);
+    }
 
-    use pretty_assertions::assert_eq;
+    #[test]
+    #[cfg(all(debug_assertions, feature = "strict"))]
+    #[should_panic(expected = "node id \"aA0\" is used by more than one node")]
+    fn should_panic_on_duplicate_ids_from_set_children_in_strict_mode() {
+        let mut component = TreeView::default().with_tree(mock_tree());
+        component.set_children(
+            "c",
+            vec![Node::new(String::from("aA0"), String::from("aA0"))],
+        );
+    }
 
     #[test]
-    fn should_initialize_component() {
+    #[cfg(all(debug_assertions, feature = "strict"))]
+    #[should_panic(expected = "selected node \"aA0\" doesn't exist in the tree")]
+    fn should_panic_on_stale_state_at_view_in_strict_mode() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
-            .background(Color::White)
-            .foreground(Color::Cyan)
-            .borders(Borders::default())
-            .inactive(Style::default())
-            .indent_size(4)
-            .modifiers(TextModifiers::all())
-            .preserve_state(true)
-            .scroll_step(4)
-            .title("My tree", Alignment::Center)
             .with_tree(mock_tree())
-            .initial_node("aB1");
-        // Check tree
-        assert_eq!(component.tree_state().selected().unwrap(), "aB1");
-        assert!(component.tree().root().query(&String::from("aB")).is_some());
+            .initial_node("aA0");
         component
             .tree_mut()
             .root_mut()
-            .add_child(Node::new(String::from("d"), String::from("d")));
+            .remove_child(&String::from("a"));
+        let mut terminal = Terminal::new(TestBackend::new(12, 5)).unwrap();
+        let _ = terminal.draw(|f| component.view(f, f.area()));
+    }
+
+    #[test]
+    fn should_change_tree_state() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.tree_state_mut().clear_selection();
+        assert!(component.selected_node().is_none());
+        assert_eq!(component.state(), State::None);
+        *component.tree_state_mut() = TreeState::default();
+        let root = component.tree().root();
+        assert!(component.tree_state().is_closed(root));
+    }
+
+    #[test]
+    fn should_get_children_ids() {
+        let component = TreeView::default().with_tree(mock_tree());
+        assert_eq!(component.children_ids("a").unwrap(), vec!["aA", "aB", "aC"]);
+        assert!(component.children_ids("aA0").unwrap().is_empty());
+        assert!(component.children_ids("none").is_none());
+        assert_eq!(component.child_count("bB"), Some(6));
+        assert_eq!(component.child_count("bB0"), Some(0));
+        assert_eq!(component.child_count("none"), None);
+    }
+
+    #[test]
+    fn should_describe_selected_node() {
+        let component = TreeView::default()
+            .description_parts(&[DescriptionPart::Label, DescriptionPart::Position])
+            .with_tree(mock_tree())
+            .initial_node("bB3");
+        assert_eq!(component.selected_description(), "bB3, item 4 of 6");
+        assert_eq!(
+            TreeView::<String>::default().selected_description(),
+            String::new()
+        );
+    }
+
+    #[test]
+    fn should_get_selected_subtree() {
+        let component = TreeView::default().with_tree(mock_tree());
+        assert!(component.selected_subtree().is_none());
+        let component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bA");
+        let subtree = component.selected_subtree().unwrap();
+        assert_eq!(subtree.root().id().as_str(), "bA");
+        assert_eq!(subtree.root().count(), 5);
+        assert!(subtree.root().query(&String::from("bA0!")).is_some());
+        assert!(subtree.root().query(&String::from("bB")).is_none());
+    }
+
+    #[test]
+    fn should_apply_queued_tree_after_debounce() {
+        let mut component = TreeView::default()
+            .debounce(Duration::from_millis(500))
+            .with_tree(mock_tree());
+        let now = Instant::now();
+        // Nothing to apply
+        assert!(!component.apply_queued(now));
+        // Queue and apply
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("a"));
+        component.queue_tree(tree);
+        assert!(component.has_queued_tree());
+        assert!(component.apply_queued(now));
+        assert!(component.tree().root().query(&String::from("a")).is_none());
+        // Queue twice; only the latest must be applied after the interval
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("b"));
+        component.queue_tree(tree);
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("c"));
+        component.queue_tree(tree);
+        assert!(!component.apply_queued(now + Duration::from_millis(100)));
+        assert!(component.has_queued_tree());
+        assert!(component.apply_queued(now + Duration::from_millis(500)));
+        assert!(component.tree().root().query(&String::from("b")).is_some());
+        assert!(component.tree().root().query(&String::from("c")).is_none());
+        assert!(!component.has_queued_tree());
+    }
+
+    #[test]
+    fn should_hide_highlight_symbol_when_not_focused() {
+        let component = TreeView::<String>::default().highlight_symbol(">");
+        assert_eq!(component.get_highlight_symbol(true).as_deref(), Some(">"));
+        assert_eq!(component.get_highlight_symbol(false).as_deref(), Some(">"));
+        let component = component.highlight_symbol_focus_only(true);
+        assert_eq!(component.get_highlight_symbol(true).as_deref(), Some(">"));
+        assert_eq!(component.get_highlight_symbol(false), None);
+    }
+
+    #[test]
+    fn should_perform_view_commands() {
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("bB2");
+        component.states.set_viewport(0, 4);
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_VIEW_TOP)),
+            CmdResult::None
+        );
+        assert_eq!(component.tree_state().offset(), 7);
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_VIEW_BOTTOM)),
+            CmdResult::None
+        );
+        assert_eq!(component.tree_state().offset(), 4);
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_VIEW_CENTER)),
+            CmdResult::None
+        );
+        assert_eq!(component.tree_state().offset(), 5);
+    }
+
+    #[test]
+    fn should_expand_on_select() {
+        // Immediately
+        let mut component = TreeView::default()
+            .expand_on_select(0)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("aB")))
+        );
+        assert!(component
+            .tree_state()
+            .is_open(component.tree().root().query(&String::from("aB")).unwrap()));
+        // After ticks
+        let mut component = TreeView::default()
+            .expand_on_select(2)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        // Selection changed; ticks are reset
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        let ac = component.tree().root().query(&String::from("aC")).unwrap();
+        assert!(component.tree_state().is_closed(ac));
+        assert_eq!(
+            component.perform(Cmd::Tick),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aC"))))
+        );
+        let ac = component.tree().root().query(&String::from("aC")).unwrap();
+        assert!(component.tree_state().is_open(ac));
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        // Disabled
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        let ab = component.tree().root().query(&String::from("aB")).unwrap();
+        assert!(component.tree_state().is_closed(ab));
+    }
+
+    #[test]
+    fn should_set_flags() {
+        let mut component = TreeView::default()
+            .submit_flag(Flag::MARKED)
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        assert!(component.ids_with_flag(Flag::MARKED).is_empty());
+        component.set_flag("bB1", Flag::MARKED);
+        component.set_flag("aA0", Flag::MARKED);
+        component.set_flag("aA0", Flag::new("queued"));
+        assert!(component.toggle_flag("c", Flag::MARKED));
+        assert!(component.has_flag("aA0", Flag::MARKED));
+        assert!(!component.has_flag("aA1", Flag::MARKED));
+        // In tree order
+        assert_eq!(
+            component.ids_with_flag(Flag::MARKED),
+            vec![String::from("aA0"), String::from("bB1"), String::from("c")]
+        );
+        assert!(!component.toggle_flag("c", Flag::MARKED));
+        component.unset_flag("aA0", Flag::MARKED);
+        assert_eq!(
+            component.ids_with_flag(Flag::new("queued")),
+            vec![String::from("aA0")]
+        );
+        // Submit flagged
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::Vec(vec![StateValue::String(String::from("bB1"))]))
+        );
+        // Pruned on tree changed
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("b"));
+        component.set_tree(tree);
+        assert!(component.ids_with_flag(Flag::MARKED).is_empty());
+        // Clear
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_CLEAR_FLAGS)),
+            CmdResult::None
+        );
+        assert!(!component.has_flag("aA0", Flag::new("queued")));
+        // Select all visible
+        component.attr(
+            Attribute::Custom(TREE_INITIAL_NODE),
+            AttrValue::String(String::from("aA0")),
+        );
+        component.set_flag("a", Flag::new("queued"));
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_SELECT_ALL_VISIBLE)),
+            CmdResult::None
+        );
+        assert_eq!(
+            component.selected_ids(),
+            vec![
+                String::from("/"),
+                String::from("a"),
+                String::from("aA"),
+                String::from("aA0"),
+                String::from("aA1"),
+                String::from("aA2"),
+                String::from("aB"),
+                String::from("aC"),
+                String::from("c"),
+            ]
+        );
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_SELECT_NONE)),
+            CmdResult::None
+        );
+        assert!(component.selected_ids().is_empty());
+        assert!(component.has_flag("a", Flag::new("queued")));
+        component.clear_flags();
+        // Submit selected when nothing is flagged
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("aA0"))))
+        );
+    }
+
+    #[test]
+    fn should_unload_closed_branches() {
+        let mut component = TreeView::default()
+            .unload_closed(Duration::from_secs(60), |node: &Node<String>| {
+                (0..2)
+                    .map(|i| {
+                        let id = format!("{}{}", node.id(), i);
+                        Node::new(id.clone(), id)
+                    })
+                    .collect()
+            })
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.perform(Cmd::Custom(TREE_CMD_OPEN));
+        component.perform(Cmd::Custom(TREE_CMD_CLOSE));
+        // Not expired yet
+        assert!(component.unload_closed_branches(Instant::now()).is_empty());
+        assert_eq!(
+            component.unload_closed_branches(Instant::now() + Duration::from_secs(60)),
+            vec![String::from("aA")]
+        );
+        let aa = component.tree().root().query(&String::from("aA")).unwrap();
+        assert!(aa.is_leaf());
+        assert!(component.tree_state().is_unloaded(aa));
+        // Reopen
+        component.perform(Cmd::Custom(TREE_CMD_OPEN));
+        let aa = component.tree().root().query(&String::from("aA")).unwrap();
+        assert_eq!(aa.count(), 3);
+        assert!(component
+            .tree()
+            .root()
+            .query(&String::from("aA1"))
+            .is_some());
+        assert!(component.tree_state().is_open(aa));
+        assert!(!component.tree_state().is_unloaded(aa));
+        // Open branches are not unloaded
+        assert!(component
+            .unload_closed_branches(Instant::now() + Duration::from_secs(120))
+            .is_empty());
+    }
+
+    #[test]
+    fn should_open_all_reloading_unloaded_branches() {
+        let mut component = TreeView::default()
+            .unload_closed(Duration::from_secs(60), |node: &Node<String>| {
+                vec![Node::new(
+                    format!("{}0", node.id()),
+                    format!("{}0", node.id()),
+                )]
+            })
+            .with_tree(mock_tree())
+            .initial_node("aA");
+        component.perform(TreeCmd::Open.into());
+        component.perform(TreeCmd::Close.into());
+        component.unload_closed_branches(Instant::now() + Duration::from_secs(60));
+        component.perform(Cmd::GoTo(Position::Begin));
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(component.tree_state().selected().unwrap(), "a");
+        assert_eq!(component.perform(TreeCmd::OpenAll.into()), CmdResult::None);
+        let aa = component.tree().root().query(&String::from("aA")).unwrap();
+        assert!(component.tree_state().is_open(aa));
+        assert_eq!(component.child_count("aA"), Some(1));
+        assert_eq!(
+            component
+                .tree_state()
+                .visible_ids(component.tree().root())
+                .len(),
+            12
+        );
+        assert_eq!(component.perform(TreeCmd::CloseAll.into()), CmdResult::None);
+        assert_eq!(
+            component
+                .tree_state()
+                .visible_ids(component.tree().root())
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn should_change_indent_size_at_runtime() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .indent_size(2)
+            .with_tree(mock_tree())
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 5)).unwrap();
+        let mut render = |component: &mut TreeView<String>| -> Vec<String> {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..4)
+                .map(|y| (1..11).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect()
+        };
+        assert_eq!(
+            render(&mut component),
+            vec!["  / ▼     ", "    a ▶   ", "    b ▶   "]
+        );
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_INDENT_INC)),
+            CmdResult::None
+        );
+        component.perform(Cmd::Custom(TREE_CMD_INDENT_INC));
+        assert_eq!(
+            render(&mut component),
+            vec!["    / ▼   ", "        a ", "        b "]
+        );
+        (0..6).for_each(|_| {
+            component.perform(Cmd::Custom(TREE_CMD_INDENT_DEC));
+        });
+        assert_eq!(component.get_indent_size(), 0);
+    }
+
+    #[test]
+    fn should_open_empty_branches() {
+        #[derive(Default)]
+        struct Entry {
+            name: &'static str,
+            dir: bool,
+        }
+
+        impl NodeValue for Entry {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.name, None))
+            }
+
+            fn is_branch_hint(&self) -> bool {
+                self.dir
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(
+                String::from("/"),
+                Entry {
+                    name: "/",
+                    dir: true,
+                },
+            )
+            .with_child(Node::new(
+                String::from("src"),
+                Entry {
+                    name: "src",
+                    dir: true,
+                },
+            ))
+            .with_child(Node::new(
+                String::from("README.md"),
+                Entry {
+                    name: "README.md",
+                    dir: false,
+                },
+            )),
+        );
+        let mut component = TreeView::default().with_tree(tree).initial_node("src");
+        let src = component.tree().root().query(&String::from("src")).unwrap();
+        assert!(component.tree_state().is_branch(src));
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            CmdResult::Custom(
+                TREE_CMD_OPEN,
+                State::One(StateValue::String(String::from("src")))
+            )
+        );
+        let src = component.tree().root().query(&String::from("src")).unwrap();
+        assert!(component.tree_state().is_open(src));
+        // Leaves can't be opened
+        component.perform(Cmd::Move(Direction::Down));
+        let readme = component
+            .tree()
+            .root()
+            .query(&String::from("README.md"))
+            .unwrap();
+        assert!(!component.tree_state().is_branch(readme));
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            CmdResult::None
+        );
+    }
+
+    #[test]
+    fn should_submit_lazy_branches_according_to_policy() {
+        #[derive(Default)]
+        struct Entry(&'static str);
+
+        impl NodeValue for Entry {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn is_branch_hint(&self) -> bool {
+                self.0.ends_with('/')
+            }
+        }
+
+        let tree = || {
+            Tree::new(
+                Node::new(String::from("/"), Entry("/"))
+                    .with_child(Node::new(String::from("src"), Entry("src/")))
+                    .with_child(Node::new(String::from("README.md"), Entry("README.md"))),
+            )
+        };
+        let src = State::One(StateValue::String(String::from("src")));
+        let mut component = TreeView::default().with_tree(tree()).initial_node("src");
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(src.clone())
+        );
+        let mut component = TreeView::default()
+            .lazy_submit(LazySubmit::Load)
+            .with_tree(tree())
+            .initial_node("src");
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Custom(TREE_CMD_OPEN, src.clone())
+        );
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Custom(TREE_CMD_OPEN, src.clone())
+        );
+        let mut component = TreeView::default()
+            .lazy_submit(LazySubmit::Toggle)
+            .with_tree(tree())
+            .initial_node("src");
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Custom(TREE_CMD_OPEN, src.clone())
+        );
+        assert_eq!(component.perform(Cmd::Submit), CmdResult::None);
+        let node = component.tree().root().query(&String::from("src")).unwrap();
+        assert!(component.tree_state().is_closed(node));
+        // Leaves are always submitted
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Submit(State::One(StateValue::String(String::from("README.md"))))
+        );
     }
 
     #[test]
-    fn should_return_consistent_state() {
-        let component = TreeView::default().with_tree(mock_tree());
-        assert_eq!(component.state(), State::None);
-        let component = TreeView::default()
-            .with_tree(mock_tree())
-            .initial_node("aA");
+    fn should_sort_columns() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        #[derive(Default)]
+        struct File(&'static str, u64);
+
+        impl NodeValue for File {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn cell(&self, column: usize) -> Option<Cow<'_, str>> {
+                (column == 1).then(|| Cow::Owned(self.1.to_string()))
+            }
+
+            fn cmp_column(&self, other: &Self, column: usize) -> Ordering {
+                match column {
+                    1 => self.1.cmp(&other.1),
+                    _ => self.0.cmp(other.0),
+                }
+            }
+        }
+
+        let tree = || {
+            Tree::new(
+                Node::new(String::from("/"), File("/", 0))
+                    .with_child(
+                        Node::new(String::from("b"), File("b", 1))
+                            .with_child(Node::new(String::from("bB"), File("bB", 3)))
+                            .with_child(Node::new(String::from("bA"), File("bA", 10))),
+                    )
+                    .with_child(Node::new(String::from("a"), File("a", 5)))
+                    .with_child(Node::new(String::from("c"), File("c", 2))),
+            )
+        };
+        let ids = |component: &TreeView<File>, id: &str| -> Vec<String> {
+            component
+                .tree()
+                .root()
+                .query(&String::from(id))
+                .unwrap()
+                .iter()
+                .map(|x| x.id().to_string())
+                .collect()
+        };
+        let mut component = TreeView::default()
+            .columns(&[("Name", 0), ("Size", 4)])
+            .with_tree(tree())
+            .initial_node("bA");
+        // Ascending, by value
+        assert_eq!(
+            component.perform(TreeCmd::SortColumn(1).into()),
+            CmdResult::None
+        );
+        assert_eq!(component.sorted_column(), Some((1, SortOrder::Ascending)));
+        assert_eq!(ids(&component, "/"), vec!["b", "c", "a"]);
+        assert_eq!(ids(&component, "b"), vec!["bB", "bA"]);
+        let mut terminal = Terminal::new(TestBackend::new(16, 6)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let header: String = (1..15).map(|x| buffer[(x, 1)].symbol()).collect();
+        // The indicator is kept visible in narrow columns
+        assert_eq!(header, "Name      Si \u{25b2}");
+        // Descending
+        component.perform(Cmd::Custom(TREE_CMD_SORT_COLUMN[1]));
+        assert_eq!(ids(&component, "/"), vec!["a", "c", "b"]);
+        assert_eq!(ids(&component, "b"), vec!["bA", "bB"]);
+        // The tree structure and the selection are kept
         assert_eq!(
             component.state(),
-            State::One(StateValue::String(String::from("aA")))
+            State::One(StateValue::String(String::from("bA")))
         );
+        // Unsorted: tree order is restored
+        component.perform(TreeCmd::SortColumn(1).into());
+        assert_eq!(component.sorted_column(), None);
+        assert_eq!(ids(&component, "/"), vec!["b", "a", "c"]);
+        assert_eq!(ids(&component, "b"), vec!["bB", "bA"]);
+        // Another column starts from ascending
+        component.perform(TreeCmd::SortColumn(1).into());
+        component.perform(TreeCmd::SortColumn(0).into());
+        assert_eq!(component.sorted_column(), Some((0, SortOrder::Ascending)));
+        assert_eq!(ids(&component, "/"), vec!["a", "b", "c"]);
+        assert_eq!(ids(&component, "b"), vec!["bA", "bB"]);
+        // New trees are sorted too, and their order is restored
+        component.set_tree(tree());
+        assert_eq!(ids(&component, "/"), vec!["a", "b", "c"]);
+        component.sort_column(0, None);
+        assert_eq!(ids(&component, "/"), vec!["b", "a", "c"]);
     }
 
     #[test]
-    fn should_perform_go_to_begin() {
+    fn should_sort_tree_keeping_selection() {
         let mut component = TreeView::default()
             .with_tree(mock_tree())
-            .initial_node("bB3");
-        // GoTo begin (changed)
+            .initial_node("bB1");
+        component.states.set_viewport(4, 4);
+        // '/', 'a', 'b', 'bA', 'bB', 'bB0', 'bB1'; bB1 is the third row in viewport
         assert_eq!(
-            component.perform(Cmd::GoTo(Position::Begin)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("bB0"))))
+            component
+                .tree_state()
+                .selected_index(component.tree().root()),
+            Some(6)
         );
-        // GoTo begin (unchanged)
+        component.sort_tree(|a, b| b.id().cmp(a.id()));
+        let ids: Vec<&str> = component
+            .tree()
+            .root()
+            .iter()
+            .map(|x| x.id().as_str())
+            .collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+        let ids: Vec<&str> = component
+            .tree()
+            .root()
+            .query(&String::from("bB"))
+            .unwrap()
+            .iter()
+            .map(|x| x.id().as_str())
+            .collect();
+        assert_eq!(ids, vec!["bB5", "bB4", "bB3", "bB2", "bB1", "bB0"]);
+        // '/', 'c', 'b', 'bB', 'bB5', 'bB4', 'bB3', 'bB2', 'bB1'
         assert_eq!(
-            component.perform(Cmd::GoTo(Position::Begin)),
-            CmdResult::None
+            component.state(),
+            State::One(StateValue::String(String::from("bB1")))
+        );
+        assert_eq!(
+            component
+                .tree_state()
+                .selected_index(component.tree().root()),
+            Some(8)
         );
+        assert_eq!(component.tree_state().offset(), 6);
     }
 
     #[test]
-    fn should_perform_go_to_end() {
+    fn should_render_flash_message_for_some_renders() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
+            .footer("footer")
             .with_tree(mock_tree())
-            .initial_node("bB1");
-        // GoTo end (changed)
-        assert_eq!(
-            component.perform(Cmd::GoTo(Position::End)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("bB5"))))
-        );
-        // GoTo end (unchanged)
-        assert_eq!(component.perform(Cmd::GoTo(Position::End)), CmdResult::None);
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 6)).unwrap();
+        // Text of the last row inside borders
+        let mut render = |component: &mut TreeView<String>| {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..11)
+                .map(|x| buffer[(x, 4)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        component.flash_message("copied", 2);
+        assert_eq!(render(&mut component), "copied");
+        assert_eq!(render(&mut component), "copied");
+        assert_eq!(render(&mut component), "footer");
+        component.flash_message("denied", 5);
+        component.clear_flash_message();
+        assert_eq!(render(&mut component), "footer");
     }
 
     #[test]
-    fn should_perform_move_down() {
+    fn should_select_and_submit_on_click() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
+            .double_click_submit(Duration::from_millis(500))
             .with_tree(mock_tree())
-            .initial_node("cA1");
-        // Move down (changed)
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 6)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        // Rows are rendered inside borders: '/', 'a', 'b', 'c'
+        let now = Instant::now();
+        assert_eq!(component.click(0, 3, now), CmdResult::None);
+        assert_eq!(component.click(4, 5, now), CmdResult::None);
         assert_eq!(
-            component.perform(Cmd::Move(Direction::Down)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("cA2"))))
+            component.click(4, 3, now),
+            CmdResult::Changed(State::One(StateValue::String(String::from("b"))))
         );
-        // Move down (unchanged)
+        // Too late for double click
         assert_eq!(
-            component.perform(Cmd::Move(Direction::Down)),
+            component.click(4, 3, now + Duration::from_secs(1)),
             CmdResult::None
         );
+        assert_eq!(
+            component.click(4, 3, now + Duration::from_millis(1200)),
+            CmdResult::Submit(State::One(StateValue::String(String::from("b"))))
+        );
+        // Clicks on different rows
+        component.click(4, 1, now);
+        assert_eq!(
+            component.click(4, 2, now),
+            CmdResult::Changed(State::One(StateValue::String(String::from("a"))))
+        );
     }
 
     #[test]
-    fn should_perform_move_up() {
-        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("a");
-        // Move up (changed)
+    fn should_load_children_with_child_loader() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        #[derive(Default)]
+        struct Lazy(String);
+
+        impl NodeValue for Lazy {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0.as_str(), None))
+            }
+
+            fn is_branch_hint(&self) -> bool {
+                true
+            }
+        }
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let loader_calls = calls.clone();
+        let tree = Tree::new(
+            Node::new(String::from("/"), Lazy::default())
+                .with_child(Node::new(String::from("a"), Lazy::default()))
+                .with_child(Node::new(String::from("b"), Lazy::default())),
+        );
+        let mut component = TreeView::default()
+            .child_loader(move |node: &Node<Lazy>| {
+                loader_calls.borrow_mut().push(node.id().to_string());
+                // Children of 'a' are available right away
+                (node.id() == "a").then(|| vec![Node::new(String::from("a0"), Lazy::default())])
+            })
+            .with_tree(tree)
+            .initial_node("a");
         assert_eq!(
-            component.perform(Cmd::Move(Direction::Up)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("/"))))
+            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            CmdResult::None
         );
-        // Move up (unchanged)
-        assert_eq!(component.perform(Cmd::Move(Direction::Up)), CmdResult::None);
+        assert_eq!(component.children_ids("a").unwrap(), vec!["a0"]);
+        assert!(!component.is_loading("a"));
+        // Children of 'b' are loaded in background
+        component.perform(Cmd::Move(Direction::Down));
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
+            CmdResult::None
+        );
+        assert!(component.is_loading("b"));
+        assert_eq!(
+            component.perform(Cmd::Tick),
+            CmdResult::Changed(component.state())
+        );
+        component.perform(Cmd::Custom(TREE_CMD_OPEN));
+        assert_eq!(*calls.borrow(), vec!["a", "b"]);
+        assert!(component.set_children("b", vec![Node::new(String::from("b0"), Lazy::default())]));
+        assert!(!component.is_loading("b"));
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("b0"))))
+        );
+        // Selected node removed with the children
+        assert!(component.set_children("b", Vec::new()));
+        assert_eq!(component.tree_state().selected().unwrap(), "b");
+        assert!(!component.set_children("c", Vec::new()));
     }
 
     #[test]
-    fn should_perform_scroll_down() {
+    fn should_reorder_siblings() {
         let mut component = TreeView::default()
-            .scroll_step(2)
             .with_tree(mock_tree())
-            .initial_node("cA0");
-        // Scroll down (changed)
+            .initial_node("aA");
+        assert_eq!(component.drop_target(), None);
         assert_eq!(
-            component.perform(Cmd::Scroll(Direction::Down)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("cA2"))))
+            component.perform(Cmd::Custom(TREE_CMD_REORDER)),
+            CmdResult::None
         );
-        // Scroll down (unchanged)
+        // Right below the parent
+        assert_eq!(component.drop_target(), Some("a"));
+        assert_eq!(component.perform(Cmd::Move(Direction::Up)), CmdResult::None);
         assert_eq!(
-            component.perform(Cmd::Scroll(Direction::Down)),
+            component.perform(Cmd::Move(Direction::Down)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        assert_eq!(component.drop_target(), Some("aB"));
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(
+            component.perform(Cmd::Move(Direction::Down)),
             CmdResult::None
         );
+        assert_eq!(component.drop_target(), Some("aC"));
+        assert_eq!(
+            component.perform(Cmd::Submit),
+            CmdResult::Custom(
+                TREE_CMD_REORDER,
+                State::One(StateValue::String(String::from("aA")))
+            )
+        );
+        assert_eq!(component.drop_target(), None);
+        assert_eq!(component.children_ids("a").unwrap(), vec!["aB", "aC", "aA"]);
+        assert_eq!(component.tree_state().selected().unwrap(), "aA");
+        // Lands after the visible descendants of the previous sibling
+        component.perform(Cmd::Move(Direction::Up));
+        component.perform(Cmd::Custom(TREE_CMD_OPEN));
+        component.perform(Cmd::Move(Direction::Down));
+        component.perform(Cmd::Move(Direction::Down));
+        assert_eq!(component.tree_state().selected().unwrap(), "aA");
+        component.perform(Cmd::Custom(TREE_CMD_REORDER));
+        assert_eq!(component.drop_target(), Some("aC0"));
+        // Cancel
+        component.perform(Cmd::Move(Direction::Up));
+        assert_eq!(
+            component.perform(Cmd::Cancel),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        assert_eq!(component.perform(Cmd::Cancel), CmdResult::None);
+        assert_eq!(component.children_ids("a").unwrap(), vec!["aB", "aC", "aA"]);
     }
 
     #[test]
-    fn should_perform_scroll_up() {
+    fn should_toggle_and_scroll_on_mouse_events() {
+        use tuirealm::event::KeyModifiers;
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let event = |kind, column, row| MouseEvent {
+            kind,
+            modifiers: KeyModifiers::NONE,
+            column,
+            row,
+        };
+        let left = MouseEventKind::Down(MouseButton::Left);
         let mut component = TreeView::default()
-            .scroll_step(4)
+            .indent_size(2)
             .with_tree(mock_tree())
-            .initial_node("aA1");
-        // Scroll Up (changed)
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 6)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        // Rows are rendered inside borders: '/', 'a', 'b', 'c'; the arrow of 'b' follows its label: "    b ▶"
+        let now = Instant::now();
+        assert!(!component.tree_state().is_arrow_at(5, 3));
+        assert!(component.tree_state().is_arrow_at(6, 3));
         assert_eq!(
-            component.perform(Cmd::Scroll(Direction::Up)),
-            CmdResult::Changed(State::One(StateValue::String(String::from("/"))))
+            component.on_mouse(event(left, 6, 3), now),
+            CmdResult::Changed(State::One(StateValue::String(String::from("b"))))
         );
-        // Scroll Up (unchanged)
+        let b = component.tree().root().query(&String::from("b")).unwrap();
+        assert!(component.tree_state().is_open(b));
+        // Double click on the label closes it
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
         assert_eq!(
-            component.perform(Cmd::Scroll(Direction::Up)),
+            component.on_mouse(event(left, 4, 3), now + Duration::from_secs(1)),
+            CmdResult::None
+        );
+        component.on_mouse(event(left, 4, 3), now + Duration::from_millis(1200));
+        let b = component.tree().root().query(&String::from("b")).unwrap();
+        assert!(component.tree_state().is_closed(b));
+        // Wheel
+        assert_eq!(
+            component.on_mouse(event(MouseEventKind::ScrollDown, 4, 3), now),
+            CmdResult::Changed(State::One(StateValue::String(String::from("c"))))
+        );
+        assert_eq!(
+            component.on_mouse(event(MouseEventKind::Moved, 4, 3), now),
             CmdResult::None
         );
     }
 
     #[test]
-    fn should_perform_submit() {
+    fn should_scroll_while_dragging_on_edges() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
             .with_tree(mock_tree())
-            .initial_node("aA1");
+            .initial_node("aA");
+        let mut terminal = Terminal::new(TestBackend::new(12, 5)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        // Rows are rendered inside borders: '/', 'a', 'aA'
+        assert_eq!(component.nudge_drag(), CmdResult::None);
         assert_eq!(
-            component.perform(Cmd::Submit),
-            CmdResult::Submit(State::One(StateValue::String(String::from("aA1"))))
+            component.drag(4, 2),
+            CmdResult::Changed(State::One(StateValue::String(String::from("a"))))
+        );
+        // Not on edge
+        assert_eq!(component.perform(Cmd::Tick), CmdResult::None);
+        // Bottom edge
+        assert_eq!(
+            component.drag(4, 3),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA"))))
+        );
+        assert_eq!(
+            component.perform(Cmd::Tick),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aB"))))
+        );
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        assert_eq!(component.tree_state().offset(), 1);
+        assert_eq!(
+            component.nudge_drag(),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aC"))))
         );
+        assert_eq!(component.end_drag(), Some(String::from("aC")));
+        assert_eq!(component.nudge_drag(), CmdResult::None);
+        assert_eq!(component.end_drag(), None);
     }
 
     #[test]
-    fn should_perform_close() {
+    fn should_match_search_text() {
+        #[derive(Default)]
+        struct Entry {
+            name: &'static str,
+            path: Option<&'static str>,
+        }
+
+        impl NodeValue for Entry {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.name, None))
+            }
+
+            fn search_text(&self) -> Option<Cow<'_, str>> {
+                self.path.map(Cow::Borrowed)
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(
+                String::from("/"),
+                Entry {
+                    name: "/",
+                    path: None,
+                },
+            )
+            .with_child(Node::new(
+                String::from("src"),
+                Entry {
+                    name: "src",
+                    path: Some("/home/user/SRC"),
+                },
+            ))
+            .with_child(Node::new(
+                String::from("lib"),
+                Entry {
+                    name: "lib",
+                    path: Some("/home/user/src/lib.rs"),
+                },
+            )),
+        );
+        let component = TreeView::default().with_tree(tree);
+        assert_eq!(
+            component.matching_ids("src"),
+            vec![String::from("src"), String::from("lib")]
+        );
+        assert_eq!(component.matching_ids(".RS"), vec![String::from("lib")]);
+        // root opts out of matching
+        assert_eq!(
+            component.matching_ids("/"),
+            vec![String::from("src"), String::from("lib")]
+        );
+        // labels are used by default
+        let component = TreeView::default().with_tree(mock_tree());
+        assert_eq!(
+            component.matching_ids("ba0"),
+            vec![String::from("bA0"), String::from("bA0!")]
+        );
+    }
+
+    #[test]
+    fn should_render_scrollbar() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
             .with_tree(mock_tree())
-            .initial_node("aA1");
-        component.states.open(component.tree.root());
+            .scrollbar(true)
+            .initial_node("bB5");
+        let mut terminal = Terminal::new(TestBackend::new(16, 6)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        // The last row is visible, so the thumb is at the end of the track
+        assert_eq!(buffer[(14, 1)].symbol(), "\u{2551}");
+        assert_eq!(buffer[(14, 4)].symbol(), "\u{2588}");
+    }
+
+    #[test]
+    fn should_render_with_auto_indent() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .indent_size(6)
+            .auto_indent(1, 8)
+            .initial_node("bA0");
+        let mut terminal = Terminal::new(TestBackend::new(20, 7)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        // 18 columns inside borders, 4 levels: 2 spaces per level
+        assert_eq!(buffer[(3, 1)].symbol(), "/");
+        assert_eq!(buffer[(5, 2)].symbol(), "a");
+        assert_eq!(buffer[(9, 5)].symbol(), "b");
+        assert_eq!(buffer[(10, 5)].symbol(), "A");
+    }
+
+    #[test]
+    fn should_mark_nodes_in_multi_select_mode() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .multi_select(true)
+            .mark_symbol("+")
+            .indent_size(2)
+            .initial_node("bA");
         assert_eq!(
-            component.perform(Cmd::Custom(TREE_CMD_CLOSE)),
+            component.state(),
+            State::One(StateValue::String(String::from("bA")))
+        );
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_TOGGLE_MARK)),
             CmdResult::None
         );
+        assert_eq!(
+            component.state(),
+            State::Vec(vec![StateValue::String(String::from("bA"))])
+        );
+        let mut terminal = Terminal::new(TestBackend::new(16, 5)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        // Marked node is rendered with the mark symbol
+        assert_eq!(terminal.backend().buffer()[(7, 3)].symbol(), "+");
+        assert_eq!(terminal.backend().buffer()[(8, 3)].symbol(), "b");
+        // Toggle again to unmark
+        component.perform(TreeCmd::ToggleMark.into());
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("bA")))
+        );
+        component.select_initial_node("bB2");
+        component.perform(TreeCmd::MarkAllSiblings.into());
+        assert_eq!(
+            component.state(),
+            State::Vec(
+                ["bB0", "bB1", "bB2", "bB3", "bB4", "bB5"]
+                    .into_iter()
+                    .map(|x| StateValue::String(String::from(x)))
+                    .collect()
+            )
+        );
+        component.perform(TreeCmd::SelectNone.into());
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("bB2")))
+        );
+        // Marks are not reported outside of multi-select mode
+        component.attr(Attribute::Custom(TREE_MULTI_SELECT), AttrValue::Flag(false));
+        component.perform(TreeCmd::ToggleMark.into());
+        assert_eq!(component.selected_ids(), vec![String::from("bB2")]);
+        assert_eq!(
+            component.state(),
+            State::One(StateValue::String(String::from("bB2")))
+        );
+    }
+
+    #[test]
+    fn should_search_and_cycle_through_matches() {
+        let mut component = TreeView::default().with_tree(mock_tree()).initial_node("/");
+        assert_eq!(
+            component.search("A0"),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA0"))))
+        );
+        assert_eq!(
+            component.search_matches(),
+            &[
+                String::from("aA0"),
+                String::from("bA0"),
+                String::from("bA0!"),
+                String::from("cA0")
+            ]
+        );
+        // Ancestors are opened
+        let root = component.tree().root();
         assert!(component
             .tree_state()
-            .is_closed(component.tree().root().query(&String::from("aA1")).unwrap()));
+            .is_open(root.query(&String::from("aA")).unwrap()));
+        assert_eq!(
+            component.perform(TreeCmd::SearchNext.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bA0"))))
+        );
+        component.search_next();
+        component.search_next();
+        // Wraps around
+        assert_eq!(
+            component.search_next(),
+            CmdResult::Changed(State::One(StateValue::String(String::from("aA0"))))
+        );
+        assert_eq!(
+            component.perform(TreeCmd::SearchPrev.into()),
+            CmdResult::Changed(State::One(StateValue::String(String::from("cA0"))))
+        );
+        // Typing ahead keeps the selected node while it matches
+        assert_eq!(component.search("cA0"), CmdResult::None);
+        assert_eq!(component.states.selected(), Some("cA0"));
+        // Search with the query prop
+        component.attr(
+            Attribute::Custom(TREE_SEARCH_QUERY),
+            AttrValue::String(String::from("bB")),
+        );
+        assert_eq!(
+            component.perform(Cmd::Custom(TREE_CMD_SEARCH)),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bB"))))
+        );
+        // No match
+        assert_eq!(component.search("zz"), CmdResult::None);
+        assert!(component.search_matches().is_empty());
+        assert_eq!(component.search_next(), CmdResult::None);
+        // Fuzzy search
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .fuzzy_search(true)
+            .initial_node("/");
+        assert_eq!(
+            component.search("ba!"),
+            CmdResult::Changed(State::One(StateValue::String(String::from("bA0!"))))
+        );
+        component.clear_search();
+        assert_eq!(component.search_next(), CmdResult::None);
     }
 
     #[test]
-    fn should_perform_open() {
+    fn should_drop_cached_labels_on_tree_change() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .indent_size(2)
+            .cache_labels(true)
+            .with_tree(mock_tree())
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 4)).unwrap();
+        let mut render = |component: &mut TreeView<String>| -> Vec<String> {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..3)
+                .map(|y| (1..11).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect()
+        };
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    a ▶   "]);
+        component
+            .tree_mut()
+            .root_mut()
+            .query_mut(&String::from("a"))
+            .unwrap()
+            .set_value(String::from("z"));
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    z ▶   "]);
+    }
+
+    #[test]
+    fn should_drop_cached_labels_on_tree_change_preserving_subtree() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .indent_size(2)
+            .cache_labels(true)
+            .preserve_subtree("a")
+            .with_tree(mock_tree())
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 4)).unwrap();
+        let mut render = |component: &mut TreeView<String>| -> Vec<String> {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..3)
+                .map(|y| (1..11).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect()
+        };
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    a ▶   "]);
+        let mut tree = mock_tree();
+        tree.root_mut()
+            .query_mut(&String::from("a"))
+            .unwrap()
+            .set_value(String::from("z"));
+        component.set_tree(tree);
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    z ▶   "]);
+    }
+
+    #[test]
+    fn should_map_displayed_labels() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .indent_size(2)
+            .display_map(|x: &String| Cow::Owned(x.to_uppercase()))
+            .with_tree(mock_tree())
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 4)).unwrap();
+        let mut render = |component: &mut TreeView<String>| -> Vec<String> {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..3)
+                .map(|y| (1..11).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect()
+        };
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    A ▶   "]);
+        // The tree is not changed
+        assert_eq!(component.selected_node().unwrap().value(), "a");
+        component.set_display_map(|x: &String| Cow::Owned(format!("<{x}>")));
+        assert_eq!(render(&mut component), vec!["  </> ▼   ", "    <a> ▶ "]);
+        component.clear_display_map();
+        assert_eq!(render(&mut component), vec!["  / ▼     ", "    a ▶   "]);
+    }
+
+    #[test]
+    fn should_truncate_rows_with_ellipsis() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
         let mut component = TreeView::default()
+            .indent_size(2)
+            .display_map(|x: &String| Cow::Owned(format!("{x} is long")))
+            .ellipsis("…")
+            .with_tree(mock_tree())
+            .initial_node("a");
+        let mut terminal = Terminal::new(TestBackend::new(12, 4)).unwrap();
+        let mut render = |component: &mut TreeView<String>| -> Vec<String> {
+            terminal.draw(|f| component.view(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..3)
+                .map(|y| (1..11).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect()
+        };
+        assert_eq!(render(&mut component), vec!["  / is … ▼", "    a i… ▶"]);
+    }
+
+    #[test]
+    fn should_render_style_per_depth() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .style_per_depth(vec![
+                Style::default().bg(Color::Black),
+                Style::default().bg(Color::DarkGray),
+            ])
             .with_tree(mock_tree())
             .initial_node("aA");
+        let mut terminal = Terminal::new(TestBackend::new(12, 5)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let colors: Vec<Color> = (1..4).map(|y| buffer[(10, y)].bg).collect();
+        assert_eq!(colors, vec![Color::Black, Color::DarkGray, Color::Reset]);
+    }
+
+    #[test]
+    fn should_match_and_highlight_with_matcher() {
+        use tuirealm::ratatui::{backend::TestBackend, Terminal};
+
+        let mut component = TreeView::default()
+            .with_tree(mock_tree())
+            .match_style(Style::default().fg(Color::Yellow))
+            .indent_size(2)
+            .initial_node("bA0");
         assert_eq!(
-            component.perform(Cmd::Custom(TREE_CMD_OPEN)),
-            CmdResult::None
+            component.matching_ids_with(&FuzzyMatcher::new("ba!")),
+            vec![String::from("bA0!")]
         );
-        assert!(component
-            .tree_state()
-            .is_open(component.tree().root().query(&String::from("aA")).unwrap()));
+        assert_eq!(
+            component.matching_ids_with(&SubstringMatcher::new("bA")),
+            vec![
+                String::from("bA"),
+                String::from("bA0"),
+                String::from("bA0!"),
+                String::from("bA1"),
+                String::from("bA2")
+            ]
+        );
+        // The matcher takes precedence over the match query
+        component.attr(
+            Attribute::Custom(TREE_MATCH_QUERY),
+            AttrValue::String(String::from("b")),
+        );
+        component.set_matcher(SubstringMatcher::new("A0"));
+        let mut terminal = Terminal::new(TestBackend::new(16, 5)).unwrap();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        // "bA0" is rendered at column 9 of the last row, within borders
+        assert_eq!(buffer[(9, 3)].symbol(), "b");
+        assert_ne!(buffer[(9, 3)].fg, Color::Yellow);
+        assert_eq!(buffer[(10, 3)].symbol(), "A");
+        assert_eq!(buffer[(10, 3)].fg, Color::Yellow);
+        assert_eq!(buffer[(11, 3)].fg, Color::Yellow);
+        // Back to the match query
+        component.clear_matcher();
+        terminal.draw(|f| component.view(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer[(9, 3)].fg, Color::Yellow);
+        assert_ne!(buffer[(10, 3)].fg, Color::Yellow);
+    }
+
+    #[test]
+    fn should_set_decorations() {
+        let mut component = TreeView::default()
+            .decoration_placeholder(Decoration::default().prefix("…"))
+            .with_tree(mock_tree());
+        assert!(component.decoration("aA").is_none());
+        component.set_decoration("aA", Decoration::default().prefix("📁"));
+        component.set_decoration("bA", Decoration::default().suffix("M"));
+        assert_eq!(component.decoration("aA").unwrap().get_prefix(), Some("📁"));
+        component.clear_decoration("bA");
+        assert!(component.decoration("bA").is_none());
+        // Decorations are pruned on tree changed
+        let mut tree = mock_tree();
+        tree.root_mut().remove_child(&String::from("a"));
+        component.set_tree(tree);
+        assert!(component.decoration("aA").is_none());
     }
 
     #[test]
@@ -0,0 +1,29 @@
+//! # Loader
+//!
+//! This module exposes the loaders of the children of lazy branches
+
+use super::{Node, NodeValue};
+
+/// ## ChildLoader
+///
+/// A child loader provides the children of lazy branches (see `NodeValue::is_branch_hint`) when they're opened
+/// without children (e.g. directories which haven't been scanned yet).
+/// Children can be returned right away, or loaded in background (e.g. by a thread which sends them through a `Port`)
+/// and provided later with `TreeView::set_children`; meanwhile the node is rendered as loading.
+///
+/// Closures taking a node and returning its optional children are loaders too
+pub trait ChildLoader<V: NodeValue> {
+    /// Start loading the children of `node`. Returns them, if they're available right away;
+    /// `None` if they will be provided later with `TreeView::set_children`
+    fn load(&mut self, node: &Node<V>) -> Option<Vec<Node<V>>>;
+}
+
+impl<V, F> ChildLoader<V> for F
+where
+    V: NodeValue,
+    F: FnMut(&Node<V>) -> Option<Vec<Node<V>>>,
+{
+    fn load(&mut self, node: &Node<V>) -> Option<Vec<Node<V>>> {
+        self(node)
+    }
+}
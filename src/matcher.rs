@@ -0,0 +1,277 @@
+//! # Matcher
+//!
+//! This module exposes the matchers used to search nodes and to highlight the matched portion of their labels
+
+use std::ops::Range;
+
+/// ## Matcher
+///
+/// A matcher defines the matching semantics of searches (e.g. substring, case-insensitive, regex or fuzzy).
+/// It is built for a query, and it tells where the query matches a text, so that the matched portion of labels can be highlighted
+pub trait Matcher {
+    /// Get the byte ranges of the matches in `text`, sorted and not overlapping; empty if `text` doesn't match
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>>;
+
+    /// Returns whether `text` matches
+    fn is_match(&self, text: &str) -> bool {
+        !self.match_ranges(text).is_empty()
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for &M {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        (**self).match_ranges(text)
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        (**self).is_match(text)
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Box<M> {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        (**self).match_ranges(text)
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        (**self).is_match(text)
+    }
+}
+
+/// ## SubstringMatcher
+///
+/// Matches the occurrences of the query, case-sensitive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstringMatcher {
+    query: String,
+}
+
+impl SubstringMatcher {
+    /// ### new
+    ///
+    /// Instantiate a new `SubstringMatcher` for `query`. Empty queries match nothing
+    pub fn new<S: AsRef<str>>(query: S) -> Self {
+        Self {
+            query: query.as_ref().to_string(),
+        }
+    }
+}
+
+impl Matcher for SubstringMatcher {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+        text.match_indices(self.query.as_str())
+            .map(|(start, x)| start..start + x.len())
+            .collect()
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        !self.query.is_empty() && text.contains(self.query.as_str())
+    }
+}
+
+/// ## CaseInsensitiveMatcher
+///
+/// Matches the occurrences of the query, ignoring case. This is the matcher used by `TreeView::matching_ids`
+/// and `TREE_MATCH_QUERY`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseInsensitiveMatcher {
+    /// Lowercase query
+    query: String,
+}
+
+impl CaseInsensitiveMatcher {
+    /// ### new
+    ///
+    /// Instantiate a new `CaseInsensitiveMatcher` for `query`. Empty queries match nothing
+    pub fn new<S: AsRef<str>>(query: S) -> Self {
+        Self {
+            query: query.as_ref().to_lowercase(),
+        }
+    }
+}
+
+impl Matcher for CaseInsensitiveMatcher {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        /// ### match_end
+        ///
+        /// Get the end of the match of `query` at the start of `text`, if any
+        fn match_end(text: &str, query: &str) -> Option<usize> {
+            let mut rest = query;
+            for (i, c) in text.char_indices() {
+                for lower in c.to_lowercase() {
+                    rest = rest.strip_prefix(lower)?;
+                }
+                if rest.is_empty() {
+                    return Some(i + c.len_utf8());
+                }
+            }
+            None
+        }
+        let mut ranges = Vec::new();
+        if self.query.is_empty() {
+            return ranges;
+        }
+        let mut last = 0;
+        for (start, _) in text.char_indices() {
+            if start < last {
+                continue;
+            }
+            if let Some(len) = match_end(&text[start..], &self.query) {
+                ranges.push(start..start + len);
+                last = start + len;
+            }
+        }
+        ranges
+    }
+}
+
+/// ## FuzzyMatcher
+///
+/// Matches texts containing all the characters of the query in order, but not necessarily contiguous
+/// (e.g. `tvw` matches `TreeView`), ignoring case. The ranges are the matched characters, the leftmost ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatcher {
+    /// Lowercase query
+    query: String,
+}
+
+impl FuzzyMatcher {
+    /// ### new
+    ///
+    /// Instantiate a new `FuzzyMatcher` for `query`. Empty queries match nothing
+    pub fn new<S: AsRef<str>>(query: S) -> Self {
+        Self {
+            query: query.as_ref().to_lowercase(),
+        }
+    }
+}
+
+impl Matcher for FuzzyMatcher {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut query = self.query.chars().peekable();
+        if query.peek().is_none() {
+            return ranges;
+        }
+        for (i, c) in text.char_indices() {
+            let Some(next) = query.peek().copied() else {
+                break;
+            };
+            // NOTE: characters whose lowercase is longer than one char are compared by their first char
+            if c.to_lowercase().next() == Some(next) {
+                query.next();
+                let end = i + c.len_utf8();
+                match ranges.last_mut() {
+                    Some(last) if last.end == i => last.end = end,
+                    _ => ranges.push(i..end),
+                }
+            }
+        }
+        match query.peek() {
+            None => ranges,
+            Some(_) => Vec::new(),
+        }
+    }
+}
+
+/// ## RegexMatcher
+///
+/// Matches the occurrences of a regular expression. Use the `(?i)` flag for case-insensitive matching.
+/// It is available with the `regex` feature
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct RegexMatcher {
+    regex: regex::Regex,
+}
+
+#[cfg(feature = "regex")]
+impl RegexMatcher {
+    /// ### new
+    ///
+    /// Instantiate a new `RegexMatcher` compiling `pattern`
+    pub fn new<S: AsRef<str>>(pattern: S) -> Result<Self, regex::Error> {
+        regex::Regex::new(pattern.as_ref()).map(Self::from)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<regex::Regex> for RegexMatcher {
+    fn from(regex: regex::Regex) -> Self {
+        Self { regex }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl Matcher for RegexMatcher {
+    fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        // Empty matches can't be highlighted
+        self.regex
+            .find_iter(text)
+            .map(|x| x.range())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.regex.find_iter(text).any(|x| !x.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_match_substrings() {
+        let matcher = SubstringMatcher::new("foo");
+        assert_eq!(matcher.match_ranges("FooBar foo"), vec![7..10]);
+        assert!(matcher.is_match("afoo"));
+        assert!(!matcher.is_match("FOO"));
+        assert!(!SubstringMatcher::new("").is_match("foo"));
+    }
+
+    #[test]
+    fn should_match_ignoring_case() {
+        let matcher = CaseInsensitiveMatcher::new("foo");
+        assert_eq!(matcher.match_ranges("FooBar foo"), vec![0..3, 7..10]);
+        assert_eq!(
+            CaseInsensitiveMatcher::new("aa").match_ranges("aaaa"),
+            vec![0..2, 2..4]
+        );
+        assert_eq!(
+            CaseInsensitiveMatcher::new("àb").match_ranges("ÀBC"),
+            vec![0..3]
+        );
+        assert!(!matcher.is_match("bar"));
+        assert!(!CaseInsensitiveMatcher::new("").is_match("foo"));
+    }
+
+    #[test]
+    fn should_match_fuzzy() {
+        let matcher = FuzzyMatcher::new("tvw");
+        assert_eq!(matcher.match_ranges("TreeView"), vec![0..1, 4..5, 7..8]);
+        assert_eq!(
+            FuzzyMatcher::new("rev").match_ranges("TreeView"),
+            vec![1..3, 4..5]
+        );
+        assert!(!matcher.is_match("Tree"));
+        assert!(!FuzzyMatcher::new("").is_match("Tree"));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn should_match_regex() {
+        let matcher = RegexMatcher::new(r"(?i)b[a-z]\d").unwrap();
+        assert_eq!(matcher.match_ranges("bA0 bb1 b"), vec![0..3, 4..7]);
+        assert!(!matcher.is_match("b00"));
+        // Empty matches are not reported
+        assert!(!RegexMatcher::new("x*").unwrap().is_match("abc"));
+        assert!(RegexMatcher::new("(").is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! # Miller
+//!
+//! This module implements a ranger-style widget, rendering the path of the selected node as columns
+
+use super::{Node, NodeValue, Tree, TreeState};
+
+use tuirealm::ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+/// ## MillerColumnsWidget
+///
+/// tui-rs widget rendering the selected node of a `TreeState` as three columns: the siblings of its parent,
+/// its own siblings and the children of the selected node as preview. Since it is driven by the same `Tree` and
+/// `TreeState` of `TreeWidget`, the two presentations can be switched without losing the selection.
+///
+/// Navigation in columns is provided by the state: `select_prev_sibling` and `select_next_sibling` move in the
+/// current column, `select_parent` moves to the parent column and `select_first_child` to the preview column
+pub struct MillerColumnsWidget<'a, V: NodeValue> {
+    /// Block properties
+    block: Option<Block<'a>>,
+    /// Style for columns
+    style: Style,
+    /// Highlight style
+    highlight_style: Style,
+    /// Relative widths of the parent, current and preview columns
+    ratios: [u32; 3],
+    /// Render arrows with ASCII characters only
+    ascii: bool,
+    /// Tree to render
+    tree: &'a Tree<V>,
+}
+
+impl<'a, V: NodeValue> MillerColumnsWidget<'a, V> {
+    /// ### new
+    ///
+    /// Setup a new `MillerColumnsWidget`
+    pub fn new(tree: &'a Tree<V>) -> Self {
+        Self {
+            block: None,
+            style: Style::default(),
+            highlight_style: Style::default(),
+            ratios: [1, 2, 2],
+            ascii: false,
+            tree,
+        }
+    }
+
+    /// ### block
+    ///
+    /// Set block to render around the columns
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// ### style
+    ///
+    /// Set style for columns
+    pub fn style(mut self, s: Style) -> Self {
+        self.style = s;
+        self
+    }
+
+    /// ### highlight_style
+    ///
+    /// Set style for the selected node and for its parent in the parent column
+    pub fn highlight_style(mut self, s: Style) -> Self {
+        self.highlight_style = s;
+        self
+    }
+
+    /// ### ratios
+    ///
+    /// Set the relative widths of the parent, current and preview columns. Default is `1, 2, 2`
+    pub fn ratios(mut self, parent: u32, current: u32, preview: u32) -> Self {
+        self.ratios = [parent, current, preview];
+        self
+    }
+
+    /// ### ascii
+    ///
+    /// Set whether the arrow of branches is rendered with ASCII characters only (`>`)
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// ### columns
+    ///
+    /// Get the nodes of the parent, current and preview columns, with the highlighted node of each column.
+    /// If no node is selected, root is the current node
+    fn columns<'t>(&self, state: &TreeState) -> [(Vec<&'t Node<V>>, Option<&'t str>); 3]
+    where
+        'a: 't,
+    {
+        let root = self.tree.root();
+        let current = state
+            .selected()
+            .and_then(|x| root.query(&x.to_string()))
+            .unwrap_or(root);
+        let siblings = |node: &'t Node<V>| -> Vec<&'t Node<V>> {
+            match root.parent(node.id()) {
+                Some(parent) => state.visible_children(parent).map(|(x, _)| x).collect(),
+                None => vec![root],
+            }
+        };
+        let parent = match root.parent(current.id()) {
+            Some(parent) => (siblings(parent), Some(parent.id().as_str())),
+            None => (Vec::new(), None),
+        };
+        let preview = match state.is_branch(current) {
+            true => state.visible_children(current).map(|(x, _)| x).collect(),
+            false => Vec::new(),
+        };
+        [
+            parent,
+            (siblings(current), Some(current.id().as_str())),
+            (preview, None),
+        ]
+    }
+
+    /// ### render_column
+    ///
+    /// Render `nodes` in `area`, scrolled so that the `highlighted` node is visible.
+    /// The last column of the area is left blank to separate the column from the next one
+    fn render_column(
+        &self,
+        nodes: &[&Node<V>],
+        highlighted: Option<&str>,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &TreeState,
+    ) {
+        let width = area.width.saturating_sub(1);
+        if width == 0 {
+            return;
+        }
+        let height = area.height as usize;
+        let index = highlighted
+            .and_then(|id| nodes.iter().position(|x| x.id() == id))
+            .unwrap_or(0);
+        let offset = (index + 1).saturating_sub(height);
+        for (row, node) in nodes.iter().skip(offset).take(height).enumerate() {
+            let row_area = Rect::new(area.x, area.y + row as u16, width, 1);
+            let style = match highlighted == Some(node.id().as_str()) {
+                true => self
+                    .highlight_style
+                    .patch(node.value().selected_style().unwrap_or_default()),
+                false => self
+                    .style
+                    .patch(node.value().base_style().unwrap_or_default()),
+            };
+            buf.set_style(row_area, style);
+            // Keep the last two columns for the arrow
+            let branch = state.is_branch(node);
+            let label_width = match branch {
+                true => width.saturating_sub(2),
+                false => width,
+            };
+            let mut x = row_area.x;
+            let right = row_area.x + label_width;
+            if let Some(icon) = node.value().icon() {
+                x = buf
+                    .set_stringn(
+                        x,
+                        row_area.y,
+                        format!("{icon} "),
+                        (right - x) as usize,
+                        style,
+                    )
+                    .0;
+            }
+            for (text, part_style) in node.value().render_parts_iter() {
+                if x >= right {
+                    break;
+                }
+                let part_style = match part_style {
+                    Some(part_style) => style.patch(part_style),
+                    None => style,
+                };
+                x = buf
+                    .set_stringn(x, row_area.y, text, (right - x) as usize, part_style)
+                    .0;
+            }
+            if branch && width >= 2 {
+                let arrow = match self.ascii {
+                    true => ">",
+                    false => "\u{25b6}",
+                };
+                buf.set_string(row_area.right() - 1, row_area.y, arrow, style);
+            }
+        }
+    }
+}
+
+impl<'a, V: NodeValue> Widget for MillerColumnsWidget<'a, V> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut state = TreeState::default();
+        StatefulWidget::render(self, area, buf, &mut state);
+    }
+}
+
+impl<'a, V: NodeValue> StatefulWidget for MillerColumnsWidget<'a, V> {
+    type State = TreeState;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        buf.set_style(area, self.style);
+        let area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let total: u32 = self.ratios.iter().sum::<u32>().max(1);
+        let areas =
+            Layout::horizontal(self.ratios.map(|x| Constraint::Ratio(x, total))).split(area);
+        for ((nodes, highlighted), area) in self.columns(state).iter().zip(areas.iter()) {
+            self.render_column(nodes, *highlighted, *area, buf, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::mock::mock_tree;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_render_miller_columns() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("aA1")).unwrap(),
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 4));
+        StatefulWidget::render(
+            MillerColumnsWidget::new(&tree).ascii(true).ratios(1, 1, 2),
+            buf.area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "aA > aA0            ",
+                "aB > aA1            ",
+                "aC > aA2            ",
+                "                    ",
+            ])
+        );
+        // Root has no parent; its children are previewed
+        state.select(tree.root(), tree.root());
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 4));
+        StatefulWidget::render(
+            MillerColumnsWidget::new(&tree).ratios(1, 1, 2),
+            buf.area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "     /  \u{25b6} a       \u{25b6} ",
+                "          b       \u{25b6} ",
+                "          c       \u{25b6} ",
+                "                    ",
+            ])
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! # Persist
+//!
+//! This module exposes the state of a tree view which can be saved and restored between sessions
+
+use super::tree_state::StateSnapshot;
+
+use std::collections::HashMap;
+
+use tuirealm::{State, StateValue};
+
+/// Key of the id of the selected node in the state map
+const SELECTED_KEY: &str = "selected";
+/// Key of the amount of open nodes in the state map; their ids are at `open.0`, `open.1`, ...
+const OPEN_KEY: &str = "open";
+
+/// ## PersistedState
+///
+/// Open nodes, selected node and bookmarks of a tree view, to save when the application exits and to restore
+/// with `TreeView::restore_persisted_state`. Enable the `serde` feature to serialize it
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PersistedState {
+    /// Ids of the open nodes
+    pub open: Vec<String>,
+    /// Id of the selected node
+    pub selected: Option<String>,
+    /// Ids of the bookmarked nodes
+    pub bookmarks: Vec<String>,
+}
+
+impl PersistedState {
+    /// ### new
+    ///
+    /// Make the persisted state from a state snapshot and the bookmarks
+    pub(crate) fn new(snapshot: StateSnapshot, bookmarks: Vec<String>) -> Self {
+        Self {
+            open: snapshot.open,
+            selected: snapshot.selected,
+            bookmarks,
+        }
+    }
+
+    /// ### into_parts
+    ///
+    /// Split the persisted state into the state snapshot to restore and the bookmarks
+    pub(crate) fn into_parts(self) -> (StateSnapshot, Vec<String>) {
+        (
+            StateSnapshot {
+                open: self.open,
+                selected: self.selected,
+            },
+            self.bookmarks,
+        )
+    }
+
+    /// ### into_state
+    ///
+    /// Get the selected node and the open nodes as the `State::Map` reported when `TREE_STATE_MAP` is set.
+    /// Bookmarks are not reported
+    pub(crate) fn into_state(self) -> State {
+        let mut map = HashMap::with_capacity(self.open.len() + 2);
+        map.insert(
+            SELECTED_KEY.to_string(),
+            self.selected
+                .map(StateValue::String)
+                .unwrap_or(StateValue::None),
+        );
+        map.insert(OPEN_KEY.to_string(), StateValue::Usize(self.open.len()));
+        for (i, id) in self.open.into_iter().enumerate() {
+            map.insert(format!("{OPEN_KEY}.{i}"), StateValue::String(id));
+        }
+        State::Map(map)
+    }
+
+    /// ### from_state
+    ///
+    /// Get the selected node and the open nodes from the `State::Map` reported by the tree view when
+    /// `TREE_STATE_MAP` is set, e.g. to restore them with `TreeView::restore_persisted_state`.
+    /// Returns `None` if `state` is not such a map
+    pub fn from_state(state: State) -> Option<Self> {
+        let State::Map(mut map) = state else {
+            return None;
+        };
+        let selected = match map.remove(SELECTED_KEY)? {
+            StateValue::String(id) => Some(id),
+            StateValue::None => None,
+            _ => return None,
+        };
+        let StateValue::Usize(open) = map.remove(OPEN_KEY)? else {
+            return None;
+        };
+        let open = (0..open)
+            .map(|i| match map.remove(&format!("{OPEN_KEY}.{i}")) {
+                Some(StateValue::String(id)) => Some(id),
+                _ => None,
+            })
+            .collect::<Option<Vec<String>>>()?;
+        Some(Self {
+            open,
+            selected,
+            bookmarks: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,175 @@
+//! # Table
+//!
+//! This module implements the sorting of siblings by the columns of the table mode (see `TREE_COLUMNS`)
+
+use super::{Node, NodeValue};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// ## SortOrder
+///
+/// Order of the siblings sorted by a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    /// ### indicator
+    ///
+    /// Get the indicator rendered after the title of the sorted column
+    pub(crate) fn indicator(&self, ascii: bool) -> &'static str {
+        match (self, ascii) {
+            (Self::Ascending, false) => "\u{25b2}",
+            (Self::Descending, false) => "\u{25bc}",
+            (Self::Ascending, true) => "^",
+            (Self::Descending, true) => "v",
+        }
+    }
+}
+
+/// ## ColumnSort
+///
+/// Column the siblings are sorted by, with the position of each node among its siblings in the tree order,
+/// so that the tree order can be restored when the sort is removed
+#[derive(Debug, Default)]
+pub(crate) struct ColumnSort {
+    /// Sorted column and order
+    sorted: Option<(usize, SortOrder)>,
+    /// Position of each node among its siblings before sorting
+    positions: HashMap<String, usize>,
+}
+
+impl ColumnSort {
+    /// ### sorted
+    ///
+    /// Get the sorted column and its order, if any
+    pub(crate) fn sorted(&self) -> Option<(usize, SortOrder)> {
+        self.sorted
+    }
+
+    /// ### next
+    ///
+    /// Get the sort following the current one when `column` is cycled: ascending, descending, then unsorted.
+    /// Cycling another column starts from ascending
+    pub(crate) fn next(&self, column: usize) -> Option<(usize, SortOrder)> {
+        match self.sorted {
+            Some((sorted, SortOrder::Ascending)) if sorted == column => {
+                Some((column, SortOrder::Descending))
+            }
+            Some((sorted, SortOrder::Descending)) if sorted == column => None,
+            _ => Some((column, SortOrder::Ascending)),
+        }
+    }
+
+    /// ### set
+    ///
+    /// Set the sorted column. If the tree isn't sorted yet, the positions of the nodes are recorded from `root`
+    pub(crate) fn set<V>(&mut self, root: &Node<V>, sorted: Option<(usize, SortOrder)>) {
+        if self.sorted.is_none() {
+            self.positions.clear();
+            self.record(root);
+        }
+        self.sorted = sorted;
+    }
+
+    /// ### tree_changed
+    ///
+    /// Record the tree order of a new tree with root `root` and sort it by the sorted column, if any
+    pub(crate) fn tree_changed<V: NodeValue>(&mut self, root: &mut Node<V>) {
+        if self.sorted.is_some() {
+            self.positions.clear();
+            self.record(root);
+            self.sort(root);
+        }
+    }
+
+    /// ### children_changed
+    ///
+    /// Record the tree order of the new children of `node` and sort them by the sorted column, if any
+    pub(crate) fn children_changed<V: NodeValue>(&mut self, node: &mut Node<V>) {
+        if self.sorted.is_some() {
+            self.record(node);
+            self.sort(node);
+        }
+    }
+
+    /// ### record
+    ///
+    /// Record the position of the children of `node` and of all its descendants
+    fn record<V>(&mut self, node: &Node<V>) {
+        for (position, child) in node.iter().enumerate() {
+            self.positions.insert(child.id().to_string(), position);
+            self.record(child);
+        }
+    }
+
+    /// ### sort
+    ///
+    /// Sort the children of `node` and of all its descendants
+    fn sort<V: NodeValue>(&self, node: &mut Node<V>) {
+        node.sort(|a, b| self.compare(a, b));
+        node.iter_mut().for_each(|x| self.sort(x));
+    }
+
+    /// ### compare
+    ///
+    /// Compare two siblings by the sorted column, or by their position in the tree order if no column is sorted
+    pub(crate) fn compare<V: NodeValue>(&self, a: &Node<V>, b: &Node<V>) -> Ordering {
+        match self.sorted {
+            Some((column, SortOrder::Ascending)) => a.value().cmp_column(b.value(), column),
+            Some((column, SortOrder::Descending)) => b.value().cmp_column(a.value(), column),
+            None => {
+                let position = |x: &Node<V>| self.positions.get(x.id()).copied();
+                // Nodes without position are kept at the end
+                match (position(a), position(b)) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::mock::mock_tree;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_cycle_sort_order() {
+        let tree = mock_tree();
+        let mut sort = ColumnSort::default();
+        assert_eq!(sort.next(1), Some((1, SortOrder::Ascending)));
+        sort.set(tree.root(), sort.next(1));
+        assert_eq!(sort.next(1), Some((1, SortOrder::Descending)));
+        sort.set(tree.root(), sort.next(1));
+        assert_eq!(sort.sorted(), Some((1, SortOrder::Descending)));
+        assert_eq!(sort.next(1), None);
+        // Another column starts from ascending
+        assert_eq!(sort.next(2), Some((2, SortOrder::Ascending)));
+        sort.set(tree.root(), sort.next(1));
+        assert_eq!(sort.sorted(), None);
+    }
+
+    #[test]
+    fn should_compare_by_tree_order_when_unsorted() {
+        let tree = mock_tree();
+        let mut sort = ColumnSort::default();
+        sort.set(tree.root(), Some((0, SortOrder::Descending)));
+        let node = |id: &str| tree.root().query(&String::from(id)).unwrap();
+        assert_eq!(sort.compare(node("a"), node("b")), Ordering::Greater);
+        sort.set(tree.root(), None);
+        assert_eq!(sort.compare(node("a"), node("b")), Ordering::Less);
+        assert_eq!(sort.compare(node("aA1"), node("aA0")), Ordering::Greater);
+        // Unknown nodes go last
+        let orphan = Node::new(String::from("zZ"), String::from("zZ"));
+        assert_eq!(sort.compare(&orphan, node("c")), Ordering::Greater);
+        assert_eq!(sort.compare(node("c"), &orphan), Ordering::Less);
+    }
+}
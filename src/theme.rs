@@ -0,0 +1,44 @@
+//! # Theme
+//!
+//! This module exposes the theme which can be applied at once to the tree view
+
+use tuirealm::props::{Color, Style, TextModifiers};
+
+/// ## TreeTheme
+///
+/// Bundles all the colors and styles used by the `TreeView`, so that they can be applied in one call
+/// (e.g. when the user switches theme at runtime)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeTheme {
+    /// Foreground color
+    pub foreground: Color,
+    /// Background color
+    pub background: Color,
+    /// Color to highlight the selected node; if `None`, the foreground is used
+    pub highlighted_color: Option<Color>,
+    /// Style to use when the component is inactive
+    pub inactive: Style,
+    /// Borders color
+    pub borders: Color,
+    /// Text modifiers
+    pub modifiers: TextModifiers,
+    /// Style patched on the guide lines
+    pub guides: Style,
+    /// Style patched on the icons of nodes
+    pub icons: Style,
+}
+
+impl Default for TreeTheme {
+    fn default() -> Self {
+        Self {
+            foreground: Color::Reset,
+            background: Color::Reset,
+            highlighted_color: None,
+            inactive: Style::default(),
+            borders: Color::Reset,
+            modifiers: TextModifiers::empty(),
+            guides: Style::default(),
+            icons: Style::default(),
+        }
+    }
+}
@@ -2,7 +2,18 @@
 //!
 //! This module implements the tree state.
 
-use super::Node;
+use super::{Node, NodeValue, Tree};
+
+use tuirealm::ratatui::layout::Rect;
+use tuirealm::ratatui::style::Style;
+use tuirealm::ratatui::widgets::ListState;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Render parts of the label of a node
+pub(crate) type LabelParts = Arc<[(String, Option<Style>)]>;
 
 /// ## TreeState
 ///
@@ -13,6 +24,106 @@ pub struct TreeState {
     open: Vec<String>,
     /// Current selected item
     selected: Option<String>,
+    /// Index of the first visible row in the viewport
+    offset: usize,
+    /// Offset the viewport is easing toward, while a smooth scroll is in progress
+    scroll_target: Option<usize>,
+    /// Rows available in the viewport at the last render
+    viewport_height: usize,
+    /// Area where rows were rendered at the last render
+    viewport_area: Rect,
+    /// Columns (x, width) of the arrow of each row rendered at the last render, if it is a branch
+    arrow_columns: Vec<Option<(u16, u16)>>,
+    /// Index of the visible rows; `None` if it must be rebuilt
+    rows: Option<VisibleRows>,
+    /// Branches whose children have been unloaded
+    unloaded: HashSet<String>,
+    /// Maximum amount of children shown for each node; `None` if unlimited
+    max_children: Option<usize>,
+    /// Amount of children shown for the nodes whose limit has been increased
+    children_limits: HashMap<String, usize>,
+    /// Nodes treated as leaves, even if they have children
+    forced_leaves: HashSet<String>,
+    /// Rows added and removed at the last render; `None` if not tracked
+    row_changes: Option<RowChanges>,
+    /// Render parts of the labels rendered so far, by node id; `None` if labels are not cached
+    labels: Option<RefCell<HashMap<String, LabelParts>>>,
+}
+
+/// ## RowChanges
+///
+/// Rows which became visible or hidden at the last render, compared to the previous one
+#[derive(Default, Clone)]
+struct RowChanges {
+    /// Ids of the rows visible at the last render; `None` before the first render
+    previous: Option<Vec<String>>,
+    /// Ids of the rows which became visible
+    added: Vec<String>,
+    /// Ids of the rows which became hidden
+    removed: Vec<String>,
+}
+
+/// ## VisibleRows
+///
+/// Pre-order index of the rows currently visible
+#[derive(Default, Clone)]
+struct VisibleRows {
+    /// Ids of visible nodes, in the order they're rendered
+    ids: Vec<String>,
+    /// Route to each visible node, in the order they're rendered
+    routes: Vec<Vec<usize>>,
+    /// Row index associated to each node id
+    index: HashMap<String, usize>,
+}
+
+impl VisibleRows {
+    /// ### insert_rows
+    ///
+    /// Insert `rows`, built for the descendants of the node at `row`, right after it
+    fn insert_rows(&mut self, row: usize, rows: VisibleRows) {
+        self.ids.splice(row + 1..row + 1, rows.ids);
+        self.routes.splice(row + 1..row + 1, rows.routes);
+        self.reindex_from(row + 1);
+    }
+
+    /// ### remove_descendants
+    ///
+    /// Remove the rows of the descendants of the node at `row`, which follow it
+    fn remove_descendants(&mut self, row: usize) {
+        let route = &self.routes[row];
+        let len = self.routes[row + 1..]
+            .iter()
+            .take_while(|x| x.starts_with(route))
+            .count();
+        self.routes.drain(row + 1..row + 1 + len);
+        for id in self.ids.drain(row + 1..row + 1 + len) {
+            self.index.remove(&id);
+        }
+        self.reindex_from(row + 1);
+    }
+
+    /// ### reindex_from
+    ///
+    /// Update the row index of the rows from `start`, after rows have been inserted or removed before them
+    fn reindex_from(&mut self, start: usize) {
+        for (i, id) in self.ids.iter().enumerate().skip(start) {
+            match self.index.get_mut(id) {
+                Some(row) => *row = i,
+                None => {
+                    self.index.insert(id.clone(), i);
+                }
+            }
+        }
+    }
+}
+
+/// ## StateSnapshot
+///
+/// Open nodes and selected node at a given time, used to undo state changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StateSnapshot {
+    pub(crate) open: Vec<String>,
+    pub(crate) selected: Option<String>,
 }
 
 impl TreeState {
@@ -32,6 +143,31 @@ impl TreeState {
         !self.is_open(node)
     }
 
+    /// ### is_unloaded
+    ///
+    /// Returns whether the children of `node` have been unloaded. An unloaded node is still a branch,
+    /// even if it has no children
+    pub fn is_unloaded<V>(&self, node: &Node<V>) -> bool {
+        self.unloaded.contains(node.id())
+    }
+
+    /// ### is_branch
+    ///
+    /// Returns whether `node` is a branch, and so it can be opened.
+    /// A node is a branch if it has children, if its value hints it to be a branch (see `NodeValue::is_branch_hint`)
+    /// or if its children have been unloaded
+    pub fn is_branch<V: NodeValue>(&self, node: &Node<V>) -> bool {
+        !self.is_forced_leaf(node)
+            && (!node.is_leaf() || node.value().is_branch_hint() || self.is_unloaded(node))
+    }
+
+    /// ### is_forced_leaf
+    ///
+    /// Returns whether `node` is treated as a leaf even if it has children, which are then never shown
+    pub fn is_forced_leaf<V>(&self, node: &Node<V>) -> bool {
+        self.forced_leaves.contains(node.id())
+    }
+
     /// ### selected
     ///
     /// Get current selected item
@@ -49,6 +185,29 @@ impl TreeState {
             .unwrap_or(false)
     }
 
+    /// ### hidden_children
+    ///
+    /// Get the amount of children of `node` which are not shown, because of the limit set with `max_children`.
+    /// If any, the first of them is rendered as a "more" row
+    pub fn hidden_children<V>(&self, node: &Node<V>) -> usize {
+        self.children_limit(node)
+            .map(|limit| node.children().len() - limit)
+            .unwrap_or(0)
+    }
+
+    /// ### is_more_row
+    ///
+    /// Returns whether `node` is rendered as the "more" row of its parent (see `hidden_children`)
+    pub fn is_more_row<V>(&self, root: &Node<V>, node: &Node<V>) -> bool {
+        root.parent(node.id())
+            .and_then(|parent| {
+                let limit = self.children_limit(parent)?;
+                parent.children().get(limit)
+            })
+            .map(|x| x.id() == node.id())
+            .unwrap_or(false)
+    }
+
     /// ### first_sibling
     ///
     /// Get first sibling in children of current selected node's parent
@@ -64,15 +223,89 @@ impl TreeState {
     pub fn last_sibling<'a, V>(&self, tree: &'a Node<V>) -> Option<&'a Node<V>> {
         let selected = self.selected.as_ref()?;
         let parent = tree.parent(selected)?;
-        parent.iter().last()
+        self.visible_children(parent).last().map(|(x, _)| x)
+    }
+
+    /// ### offset
+    ///
+    /// Get the index of the first row displayed in the viewport
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// ### viewport_height
+    ///
+    /// Get the amount of rows which were available in the viewport at the last render
+    pub fn viewport_height(&self) -> usize {
+        self.viewport_height
+    }
+
+    /// ### viewport_area
+    ///
+    /// Get the area where rows have been rendered at the last render
+    pub fn viewport_area(&self) -> Rect {
+        self.viewport_area
+    }
+
+    /// ### row_at
+    ///
+    /// Get the index of the row rendered at the provided screen position at the last render, if any
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.viewport_area;
+        match column >= area.x && column < area.right() && row >= area.y && row < area.bottom() {
+            true => Some(self.offset + (row - area.y) as usize),
+            false => None,
+        }
+    }
+
+    /// ### is_arrow_at
+    ///
+    /// Returns whether the open/closed arrow of a branch has been rendered at the provided screen position
+    /// at the last render
+    pub fn is_arrow_at(&self, column: u16, row: u16) -> bool {
+        let area = self.viewport_area;
+        if row < area.y || row >= area.bottom() {
+            return false;
+        }
+        matches!(
+            self.arrow_columns.get((row - area.y) as usize),
+            Some(Some((x, width))) if column >= *x && column < x + width
+        )
+    }
+
+    /// ### snapshot
+    ///
+    /// Take a snapshot of the open nodes and of the selected node
+    pub(crate) fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            open: self.open.clone(),
+            selected: self.selected.clone(),
+        }
     }
 
     // -- modifiers
 
+    /// ### restore
+    ///
+    /// Restore the open nodes and the selected node from `snapshot`.
+    /// Nodes which don't exist anymore in the tree are ignored; if the selected one is gone, the selection is kept
+    pub(crate) fn restore<V>(&mut self, root: &Node<V>, snapshot: StateSnapshot) {
+        self.invalidate_rows();
+        self.open = snapshot.open;
+        self.open.retain(|x| root.query(x).is_some());
+        if let Some(selected) = snapshot.selected.filter(|x| root.query(x).is_some()) {
+            self.selected = Some(selected);
+        }
+    }
+
     /// ### tree_changed
     ///
     /// The tree has changed, so this method must check whether to keep states or not
     pub fn tree_changed<V>(&mut self, root: &Node<V>, preserve: bool) {
+        self.invalidate_rows();
+        self.invalidate_labels();
+        #[cfg(feature = "debug")]
+        let (prev_selected, prev_open) = (self.selected.clone(), self.open.len());
         if preserve {
             // Check whether selected is still valid; if doesn't exist, use root
             self.selected = self
@@ -81,24 +314,96 @@ impl TreeState {
                 .map(|selected| root.query(&selected).unwrap_or(root).id().to_string());
             // Check whether open nodes still exist
             self.open.retain(|x| root.query(x).is_some());
+            self.children_limits.retain(|x, _| root.query(x).is_some());
         } else {
             // Reset state
             self.open = Vec::new();
+            self.unloaded.clear();
+            self.children_limits.clear();
             self.selected = Some(root.id().to_string());
+            self.offset = 0;
+            self.scroll_target = None;
         }
+        // Unloaded nodes which have got children back are loaded
+        self.unloaded
+            .retain(|x| root.query(x).map(|x| x.is_leaf()).unwrap_or(false));
+        debug_log!(
+            "tree changed (preserve: {}): selected {:?} -> {:?}; open nodes {} -> {}",
+            preserve,
+            prev_selected,
+            self.selected,
+            prev_open,
+            self.open.len()
+        );
+    }
+
+    /// ### subtree_changed
+    ///
+    /// Update the state after the tree has changed, preserving it only inside the subtree with id `subtree`:
+    /// open nodes in the subtree (and its open ancestors) are kept, while all the other nodes are closed.
+    /// The selected node is kept only if it's in the subtree; otherwise root is selected.
+    /// If `subtree` doesn't exist anymore, the state is reset
+    pub fn subtree_changed<V>(&mut self, root: &Node<V>, subtree: &str) {
+        let Some(subtree) = root.query(&subtree.to_string()) else {
+            self.tree_changed(root, false);
+            return;
+        };
+        self.invalidate_rows();
+        self.invalidate_labels();
+        // Keep open nodes which are in the subtree or are ancestors of it
+        self.open.retain(|x| {
+            subtree.query(x).is_some()
+                || root
+                    .query(x)
+                    .map(|x| x.query(subtree.id()).is_some())
+                    .unwrap_or(false)
+        });
+        self.unloaded.retain(|x| subtree.query(x).is_some());
+        self.children_limits
+            .retain(|x, _| subtree.query(x).is_some());
+        if self
+            .selected
+            .as_ref()
+            .and_then(|x| subtree.query(x))
+            .is_none()
+        {
+            self.selected = Some(root.id().to_string());
+            self.offset = 0;
+        }
+        // Unloaded nodes which have got children back are loaded
+        self.unloaded
+            .retain(|x| root.query(x).map(|x| x.is_leaf()).unwrap_or(false));
+        debug_log!(
+            "tree changed (preserve subtree {:?}): selected {:?}; open nodes {}",
+            subtree.id(),
+            self.selected,
+            self.open.len()
+        );
     }
 
     /// ### open
     ///
-    /// Open currently selected `node`. Node can be open only if it is closed and it is NOT a leaf
-    pub fn open<V>(&mut self, root: &Node<V>) {
+    /// Open currently selected `node`. Node can be open only if it is closed and it is a branch (see `is_branch`)
+    pub fn open<V: NodeValue>(&mut self, root: &Node<V>) {
         if let Some(selected) = self.selected.as_ref() {
             if let Some(node) = root.query(selected) {
+                debug_log!("open {:?}", node.id());
                 self.open_node(root, node);
             }
         }
     }
 
+    /// ### open_ids
+    ///
+    /// Open the nodes with the provided ids and all of their ancestors. Ids which don't exist in `root` are ignored
+    pub(crate) fn open_ids<V>(&mut self, root: &Node<V>, ids: &[String]) {
+        for node in ids.iter().filter_map(|x| root.query(x)) {
+            debug_log!("open {:?}", node.id());
+            self.push_open(node);
+            self.open_ancestors(root, node);
+        }
+    }
+
     /// ### close
     ///
     /// Close currently selected `node`.
@@ -107,12 +412,91 @@ impl TreeState {
         if let Some(selected) = self.selected.as_ref() {
             if let Some(node) = root.query(selected) {
                 if self.is_open(node) {
+                    debug_log!("close {:?}", node.id());
                     self.close_node(node);
                 }
             }
         }
     }
 
+    /// ### open_recursive
+    ///
+    /// Open currently selected node and all of its descendants which are branches (see `is_branch`).
+    /// Children of nodes treated as leaves are not opened
+    pub fn open_recursive<V: NodeValue>(&mut self, root: &Node<V>) {
+        /// ### collect_branches
+        ///
+        /// Collect ids of `node` and of its descendants which are closed branches
+        fn collect_branches<V: NodeValue>(
+            state: &TreeState,
+            node: &Node<V>,
+            acc: &mut Vec<String>,
+        ) {
+            if !state.is_branch(node) {
+                return;
+            }
+            if state.is_closed(node) {
+                acc.push(node.id().to_string());
+            }
+            node.iter().for_each(|x| collect_branches(state, x, acc));
+        }
+        let Some(node) = self.selected.as_ref().and_then(|x| root.query(x)) else {
+            return;
+        };
+        debug_log!("open recursive {:?}", node.id());
+        let mut branches = Vec::new();
+        collect_branches(self, node, &mut branches);
+        self.open_ancestors(root, node);
+        if !branches.is_empty() {
+            self.open.extend(branches);
+            self.invalidate_rows();
+        }
+    }
+
+    /// ### close_recursive
+    ///
+    /// Close currently selected node and all of its descendants, even if the node itself is closed
+    pub fn close_recursive<V>(&mut self, root: &Node<V>) {
+        if let Some(node) = self.selected.as_ref().and_then(|x| root.query(x)) {
+            debug_log!("close recursive {:?}", node.id());
+            self.close_node(node);
+        }
+    }
+
+    /// ### close_deeper_than
+    ///
+    /// Close all the open nodes with a depth greater or equal than `depth` (root has depth 0),
+    /// so that no node deeper than `depth` is visible.
+    /// If the selected node gets hidden, the selection is moved to its ancestor at `depth`
+    pub fn close_deeper_than<V>(&mut self, root: &Node<V>, depth: usize) {
+        /// ### collect_shallow_nodes
+        ///
+        /// Collect ids of nodes with depth less than `depth`
+        fn collect_shallow_nodes<V>(node: &Node<V>, depth: usize, acc: &mut Vec<String>) {
+            if depth > 0 {
+                acc.push(node.id().to_string());
+                node.iter()
+                    .for_each(|x| collect_shallow_nodes(x, depth - 1, acc));
+            }
+        }
+        let mut shallow = Vec::new();
+        collect_shallow_nodes(root, depth, &mut shallow);
+        self.open.retain(|x| shallow.contains(x));
+        self.invalidate_rows();
+        // Relocate selection to its surviving ancestor
+        if let Some(route) = self
+            .selected
+            .as_ref()
+            .and_then(|selected| root.route_by_node(selected))
+        {
+            if route.len() > depth {
+                self.selected = root
+                    .node_by_route(&route[..depth])
+                    .map(|x| x.id().to_string());
+            }
+        }
+    }
+
     /// ### move_down
     ///
     /// Move cursor down in current tree from current position. Rewind if required
@@ -121,7 +505,7 @@ impl TreeState {
             // Get current node
             if let Some(node) = root.query(&selected) {
                 // If node is open, then move to its first child
-                if !node.is_leaf() && self.is_open(node) {
+                if !node.is_leaf() && self.is_open(node) && !self.is_more_row(root, node) {
                     // NOTE: unwrap is safe; checked by `is_leaf()`
                     self.selected = Some(node.iter().next().unwrap().id().to_string());
                 } else {
@@ -176,20 +560,665 @@ impl TreeState {
     /// ### select
     ///
     /// Set current selected node.
-    /// When selecting a node, all its ancestors will be opened
-    pub fn select<V>(&mut self, root: &Node<V>, node: &Node<V>) {
+    /// When selecting a node, all its ancestors will be opened.
+    /// Returns whether `node` has been selected; if no node with its id exists under `root`, the state is left unchanged
+    pub fn select<V>(&mut self, root: &Node<V>, node: &Node<V>) -> bool {
+        // Visible nodes are known to be in the tree; otherwise look for it
+        let visible = self
+            .rows
+            .as_ref()
+            .map(|x| x.index.contains_key(node.id()))
+            .unwrap_or(false);
+        if !visible && root.query(node.id()).is_none() {
+            debug_log!("select {:?}: node is not in tree", node.id());
+            return false;
+        }
+        if !visible && self.is_under_forced_leaf(root, node) {
+            debug_log!("select {:?}: node is hidden by a leaf", node.id());
+            return false;
+        }
+        debug_log!("select {:?} -> {:?}", self.selected, node.id());
         self.open_ancestors(root, node);
+        if !visible {
+            self.show_ancestors(root, node);
+        }
         self.selected = Some(node.id().to_string());
+        true
+    }
+
+    /// ### select_parent
+    ///
+    /// Select the parent of the selected node, whatever its position among its siblings.
+    /// Returns whether the selection has changed; root has no parent, so it stays selected
+    pub fn select_parent<V>(&mut self, root: &Node<V>) -> bool {
+        let Some(parent) = self.selected.as_ref().and_then(|x| root.parent(x)) else {
+            return false;
+        };
+        debug_log!("select parent {:?} -> {:?}", self.selected, parent.id());
+        self.selected = Some(parent.id().to_string());
+        true
+    }
+
+    /// ### select_next_sibling
+    ///
+    /// Select the sibling after the selected node, skipping its descendants (e.g. to move in a column of
+    /// `MillerColumnsWidget`). Returns whether the selection has changed
+    pub fn select_next_sibling<V>(&mut self, root: &Node<V>) -> bool {
+        let Some(node) = self.selected.as_ref().and_then(|x| root.query(x)) else {
+            return false;
+        };
+        match self.next_sibling(root, node) {
+            Some(sibling) => self.select(root, sibling),
+            None => false,
+        }
+    }
+
+    /// ### select_prev_sibling
+    ///
+    /// Select the sibling before the selected node, skipping the descendants of the siblings.
+    /// Returns whether the selection has changed
+    pub fn select_prev_sibling<V>(&mut self, root: &Node<V>) -> bool {
+        let Some(node) = self.selected.as_ref().and_then(|x| root.query(x)) else {
+            return false;
+        };
+        match self.previous_sibling(root, node) {
+            Some(sibling) => self.select(root, sibling),
+            None => false,
+        }
+    }
+
+    /// ### select_first_child
+    ///
+    /// Open the selected node and select its first child. Returns whether the selection has changed;
+    /// leaves and branches without children keep the selection
+    pub fn select_first_child<V>(&mut self, root: &Node<V>) -> bool {
+        let Some(child) = self
+            .selected
+            .as_ref()
+            .and_then(|x| root.query(x))
+            .filter(|x| !self.is_forced_leaf(x))
+            .and_then(|x| x.iter().next())
+        else {
+            return false;
+        };
+        self.select(root, child)
+    }
+
+    /// ### clear_selection
+    ///
+    /// Unselect the selected node, if any
+    pub fn clear_selection(&mut self) {
+        debug_log!("clear selection {:?}", self.selected);
+        self.selected = None;
+    }
+
+    /// ### select_visible
+    ///
+    /// Select `node` without opening its ancestors: if `node` is hidden by a closed ancestor, the closest visible
+    /// ancestor is selected instead. Returns whether `node` has been selected
+    pub fn select_visible<V>(&mut self, root: &Node<V>, node: &Node<V>) -> bool {
+        let Some(route) = root.route_by_node(node.id()) else {
+            return false;
+        };
+        let mut current = root;
+        for i in route {
+            if self.is_closed(current) {
+                break;
+            }
+            // NOTE: unwrap is safe; route has been calculated on root
+            current = current.iter().nth(i).unwrap();
+        }
+        debug_log!("select {:?} -> {:?}", self.selected, current.id());
+        self.selected = Some(current.id().to_string());
+        current.id() == node.id()
+    }
+
+    /// ### view_center
+    ///
+    /// Scroll the viewport so that the selected node is in the middle of it
+    pub fn view_center<V>(&mut self, root: &Node<V>) {
+        if let Some(index) = self.selected_index(root) {
+            self.offset = index.saturating_sub(self.viewport_height / 2);
+        }
+    }
+
+    /// ### view_top
+    ///
+    /// Scroll the viewport so that the selected node is the first row of it
+    pub fn view_top<V>(&mut self, root: &Node<V>) {
+        if let Some(index) = self.selected_index(root) {
+            self.offset = index;
+        }
+    }
+
+    /// ### view_bottom
+    ///
+    /// Scroll the viewport so that the selected node is the last row of it
+    pub fn view_bottom<V>(&mut self, root: &Node<V>) {
+        if let Some(index) = self.selected_index(root) {
+            self.offset = (index + 1).saturating_sub(self.viewport_height);
+        }
+    }
+
+    /// ### set_unloaded
+    ///
+    /// Set whether the children of node with provided `id` have been unloaded
+    pub(crate) fn set_unloaded(&mut self, id: &str, unloaded: bool) {
+        match unloaded {
+            true => {
+                self.unloaded.insert(id.to_string());
+            }
+            false => {
+                self.unloaded.remove(id);
+            }
+        }
+    }
+
+    /// ### set_forced_leaf
+    ///
+    /// Set whether the node with `id` is treated as a leaf even if it has children.
+    /// The node gets closed; if the selected node is one of its descendants, the node itself gets selected
+    pub(crate) fn set_forced_leaf<V>(&mut self, root: &Node<V>, id: &str, leaf: bool) {
+        if !leaf {
+            self.forced_leaves.remove(id);
+            return;
+        }
+        self.forced_leaves.insert(id.to_string());
+        if let Some(node) = root.query(&id.to_string()) {
+            self.close_node(node);
+            if self
+                .selected
+                .as_ref()
+                .map(|x| x != node.id() && node.query(x).is_some())
+                .unwrap_or(false)
+            {
+                self.selected = Some(node.id().to_string());
+            }
+        }
+    }
+
+    /// ### set_max_children
+    ///
+    /// Set the maximum amount of children shown for each node; the limit of a node is increased by this amount
+    /// with `show_more_children`
+    pub(crate) fn set_max_children(&mut self, max: Option<usize>) {
+        self.max_children = max;
+        self.children_limits.clear();
+        self.invalidate_rows();
+    }
+
+    /// ### show_more_children
+    ///
+    /// Increase the amount of children shown for `node` by the maximum set with `max_children`
+    pub(crate) fn show_more_children<V>(&mut self, node: &Node<V>) {
+        if let (Some(limit), Some(max)) = (self.children_limit(node), self.max_children) {
+            self.children_limits
+                .insert(node.id().to_string(), limit.saturating_add(max));
+            self.invalidate_rows();
+        }
+    }
+
+    /// ### set_viewport
+    ///
+    /// Set offset and height of the viewport. This is meant to be called by the widget after rendering
+    pub(crate) fn set_viewport(&mut self, offset: usize, height: usize) {
+        self.offset = offset;
+        self.viewport_height = height;
+    }
+
+    /// ### is_scrolling
+    ///
+    /// Returns whether a smooth scroll is in progress, i.e. the viewport hasn't reached its target offset yet
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll_target.is_some()
+    }
+
+    /// ### set_scroll_target
+    ///
+    /// Set the offset the viewport eases toward on each `step_scroll`. This is meant to be called by the widget
+    pub(crate) fn set_scroll_target(&mut self, target: Option<usize>) {
+        self.scroll_target = target.filter(|x| *x != self.offset);
+    }
+
+    /// ### step_scroll
+    ///
+    /// Move the viewport halfway to the scroll target (ease-out), reaching it in a few steps.
+    /// Returns whether the viewport has moved
+    pub(crate) fn step_scroll(&mut self) -> bool {
+        let Some(target) = self.scroll_target else {
+            return false;
+        };
+        let step = target.abs_diff(self.offset).div_ceil(2);
+        self.offset = match target > self.offset {
+            true => self.offset + step,
+            false => self.offset - step,
+        };
+        if self.offset == target {
+            self.scroll_target = None;
+        }
+        true
+    }
+
+    /// ### track_row_changes
+    ///
+    /// Set whether to compare the visible rows at each render with the ones of the previous render, so that
+    /// tick-driven apps can animate the rows which appear and disappear (see `rows_added` and `rows_removed`)
+    pub fn track_row_changes(&mut self, track: bool) {
+        self.row_changes = match track {
+            true => self
+                .row_changes
+                .take()
+                .or_else(|| Some(RowChanges::default())),
+            false => None,
+        };
+    }
+
+    /// ### rows_added
+    ///
+    /// Get the ids of the rows which became visible (i.e. not hidden by a closed ancestor) at the last render,
+    /// in the order they're rendered. Empty if row changes are not tracked and at the first render
+    pub fn rows_added(&self) -> &[String] {
+        self.row_changes
+            .as_ref()
+            .map(|x| x.added.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// ### rows_removed
+    ///
+    /// Get the ids of the rows which became hidden or have been removed from the tree at the last render,
+    /// in the order they were rendered. Empty if row changes are not tracked and at the first render
+    pub fn rows_removed(&self) -> &[String] {
+        self.row_changes
+            .as_ref()
+            .map(|x| x.removed.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// ### set_viewport_area
+    ///
+    /// Set the area where rows have been rendered. This is meant to be called by the widget after rendering
+    pub(crate) fn set_viewport_area(&mut self, area: Rect) {
+        self.viewport_area = area;
+    }
+
+    /// ### set_arrow_columns
+    ///
+    /// Set the columns of the arrows of the rendered rows. This is meant to be called by the widget after rendering
+    pub(crate) fn set_arrow_columns(&mut self, arrows: Vec<Option<(u16, u16)>>) {
+        self.arrow_columns = arrows;
+    }
+
+    /// ### selected_index
+    ///
+    /// Get the index of the selected node in the rows currently visible (not hidden by a closed ancestor).
+    /// This is a O(1) lookup, if the visible rows index is up to date (it is rebuilt at each render)
+    pub fn selected_index<V>(&self, root: &Node<V>) -> Option<usize> {
+        self.row_index(root, self.selected.as_deref()?)
+    }
+
+    /// ### is_on_screen
+    ///
+    /// Returns whether node with `id` is visible (not hidden by a closed ancestor) and in the viewport,
+    /// assuming it's `viewport_height` rows high. If not, the view must be scrolled to reveal it
+    pub fn is_on_screen<V>(&self, root: &Node<V>, id: &str, viewport_height: usize) -> bool {
+        self.row_index(root, id)
+            .map(|row| row >= self.offset && row < self.offset + viewport_height)
+            .unwrap_or(false)
+    }
+
+    /// ### as_list_state
+    ///
+    /// Get the selection and the viewport offset as a ratatui `ListState`, indexed over the visible rows,
+    /// to feed companion widgets which work with indices (e.g. a `Scrollbar` or a custom gutter)
+    pub fn as_list_state<V>(&self, root: &Node<V>) -> ListState {
+        ListState::default()
+            .with_selected(self.selected_index(root))
+            .with_offset(self.offset)
+    }
+
+    /// ### node_at_row
+    ///
+    /// Get the node rendered at the provided row index (0 is the root).
+    /// If the visible rows index is up to date, the node is resolved through its route, otherwise the tree is walked
+    pub fn node_at_row<'a, V>(&self, root: &'a Node<V>, row: usize) -> Option<&'a Node<V>> {
+        /// ### node_at_row_r
+        ///
+        /// Inner recursive call; returns the rows walked or, as error, the node at `row`
+        fn node_at_row_r<'a, V>(
+            state: &TreeState,
+            node: &'a Node<V>,
+            more: bool,
+            row: usize,
+            acc: usize,
+        ) -> Result<usize, &'a Node<V>> {
+            if acc == row {
+                return Err(node);
+            }
+            let mut acc = acc + 1;
+            if state.is_expanded(node, more) {
+                for (child, more) in state.visible_children(node) {
+                    acc = node_at_row_r(state, child, more, row, acc)?;
+                }
+            }
+            Ok(acc)
+        }
+        match self.rows.as_ref() {
+            Some(rows) => rows
+                .routes
+                .get(row)
+                .and_then(|route| root.node_by_route(route)),
+            None => node_at_row_r(self, root, false, row, 0).err(),
+        }
+    }
+
+    /// ### visible_ids
+    ///
+    /// Get the ids of the visible nodes, in the order they're rendered
+    pub fn visible_ids<V>(&self, root: &Node<V>) -> Vec<String> {
+        /// ### visible_ids_r
+        ///
+        /// Push `node` and its visible descendants to `ids`
+        fn visible_ids_r<V>(state: &TreeState, node: &Node<V>, more: bool, ids: &mut Vec<String>) {
+            ids.push(node.id().to_string());
+            if state.is_expanded(node, more) {
+                state
+                    .visible_children(node)
+                    .for_each(|(x, more)| visible_ids_r(state, x, more, ids));
+            }
+        }
+        match self.rows.as_ref() {
+            Some(rows) => rows.ids.clone(),
+            None => {
+                let mut ids = Vec::new();
+                visible_ids_r(self, root, false, &mut ids);
+                ids
+            }
+        }
+    }
+
+    /// ### invalidate_labels
+    ///
+    /// Drop the labels cached by `TreeWidget::cache_labels`.
+    /// This must be called if node values have been changed without calling `tree_changed`
+    pub fn invalidate_labels(&mut self) {
+        if let Some(labels) = self.labels.as_mut() {
+            labels.get_mut().clear();
+        }
+    }
+
+    /// ### enable_label_cache
+    ///
+    /// Cache the render parts of the labels from now on, if they're not cached yet
+    pub(crate) fn enable_label_cache(&mut self) {
+        self.labels.get_or_insert_with(RefCell::default);
+    }
+
+    /// ### label_parts
+    ///
+    /// Get the render parts of the label of `node` from the cache, computing them if they're not cached yet.
+    /// Returns `None` if labels are not cached
+    pub(crate) fn label_parts<V: NodeValue>(&self, node: &Node<V>) -> Option<LabelParts> {
+        let mut labels = self.labels.as_ref()?.borrow_mut();
+        if let Some(parts) = labels.get(node.id()) {
+            return Some(parts.clone());
+        }
+        let parts: LabelParts = node
+            .value()
+            .render_parts_iter()
+            .map(|(text, style)| (text.to_string(), style))
+            .collect();
+        labels.insert(node.id().to_string(), parts.clone());
+        Some(parts)
+    }
+
+    /// ### invalidate_rows
+    ///
+    /// Invalidate the index of the visible rows.
+    /// This must be called if the tree has been changed without calling `tree_changed`
+    pub fn invalidate_rows(&mut self) {
+        self.rows = None;
+    }
+
+    /// ### refresh_rows
+    ///
+    /// Rebuild the index of the visible rows, if it has been invalidated
+    pub(crate) fn refresh_rows<V>(&mut self, root: &Node<V>) {
+        if self.rows.is_none() {
+            let mut rows = VisibleRows::default();
+            self.refresh_rows_r(root, false, &mut Vec::new(), &mut rows);
+            self.rows = Some(rows);
+        }
+    }
+
+    /// ### visible_len
+    ///
+    /// Get the amount of rows currently visible (not hidden by a closed ancestor), e.g. to render a "line N of M"
+    /// indicator along with `selected_index`
+    pub fn visible_len<V>(&self, root: &Node<V>) -> usize {
+        match self.rows.as_ref() {
+            Some(rows) => rows.ids.len(),
+            None => self.rows_r(root),
+        }
+    }
+
+    /// ### diff_rows
+    ///
+    /// Compare the visible rows with the ones of the previous render, if row changes are tracked.
+    /// This is meant to be called by the widget after refreshing rows
+    pub(crate) fn diff_rows(&mut self) {
+        let (Some(changes), Some(rows)) = (self.row_changes.as_mut(), self.rows.as_ref()) else {
+            return;
+        };
+        if changes.previous.as_ref() == Some(&rows.ids) {
+            changes.added.clear();
+            changes.removed.clear();
+            return;
+        }
+        if let Some(previous) = changes.previous.as_ref() {
+            let before: HashSet<&String> = previous.iter().collect();
+            changes.added = rows
+                .ids
+                .iter()
+                .filter(|x| !before.contains(x))
+                .cloned()
+                .collect();
+            changes.removed = previous
+                .iter()
+                .filter(|x| !rows.index.contains_key(x.as_str()))
+                .cloned()
+                .collect();
+        }
+        changes.previous = Some(rows.ids.clone());
+    }
+
+    /// ### max_depth
+    ///
+    /// Get the depth of the deepest row currently visible; 0 for root. Rows must have been refreshed
+    pub(crate) fn max_depth(&self) -> usize {
+        self.rows
+            .as_ref()
+            .and_then(|rows| rows.routes.iter().map(|x| x.len()).max())
+            .unwrap_or(0)
+    }
+
+    /// ### stale_id
+    ///
+    /// Get the first selected or open id which doesn't exist in `root`, with whether it is `"selected"` or `"open"`
+    #[cfg(debug_assertions)]
+    pub(crate) fn stale_id<V>(&self, root: &Node<V>) -> Option<(&str, &'static str)> {
+        /// ### collect_ids_r
+        ///
+        /// Push the ids of `node` and of all its descendants to `ids`
+        fn collect_ids_r<'n, V>(node: &'n Node<V>, ids: &mut HashSet<&'n str>) {
+            ids.insert(node.id().as_str());
+            node.iter().for_each(|x| collect_ids_r(x, ids));
+        }
+        let mut ids = HashSet::new();
+        collect_ids_r(root, &mut ids);
+        self.selected
+            .iter()
+            .map(|x| (x.as_str(), "selected"))
+            .chain(self.open.iter().map(|x| (x.as_str(), "open")))
+            .find(|(id, _)| !ids.contains(id))
     }
 
     // -- private
 
+    /// ### row_index
+    ///
+    /// Get the index of the node with `id` in the rows currently visible
+    fn row_index<V>(&self, root: &Node<V>, id: &str) -> Option<usize> {
+        /// ### row_index_r
+        ///
+        /// Inner recursive call; returns the rows walked or, as error, the index of the node,
+        /// in order to stop the iteration as soon as it is found
+        fn row_index_r<V>(
+            state: &TreeState,
+            node: &Node<V>,
+            more: bool,
+            id: &str,
+            acc: usize,
+        ) -> Result<usize, usize> {
+            if node.id().as_str() == id {
+                return Err(acc);
+            }
+            let mut acc = acc + 1;
+            if state.is_expanded(node, more) {
+                for (child, more) in state.visible_children(node) {
+                    acc = row_index_r(state, child, more, id, acc)?;
+                }
+            }
+            Ok(acc)
+        }
+        match self.rows.as_ref() {
+            Some(rows) => rows.index.get(id).copied(),
+            None => row_index_r(self, root, false, id, 0).err(),
+        }
+    }
+
+    /// ### refresh_rows_r
+    ///
+    /// Push `node`, whose route is `route`, and its visible descendants to `rows`
+    fn refresh_rows_r<V>(
+        &self,
+        node: &Node<V>,
+        more: bool,
+        route: &mut Vec<usize>,
+        rows: &mut VisibleRows,
+    ) {
+        rows.index.insert(node.id().to_string(), rows.ids.len());
+        rows.ids.push(node.id().to_string());
+        rows.routes.push(route.clone());
+        if self.is_expanded(node, more) {
+            self.refresh_children_rows(node, route, rows);
+        }
+    }
+
+    /// ### refresh_children_rows
+    ///
+    /// Push the visible descendants of `node`, whose route is `route`, to `rows`
+    fn refresh_children_rows<V>(
+        &self,
+        node: &Node<V>,
+        route: &mut Vec<usize>,
+        rows: &mut VisibleRows,
+    ) {
+        for (i, (child, more)) in self.visible_children(node).enumerate() {
+            route.push(i);
+            self.refresh_rows_r(child, more, route, rows);
+            route.pop();
+        }
+    }
+
+    /// ### visible_row
+    ///
+    /// Get the row of `node` in the index of the visible rows, if the index is up to date and `node` is visible
+    fn visible_row<V>(&self, node: &Node<V>) -> Option<usize> {
+        self.rows.as_ref()?.index.get(node.id()).copied()
+    }
+
+    /// ### rows_r
+    ///
+    /// Count visible rows in `node` walking the tree
+    fn rows_r<V>(&self, node: &Node<V>) -> usize {
+        match self.is_open(node) {
+            true => {
+                1 + self
+                    .visible_children(node)
+                    .map(|(x, more)| match more {
+                        true => 1,
+                        false => self.rows_r(x),
+                    })
+                    .sum::<usize>()
+            }
+            false => 1,
+        }
+    }
+
+    /// ### children_limit
+    ///
+    /// Get the amount of children shown for `node`, if it has more children than that
+    fn children_limit<V>(&self, node: &Node<V>) -> Option<usize> {
+        let limit = self
+            .children_limits
+            .get(node.id())
+            .copied()
+            .or(self.max_children)?;
+        (node.children().len() > limit).then_some(limit)
+    }
+
+    /// ### visible_children
+    ///
+    /// Iterate over the children of `node` which are shown, with whether the child is rendered as the "more" row
+    pub(crate) fn visible_children<'a, V>(
+        &self,
+        node: &'a Node<V>,
+    ) -> impl Iterator<Item = (&'a Node<V>, bool)> {
+        let limit = self.children_limit(node);
+        node.iter()
+            .take(limit.map(|x| x + 1).unwrap_or(usize::MAX))
+            .enumerate()
+            .map(move |(i, x)| (x, Some(i) == limit))
+    }
+
+    /// ### is_expanded
+    ///
+    /// Returns whether the children of `node` are rendered; the children of a "more" row never are
+    fn is_expanded<V>(&self, node: &Node<V>, more: bool) -> bool {
+        !more && self.is_open(node)
+    }
+
+    /// ### show_ancestors
+    ///
+    /// Increase the children limits of the ancestors of `node`, so that it isn't hidden behind a "more" row
+    fn show_ancestors<V>(&mut self, root: &Node<V>, node: &Node<V>) {
+        let Some(route) = root.route_by_node(node.id()) else {
+            return;
+        };
+        let mut current = root;
+        for i in route {
+            if self.children_limit(current).map(|x| i > x).unwrap_or(false) {
+                self.children_limits.insert(current.id().to_string(), i + 1);
+                self.invalidate_rows();
+            }
+            // NOTE: unwrap is safe; route has been calculated on root
+            current = current.iter().nth(i).unwrap();
+        }
+    }
+
     /// ### close_node
     ///
     /// Close `node`
     fn close_node<V>(&mut self, node: &Node<V>) {
-        // Remove from open nodes
+        // Remove from open nodes; only the rows of its subtree are removed from the visible rows,
+        // while hidden nodes have no rows at all
         self.open.retain(|x| x != node.id());
+        if let Some(row) = self.visible_row(node) {
+            if let Some(rows) = self.rows.as_mut() {
+                rows.remove_descendants(row);
+            }
+        }
         // Close children for node
         self.close_children(node);
     }
@@ -197,15 +1226,40 @@ impl TreeState {
     /// ### open_node
     ///
     /// Open provided node
-    /// Node is opened only if is NOT a leaf and it is closed.
+    /// Node is opened only if is a branch and it is closed.
     /// It will also open all the ancestors for `node`
-    fn open_node<V>(&mut self, root: &Node<V>, node: &Node<V>) {
-        if !node.is_leaf() && self.is_closed(node) {
-            self.open.push(node.id().to_string());
+    fn open_node<V: NodeValue>(&mut self, root: &Node<V>, node: &Node<V>) {
+        // If the node is visible, only the rows of its subtree are built and inserted in the visible rows
+        let row = self
+            .visible_row(node)
+            .filter(|_| self.is_closed(node) && !self.is_more_row(root, node));
+        match (self.is_branch(node), row) {
+            (true, Some(row)) => {
+                self.open.push(node.id().to_string());
+                // NOTE: unwrap is safe; the row has been found in the index
+                let mut route = self.rows.as_ref().unwrap().routes[row].clone();
+                let mut rows = VisibleRows::default();
+                self.refresh_children_rows(node, &mut route, &mut rows);
+                if let Some(visible) = self.rows.as_mut() {
+                    visible.insert_rows(row, rows);
+                }
+            }
+            (true, None) => self.push_open(node),
+            (false, _) => {}
         }
         self.open_ancestors(root, node);
     }
 
+    /// ### push_open
+    ///
+    /// Mark `node` as open, if it is closed
+    fn push_open<V>(&mut self, node: &Node<V>) {
+        if self.is_closed(node) && !self.is_forced_leaf(node) {
+            self.open.push(node.id().to_string());
+            self.invalidate_rows();
+        }
+    }
+
     /// ### close_children
     ///
     /// Close all node children recursively
@@ -213,12 +1267,30 @@ impl TreeState {
         node.iter().for_each(|x| self.close_node(x));
     }
 
+    /// ### is_under_forced_leaf
+    ///
+    /// Returns whether any ancestor of `node` is treated as a leaf
+    fn is_under_forced_leaf<V>(&self, root: &Node<V>, node: &Node<V>) -> bool {
+        if self.forced_leaves.is_empty() {
+            return false;
+        }
+        let mut current = node;
+        while let Some(parent) = root.parent(current.id()) {
+            if self.is_forced_leaf(parent) {
+                return true;
+            }
+            current = parent;
+        }
+        false
+    }
+
     /// ### open_ancestors
     ///
     /// Open all ancestors for `node` in the current `tree`
     fn open_ancestors<V>(&mut self, root: &Node<V>, node: &Node<V>) {
         if let Some(parent) = root.parent(node.id()) {
-            self.open_node(root, parent);
+            self.push_open(parent);
+            self.open_ancestors(root, parent);
         }
     }
 
@@ -247,7 +1319,7 @@ impl TreeState {
     fn next_sibling<'a, V>(&mut self, root: &'a Node<V>, node: &'a Node<V>) -> Option<&'a Node<V>> {
         let parent = root.parent(node.id())?;
         let mut keep_next = false;
-        for child in parent.iter() {
+        for (child, _) in self.visible_children(parent) {
             if keep_next {
                 // Return child
                 return Some(child);
@@ -263,8 +1335,12 @@ impl TreeState {
     /// Get last open heir for node
     fn get_last_open_heir<'a, V>(&self, node: &'a Node<V>) -> &'a Node<V> {
         if self.is_open(node) {
-            // If node is open, get its last child and call this function recursively
-            self.get_last_open_heir(node.iter().last().unwrap())
+            // If node is open, get its last shown child and call this function recursively
+            match self.visible_children(node).last() {
+                Some((child, false)) => self.get_last_open_heir(child),
+                Some((child, true)) => child,
+                None => node,
+            }
         } else {
             // Else return `node`
             node
@@ -276,7 +1352,72 @@ impl TreeState {
     ///
     /// Force open nodes
     pub fn force_open(&mut self, open: &[&str]) {
-        self.open = open.into_iter().map(|x| x.to_string()).collect();
+        self.open = open.iter().map(|x| x.to_string()).collect();
+        self.invalidate_rows();
+    }
+}
+
+/// ## TreeStateBuilder
+///
+/// Builds a `TreeState` with some nodes already open and selected (e.g. in tests, or to restore a saved state)
+#[derive(Debug, Default, Clone)]
+pub struct TreeStateBuilder {
+    open: Vec<String>,
+    selected: Option<String>,
+}
+
+impl TreeStateBuilder {
+    /// ### open_ids
+    ///
+    /// Set the ids of the open nodes
+    pub fn open_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.open = ids.into_iter().map(|x| x.as_ref().to_string()).collect();
+        self
+    }
+
+    /// ### selected
+    ///
+    /// Set the id of the selected node
+    pub fn selected<S: AsRef<str>>(mut self, id: S) -> Self {
+        self.selected = Some(id.as_ref().to_string());
+        self
+    }
+
+    /// ### build
+    ///
+    /// Build the state as it is, without checking the ids.
+    /// Nodes which don't exist are ignored until the state is used with a tree where they exist
+    pub fn build(self) -> TreeState {
+        TreeState {
+            open: self.open,
+            selected: self.selected,
+            ..TreeState::default()
+        }
+    }
+
+    /// ### validate_against
+    ///
+    /// Build the state for `tree`: ids which don't exist in the tree are dropped, the ancestors of the open nodes and
+    /// of the selected node are opened, so that it is visible. If the selected node doesn't exist, root is selected
+    pub fn validate_against<V>(self, tree: &Tree<V>) -> TreeState {
+        let root = tree.root();
+        let mut state = TreeState::default();
+        for node in self.open.iter().filter_map(|x| root.query(x)) {
+            state.push_open(node);
+            state.open_ancestors(root, node);
+        }
+        let selected = self
+            .selected
+            .as_ref()
+            .and_then(|x| root.query(x))
+            .unwrap_or(root);
+        state.open_ancestors(root, selected);
+        state.selected = Some(selected.id().to_string());
+        state
     }
 }
 
@@ -308,6 +1449,266 @@ mod test {
         assert!(state.is_open(tree.root()));
     }
 
+    #[test]
+    fn should_clear_selection() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap());
+        // Open nodes are kept
+        state.clear_selection();
+        assert!(state.selected().is_none());
+        assert_eq!(state.open.len(), 2);
+        // Clearing again is a no-op
+        state.clear_selection();
+        assert!(state.selected().is_none());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn should_find_stale_ids() {
+        let mut state = TreeState::default();
+        let mut tree = mock_tree();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("aA0")).unwrap(),
+        );
+        assert_eq!(state.stale_id(tree.root()), None);
+        tree.root_mut()
+            .query_mut(&String::from("a"))
+            .unwrap()
+            .remove_child(&String::from("aB"));
+        assert_eq!(state.stale_id(tree.root()), None);
+        tree.root_mut().remove_child(&String::from("a"));
+        assert_eq!(state.stale_id(tree.root()), Some(("aA0", "selected")));
+        state.selected = Some(String::from("/"));
+        assert_eq!(state.stale_id(tree.root()), Some(("aA", "open")));
+    }
+
+    #[test]
+    fn should_select_visible_nodes() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let ba0 = tree.root().query(&String::from("bA0")).unwrap();
+        // Root is closed
+        assert!(!state.select_visible(tree.root(), ba0));
+        assert_eq!(state.selected().unwrap(), "/");
+        state.force_open(&["/", "b"]);
+        assert!(!state.select_visible(tree.root(), ba0));
+        assert_eq!(state.selected().unwrap(), "bA");
+        state.force_open(&["/", "b", "bA"]);
+        assert!(state.select_visible(tree.root(), ba0));
+        assert_eq!(state.selected().unwrap(), "bA0");
+        // No node was opened
+        assert_eq!(state.open.len(), 3);
+    }
+
+    #[test]
+    fn should_limit_children() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let a = tree.root().query(&String::from("a")).unwrap();
+        state.set_max_children(Some(1));
+        state.force_open(&["/", "a"]);
+        // 'a' shows 'aA', then 'aB' as "more" row
+        assert_eq!(state.hidden_children(a), 2);
+        assert!(state.is_more_row(tree.root(), tree.root().query(&String::from("aB")).unwrap()));
+        assert_eq!(
+            state.visible_ids(tree.root()),
+            vec!["/", "a", "aA", "aB", "b"]
+        );
+        assert_eq!(state.visible_len(tree.root()), 5);
+        // Move over "more" row
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.move_down(tree.root());
+        assert_eq!(state.selected().unwrap(), "aB");
+        state.move_down(tree.root());
+        assert_eq!(state.selected().unwrap(), "b");
+        state.move_up(tree.root());
+        assert_eq!(state.selected().unwrap(), "aB");
+        // Show more
+        state.show_more_children(a);
+        assert_eq!(state.hidden_children(a), 1);
+        assert_eq!(
+            state.visible_ids(tree.root()),
+            vec!["/", "a", "aA", "aB", "aC", "b"]
+        );
+        // Selecting an hidden node reveals it
+        state.set_max_children(Some(1));
+        state.select(tree.root(), tree.root().query(&String::from("aC")).unwrap());
+        assert_eq!(state.hidden_children(a), 0);
+    }
+
+    #[test]
+    fn should_restore_snapshot() {
+        let mut state = TreeState::default();
+        let mut tree = mock_tree();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        let snapshot = state.snapshot();
+        state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap());
+        state.restore(tree.root(), snapshot.clone());
+        assert_eq!(state.selected().unwrap(), "aA");
+        assert_eq!(state.snapshot(), snapshot);
+        // Removed nodes are ignored
+        tree.root_mut().remove_child(&String::from("a"));
+        state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap());
+        state.restore(tree.root(), snapshot);
+        assert_eq!(state.selected().unwrap(), "bA");
+        assert_eq!(state.open, vec![String::from("/")]);
+    }
+
+    #[test]
+    fn should_treat_forced_leaves_as_leaves() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let a = tree.root().query(&String::from("a")).unwrap();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.set_forced_leaf(tree.root(), "a", true);
+        assert!(state.is_forced_leaf(a));
+        assert!(!state.is_branch(a));
+        assert!(state.is_closed(a));
+        // Selection moved out of the hidden children
+        assert_eq!(state.selected().unwrap(), "a");
+        state.open(tree.root());
+        assert!(state.is_closed(a));
+        state.move_down(tree.root());
+        assert_eq!(state.selected().unwrap(), "b");
+        // Children can't be selected
+        assert!(!state.select(
+            tree.root(),
+            tree.root().query(&String::from("aB0")).unwrap()
+        ));
+        assert_eq!(state.selected().unwrap(), "b");
+        // Unset
+        state.set_forced_leaf(tree.root(), "a", false);
+        assert!(state.is_branch(a));
+        assert!(state.select(
+            tree.root(),
+            tree.root().query(&String::from("aB0")).unwrap()
+        ));
+    }
+
+    #[test]
+    fn should_build_state() {
+        let tree = mock_tree();
+        let state = TreeStateBuilder::default()
+            .open_ids(["b", "zZ"])
+            .selected("aA0")
+            .validate_against(&tree);
+        assert_eq!(state.selected().unwrap(), "aA0");
+        assert_eq!(state.open, vec!["b", "/", "aA", "a"]);
+        // Missing selected node
+        let state = TreeStateBuilder::default()
+            .selected("zZ")
+            .validate_against(&tree);
+        assert_eq!(state.selected().unwrap(), "/");
+        // Unchecked
+        let state = TreeStateBuilder::default()
+            .open_ids(["zZ"])
+            .selected("zZ")
+            .build();
+        assert_eq!(state.selected().unwrap(), "zZ");
+        assert_eq!(state.open, vec!["zZ"]);
+    }
+
+    #[test]
+    fn should_not_select_nodes_not_in_tree() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        assert!(state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap()));
+        let orphan = Node::new(String::from("zZ"), String::from("zZ"));
+        assert!(!state.select(tree.root(), &orphan));
+        // State is unchanged
+        assert_eq!(state.selected().unwrap(), "bA");
+        assert_eq!(state.open.len(), 2);
+    }
+
+    #[test]
+    fn should_track_row_changes() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let render = |state: &mut TreeState| {
+            state.refresh_rows(tree.root());
+            state.diff_rows();
+        };
+        // Not tracked
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        render(&mut state);
+        assert!(state.rows_added().is_empty());
+        state.track_row_changes(true);
+        // Nothing is added at the first render
+        render(&mut state);
+        assert!(state.rows_added().is_empty());
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        state.open(tree.root());
+        render(&mut state);
+        assert_eq!(
+            state.rows_added(),
+            &[String::from("aA"), String::from("aB"), String::from("aC")]
+        );
+        assert!(state.rows_removed().is_empty());
+        // Unchanged rows
+        render(&mut state);
+        assert!(state.rows_added().is_empty());
+        state.select(tree.root(), tree.root().query(&String::from("aB")).unwrap());
+        state.open(tree.root());
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        state.close(tree.root());
+        render(&mut state);
+        // aB was open, but it wasn't rendered
+        assert!(state.rows_added().is_empty());
+        assert_eq!(
+            state.rows_removed(),
+            &[String::from("aA"), String::from("aB"), String::from("aC")]
+        );
+        state.track_row_changes(false);
+        assert!(state.rows_removed().is_empty());
+    }
+
+    #[test]
+    fn should_select_parent() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        assert_eq!(state.select_parent(tree.root()), false);
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bB3")).unwrap(),
+        );
+        assert_eq!(state.select_parent(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "bB");
+        // Parent stays open
+        assert!(state.is_open(tree.root().query(&String::from("bB")).unwrap()));
+        assert_eq!(state.select_parent(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "b");
+        assert_eq!(state.select_parent(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "/");
+        assert_eq!(state.select_parent(tree.root()), false);
+        assert_eq!(state.selected().unwrap(), "/");
+    }
+
+    #[test]
+    fn should_select_siblings_and_first_child() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        assert_eq!(state.select_next_sibling(tree.root()), false);
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.open(tree.root());
+        assert_eq!(state.select_next_sibling(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "aB");
+        assert_eq!(state.select_prev_sibling(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "aA");
+        assert_eq!(state.select_prev_sibling(tree.root()), false);
+        assert_eq!(state.select_first_child(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "aA0");
+        // Leaves have no children
+        assert_eq!(state.select_first_child(tree.root()), false);
+        state.select(tree.root(), tree.root().query(&String::from("aC")).unwrap());
+        assert_eq!(state.select_next_sibling(tree.root()), false);
+        assert_eq!(state.select_first_child(tree.root()), true);
+        assert_eq!(state.selected().unwrap(), "aC0");
+        assert!(state.is_open(tree.root().query(&String::from("aC")).unwrap()));
+    }
+
     #[test]
     fn should_open_and_close_nodes() {
         let mut state = TreeState::default();
@@ -340,6 +1741,115 @@ mod test {
         assert!(state.is_open(tree.root()));
     }
 
+    #[test]
+    fn should_open_and_close_nodes_recursively() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let b = tree.root().query(&String::from("b")).unwrap();
+        state.select(tree.root(), b);
+        state.open_recursive(tree.root());
+        // 'b', 'bA', 'bA0', 'bB' and the root
+        assert_eq!(state.open.len(), 5);
+        assert!(state.is_open(tree.root().query(&String::from("bA0")).unwrap()));
+        // Leaves are not opened
+        assert!(state.is_closed(tree.root().query(&String::from("bA0!")).unwrap()));
+        assert!(state.is_closed(tree.root().query(&String::from("a")).unwrap()));
+        state.close_recursive(tree.root());
+        assert_eq!(state.open.len(), 1);
+        assert!(state.is_open(tree.root()));
+        // Closing a closed node also closes its descendants
+        let ba0 = tree.root().query(&String::from("bA0")).unwrap();
+        state.select(tree.root(), ba0);
+        state.select(tree.root(), b);
+        state.open.retain(|x| x != "b");
+        state.close_recursive(tree.root());
+        assert!(state.is_closed(ba0));
+    }
+
+    #[test]
+    fn should_update_visible_rows_on_open_and_close() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let root = tree.root();
+        state.select(root, root);
+        state.open(root);
+        state.refresh_rows(root);
+        for (id, open) in [
+            ("a", true),
+            ("aB", true),
+            ("b", true),
+            ("bA", true),
+            ("a", false),
+            ("bA", false),
+            ("a", true),
+            ("/", false),
+            ("/", true),
+        ] {
+            state.select(root, root.query(&String::from(id)).unwrap());
+            match open {
+                true => state.open(root),
+                false => state.close(root),
+            }
+            // The rows are updated in place, and match the ones built from scratch
+            assert!(state.rows.is_some());
+            let mut rebuilt = state.clone();
+            rebuilt.invalidate_rows();
+            rebuilt.refresh_rows(root);
+            let (rows, rebuilt) = (state.rows.as_ref().unwrap(), rebuilt.rows.unwrap());
+            assert_eq!(rows.ids, rebuilt.ids);
+            assert_eq!(rows.routes, rebuilt.routes);
+            assert_eq!(rows.index, rebuilt.index);
+        }
+    }
+
+    #[test]
+    fn should_convert_to_list_state() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        assert_eq!(state.as_list_state(tree.root()).selected(), None);
+        state.select(tree.root(), tree.root().query(&String::from("b")).unwrap());
+        state.set_viewport(1, 4);
+        let list_state = state.as_list_state(tree.root());
+        assert_eq!(list_state.selected(), Some(2));
+        assert_eq!(list_state.offset(), 1);
+    }
+
+    #[test]
+    fn should_step_scroll_toward_target() {
+        let mut state = TreeState::default();
+        state.set_viewport(100, 10);
+        assert!(!state.step_scroll());
+        state.set_scroll_target(Some(20));
+        assert!(state.is_scrolling());
+        let mut offsets = Vec::new();
+        while state.step_scroll() {
+            offsets.push(state.offset());
+        }
+        assert_eq!(offsets, vec![60, 40, 30, 25, 22, 21, 20]);
+        assert!(!state.is_scrolling());
+        // Target equal to offset is no scroll
+        state.set_scroll_target(Some(20));
+        assert!(!state.is_scrolling());
+    }
+
+    #[test]
+    fn should_tell_whether_node_is_on_screen() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        let root = tree.root();
+        state.select(root, root.query(&String::from("aB")).unwrap());
+        state.set_viewport(1, 3);
+        // Rows: /, a, aA, aB, aC, b, c
+        assert!(!state.is_on_screen(root, "/", 3));
+        assert!(state.is_on_screen(root, "a", 3));
+        assert!(state.is_on_screen(root, "aB", 3));
+        assert!(!state.is_on_screen(root, "aC", 3));
+        assert!(state.is_on_screen(root, "aC", 4));
+        // Hidden by a closed ancestor
+        assert!(!state.is_on_screen(root, "aB0", 10));
+        assert!(!state.is_on_screen(root, "none", 10));
+    }
+
     #[test]
     fn should_not_open_twice() {
         let mut state = TreeState::default();
@@ -426,6 +1936,30 @@ mod test {
         assert_eq!(state.selected().unwrap(), "bB5");
     }
 
+    #[test]
+    fn should_preserve_subtree_state() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        // Open bA0 and cA branches
+        state.force_open(&["/", "b", "bA", "bA0", "c", "cA"]);
+        let ba0 = tree.root().query(&String::from("bA0")).unwrap();
+        state.select(tree.root(), ba0);
+        // Preserve 'bA'
+        state.subtree_changed(tree.root(), "bA");
+        assert_eq!(state.selected().unwrap(), "bA0");
+        assert!(state.is_open(ba0));
+        assert!(state.is_open(tree.root().query(&String::from("b")).unwrap()));
+        assert!(state.is_closed(tree.root().query(&String::from("c")).unwrap()));
+        assert!(state.is_closed(tree.root().query(&String::from("cA")).unwrap()));
+        // Selected is outside of the subtree
+        state.subtree_changed(tree.root(), "c");
+        assert_eq!(state.selected().unwrap(), "/");
+        assert_eq!(state.open.len(), 1);
+        // Subtree doesn't exist
+        state.subtree_changed(tree.root(), "d");
+        assert!(state.open.is_empty());
+    }
+
     #[test]
     fn should_not_preserve_tree_state() {
         let mut state = TreeState::default();
@@ -469,6 +2003,124 @@ mod test {
         assert_eq!(state.selected().unwrap(), "/");
     }
 
+    #[test]
+    fn should_close_nodes_deeper_than_depth() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        state.force_open(&["/", "a", "aA", "b", "bA", "bA0"]);
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bA0!")).unwrap(),
+        );
+        state.close_deeper_than(tree.root(), 2);
+        assert_eq!(
+            state.open,
+            vec![String::from("/"), String::from("a"), String::from("b")]
+        );
+        // Selection should be moved to the ancestor at depth 2
+        assert_eq!(state.selected().unwrap(), "bA");
+        // Collapse everything
+        state.close_deeper_than(tree.root(), 0);
+        assert!(state.open.is_empty());
+        assert_eq!(state.selected().unwrap(), "/");
+    }
+
+    #[test]
+    fn should_keep_selection_when_closing_deeper_nodes() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.close_deeper_than(tree.root(), 3);
+        assert_eq!(state.selected().unwrap(), "aA");
+        assert_eq!(state.open.len(), 2);
+    }
+
+    #[test]
+    fn should_get_selected_index() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        assert_eq!(state.selected_index(tree.root()), None);
+        assert_eq!(state.visible_len(tree.root()), 1);
+        state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap());
+        // '/', 'a', 'b', 'bA'
+        assert_eq!(state.selected_index(tree.root()), Some(3));
+        assert_eq!(state.visible_len(tree.root()), 6);
+        state.force_open(&["/", "a", "aA", "b"]);
+        assert_eq!(state.selected_index(tree.root()), Some(9));
+        assert_eq!(state.visible_len(tree.root()), 12);
+    }
+
+    #[test]
+    fn should_index_visible_rows() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        state.select(tree.root(), tree.root().query(&String::from("bA")).unwrap());
+        state.refresh_rows(tree.root());
+        assert_eq!(
+            state.rows.as_ref().unwrap().ids,
+            vec![
+                String::from("/"),
+                String::from("a"),
+                String::from("b"),
+                String::from("bA"),
+                String::from("bB"),
+                String::from("c")
+            ]
+        );
+        assert_eq!(state.selected_index(tree.root()), Some(3));
+        assert_eq!(state.visible_len(tree.root()), 6);
+        assert_eq!(
+            state.node_at_row(tree.root(), 3).unwrap().id().as_str(),
+            "bA"
+        );
+        assert!(state.node_at_row(tree.root(), 6).is_none());
+        // Moving doesn't invalidate rows
+        state.move_down(tree.root());
+        assert!(state.rows.is_some());
+        assert_eq!(state.selected_index(tree.root()), Some(4));
+        // Open updates rows in place
+        state.open(tree.root());
+        assert!(state.rows.is_some());
+        assert_eq!(
+            state.node_at_row(tree.root(), 5).unwrap().id().as_str(),
+            "bB0"
+        );
+        state.refresh_rows(tree.root());
+        assert_eq!(state.visible_len(tree.root()), 12);
+        assert_eq!(
+            state.node_at_row(tree.root(), 5).unwrap().id().as_str(),
+            "bB0"
+        );
+        // Close updates rows in place
+        state.close(tree.root());
+        assert!(state.rows.is_some());
+        state.refresh_rows(tree.root());
+        // Tree changed invalidates rows
+        state.tree_changed(tree.root(), true);
+        assert!(state.rows.is_none());
+    }
+
+    #[test]
+    fn should_reposition_viewport() {
+        let mut state = TreeState::default();
+        let tree = mock_tree();
+        state.force_open(&["/", "a", "aA", "aB", "aC", "b", "bA", "bB"]);
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bB2")).unwrap(),
+        );
+        state.set_viewport(0, 8);
+        state.view_top(tree.root());
+        assert_eq!(state.offset(), 20);
+        state.view_bottom(tree.root());
+        assert_eq!(state.offset(), 13);
+        state.view_center(tree.root());
+        assert_eq!(state.offset(), 16);
+        // Reset on tree changed
+        state.tree_changed(tree.root(), false);
+        assert_eq!(state.offset(), 0);
+    }
+
     #[test]
     fn should_move_cursor_down_on_sibling() {
         let mut state = TreeState::default();
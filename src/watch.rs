@@ -0,0 +1,98 @@
+//! # Watch
+//!
+//! This module provides a channel which publishes the selected node of a tree view, so that background tasks
+//! (e.g. metadata prefetchers or preview generators) can react to selection changes without routing them
+//! through the application messages. It is available with the `watch` feature.
+//!
+//! The channel is a `tokio::sync::watch` channel: receivers only see the latest selection, and intermediate
+//! values are skipped if the selection changes faster than they're read.
+
+use tokio::sync::watch;
+
+/// ## SelectionReceiver
+///
+/// Receives the selection changes of a tree view (see `TreeView::watch_selection`).
+/// Receivers can be cloned and sent to other threads or tasks
+pub type SelectionReceiver = watch::Receiver<Option<String>>;
+
+/// ## SelectionSender
+///
+/// Publishes the selection to the receivers; owned by the tree view
+pub(crate) struct SelectionSender {
+    sender: watch::Sender<Option<String>>,
+}
+
+impl SelectionSender {
+    /// ### new
+    ///
+    /// Instantiate a new sender publishing `selected`
+    pub(crate) fn new(selected: Option<String>) -> Self {
+        Self {
+            sender: watch::Sender::new(selected),
+        }
+    }
+
+    /// ### subscribe
+    ///
+    /// Get a new receiver, which has already seen the current selection
+    pub(crate) fn subscribe(&self) -> SelectionReceiver {
+        self.sender.subscribe()
+    }
+
+    /// ### send
+    ///
+    /// Publish `selected`, if it's different from the current value
+    pub(crate) fn send(&self, selected: Option<&str>) {
+        self.sender.send_if_modified(|value| {
+            if value.as_deref() == selected {
+                return false;
+            }
+            *value = selected.map(|x| x.to_string());
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use std::thread;
+
+    #[test]
+    fn should_publish_latest_selection() {
+        let sender = SelectionSender::new(Some(String::from("/")));
+        let mut receiver = sender.subscribe();
+        assert!(!receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow().as_deref(), Some("/"));
+        // Same value is not published
+        sender.send(Some("/"));
+        assert!(!receiver.has_changed().unwrap());
+        sender.send(Some("a"));
+        sender.send(Some("b"));
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().as_deref(), Some("b"));
+        assert!(!receiver.has_changed().unwrap());
+    }
+
+    #[test]
+    fn should_wake_receivers() {
+        let sender = SelectionSender::new(None);
+        let mut receiver = sender.subscribe();
+        let handle = thread::spawn(move || {
+            sender.send(Some("a"));
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            receiver.changed().await.unwrap();
+            assert_eq!(receiver.borrow_and_update().as_deref(), Some("a"));
+            // Sender is dropped with the thread
+            handle.join().unwrap();
+            assert!(receiver.changed().await.is_err());
+        });
+    }
+}
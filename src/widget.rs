@@ -2,16 +2,65 @@
 //!
 //! This module implements the tui widget for rendering a treeview
 
-use super::{Node, NodeValue, Tree, TreeState};
+use super::tree_state::LabelParts;
+use super::{
+    CaseInsensitiveMatcher, Decoration, Matcher, Node, NodeDecorator, NodeValue, SortOrder, Tree,
+    TreeState,
+};
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 
+#[cfg(feature = "unstable-widget-ref")]
+use tuirealm::ratatui::widgets::{StatefulWidgetRef, WidgetRef};
 use tuirealm::ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Style,
-    widgets::{Block, StatefulWidget, Widget},
+    style::{Modifier, Style},
+    widgets::{Block, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget, Widget},
 };
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+/// ## RowCtx
+///
+/// Context of a row passed to the row renderer (see `TreeWidget::row_renderer`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowCtx {
+    /// Area of the row
+    pub area: Rect,
+    /// Depth of the node; 0 for root
+    pub depth: usize,
+    /// Column where the label would start, relative to the row (i.e. the indentation of the node)
+    pub indent: usize,
+    /// Whether the node is selected
+    pub selected: bool,
+    /// Whether the node is open
+    pub open: bool,
+    /// Style of the row, which has already been applied to its area
+    pub style: Style,
+}
+
+/// ## BackgroundFill
+///
+/// Describes where the widget style is painted as background
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundFill {
+    /// Paint the whole area, before rendering the block: block styles are patched over the widget style
+    #[default]
+    Area,
+    /// Paint only the area inside the block, so that borders and title keep the style of the block
+    Inner,
+    /// Don't paint the background; only rows are styled
+    None,
+}
+
+/// Callback which draws a row on its own; returns whether the row has been drawn
+type RowRendererFn<'a, V> = dyn Fn(&Node<V>, RowCtx, &mut Buffer) -> bool + 'a;
+/// Callback which maps a node value to the label to render instead of its render parts
+pub(crate) type DisplayMapFn<'a, V> = dyn Fn(&V) -> Cow<'_, str> + 'a;
+
 /// ## TreeWidget
 ///
 /// tui-rs widget implementation of a tree view
@@ -20,12 +69,76 @@ pub struct TreeWidget<'a, V: NodeValue> {
     block: Option<Block<'a>>,
     /// Style for tree
     style: Style,
+    /// Styles patched on the rows, cycled by depth
+    style_per_depth: Vec<Style>,
+    /// Where the style for tree is painted as background
+    background_fill: BackgroundFill,
     /// Highlight style
     highlight_style: Style,
     /// Symbol to display on the side of the current highlighted
     highlight_symbol: Option<String>,
+    /// Style for the highlight symbol; if `None`, the highlight style is used
+    highlight_symbol_style: Option<Style>,
     /// Spaces to use for indentation
     indent_size: usize,
+    /// Bounds of the indent size computed on each render, if it is computed automatically
+    auto_indent: Option<(usize, usize)>,
+    /// Footer line to render at the bottom of the tree area
+    footer: Option<String>,
+    /// Titles and widths of the columns of the table mode; the first one is the tree column
+    columns: Vec<(String, u16)>,
+    /// Column the siblings are sorted by, with the order to indicate next to its title
+    sorted_column: Option<(usize, SortOrder)>,
+    /// Decorations associated to nodes
+    decorations: Option<&'a HashMap<String, Decoration>>,
+    /// Decoration to render for nodes which have no decoration yet
+    decoration_placeholder: Option<Decoration>,
+    /// Recently submitted nodes, with the decoration to render for them
+    recent: Option<(&'a [String], &'a Decoration)>,
+    /// Bookmarked nodes, with the decoration merged on top of their own one
+    bookmarks: Option<(&'a [String], &'a Decoration)>,
+    /// Decorators merged in order on top of the node decoration
+    decorators: &'a [Box<dyn NodeDecorator<V>>],
+    /// Nodes whose children are being loaded, with the placeholder rendered for them
+    loading: Option<(&'a HashSet<String>, &'a str)>,
+    /// Closed nodes containing dirty descendants, with the badge rendered for them
+    dirty: Option<(&'a HashSet<String>, &'a str, Style)>,
+    /// Marked nodes, with the symbol rendered before their label and the style patched on it
+    marks: Option<(&'a HashSet<String>, &'a str, Style)>,
+    /// Row above the landing position of a node being moved, with the style patched on it
+    drop_indicator: Option<(&'a str, Style)>,
+    /// Matcher whose matches are highlighted in labels, with the style to apply
+    matches: Option<(Box<dyn Matcher + 'a>, Style)>,
+    /// Render the open/closed arrow before the label
+    arrow_first: bool,
+    /// Render rows from right to left
+    rtl: bool,
+    /// Rows to scroll at once when the selected node leaves the viewport
+    scroll_jump: usize,
+    /// Columns between two tab stops in labels
+    tab_width: usize,
+    /// Animate jumps larger than the viewport
+    smooth_scroll: bool,
+    /// Render arrows and symbols with ASCII characters only
+    ascii: bool,
+    /// Render a vertical scrollbar when the rows don't fit the area
+    scrollbar: bool,
+    /// Render tree guide lines instead of plain indentation
+    guides: bool,
+    /// Style patched on the guide lines
+    guides_style: Option<Style>,
+    /// Style patched on the icons of nodes
+    icon_style: Option<Style>,
+    /// String ending the rows which are truncated, if they're not just cut at the end of the area
+    ellipsis: Option<String>,
+    /// Committed node and style for the cursor, when the cursor is just a preview of the selection
+    soft_selection: Option<(Option<&'a str>, Style)>,
+    /// Custom renderer for rows
+    row_renderer: Option<Box<RowRendererFn<'a, V>>>,
+    /// Label to render for values, instead of their render parts
+    display_map: Option<Box<DisplayMapFn<'a, V>>>,
+    /// Cache the render parts of labels in the state
+    cache_labels: bool,
     /// Tree to render
     tree: &'a Tree<V>,
 }
@@ -38,9 +151,41 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         Self {
             block: None,
             style: Style::default(),
+            style_per_depth: Vec::new(),
+            background_fill: BackgroundFill::default(),
             highlight_style: Style::default(),
             highlight_symbol: None,
+            highlight_symbol_style: None,
             indent_size: 4,
+            auto_indent: None,
+            footer: None,
+            columns: Vec::new(),
+            sorted_column: None,
+            decorations: None,
+            decoration_placeholder: None,
+            recent: None,
+            bookmarks: None,
+            decorators: &[],
+            loading: None,
+            dirty: None,
+            marks: None,
+            drop_indicator: None,
+            matches: None,
+            arrow_first: false,
+            rtl: false,
+            scroll_jump: 1,
+            tab_width: 4,
+            smooth_scroll: false,
+            ascii: false,
+            scrollbar: false,
+            soft_selection: None,
+            row_renderer: None,
+            display_map: None,
+            cache_labels: false,
+            guides: false,
+            guides_style: None,
+            icon_style: None,
+            ellipsis: None,
             tree,
         }
     }
@@ -61,6 +206,23 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         self
     }
 
+    /// ### style_per_depth
+    ///
+    /// Set styles to patch on the rows which aren't highlighted, cycled by depth (e.g. a slightly different background
+    /// for each nesting level), to make deep hierarchies readable without guide lines. Root has depth 0
+    pub fn style_per_depth(mut self, styles: Vec<Style>) -> Self {
+        self.style_per_depth = styles;
+        self
+    }
+
+    /// ### background_fill
+    ///
+    /// Set where the style for tree is painted as background. Default is the whole area
+    pub fn background_fill(mut self, fill: BackgroundFill) -> Self {
+        self.background_fill = fill;
+        self
+    }
+
     /// ### highlight_style
     ///
     /// Set highlighted entry style
@@ -77,6 +239,103 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         self
     }
 
+    /// ### highlight_symbol_style
+    ///
+    /// Set style for the highlight symbol, independently from the style of the highlighted label
+    pub fn highlight_symbol_style(mut self, s: Style) -> Self {
+        self.highlight_symbol_style = Some(s);
+        self
+    }
+
+    /// ### scroll_jump
+    ///
+    /// Set the amount of rows to scroll at once when the selected node leaves the viewport. Default is 1.
+    /// Since all the rows change when scrolling, jumping by many rows reduces the amount of cells to redraw
+    /// while moving through a large tree (e.g. on slow remote terminals)
+    pub fn scroll_jump(mut self, rows: usize) -> Self {
+        self.scroll_jump = rows.max(1);
+        self
+    }
+
+    /// ### smooth_scroll
+    ///
+    /// Set whether jumps larger than the viewport (e.g. `GoTo` end) are animated: the target offset is stored
+    /// in the state, which eases toward it on each `TreeState::step_scroll` (i.e. on each tick)
+    pub fn smooth_scroll(mut self, smooth: bool) -> Self {
+        self.smooth_scroll = smooth;
+        self
+    }
+
+    /// ### ascii
+    ///
+    /// Set whether arrows and symbols are rendered with ASCII characters only (`v`, `>`, `<`, `...`),
+    /// for terminals which can't render unicode (e.g. serial consoles or CI logs)
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// ### scrollbar
+    ///
+    /// Set whether to render a vertical scrollbar on the last column, reflecting the window of visible rows over the
+    /// whole tree, when the rows don't fit the area. In RTL mode it is rendered on the first column
+    pub fn scrollbar(mut self, scrollbar: bool) -> Self {
+        self.scrollbar = scrollbar;
+        self
+    }
+
+    /// ### guides
+    ///
+    /// Set whether to render tree guide lines (`│`, `├─`, `└─`) instead of plain indentation, connecting each node to
+    /// its parent and to its next siblings
+    pub fn guides(mut self, guides: bool) -> Self {
+        self.guides = guides;
+        self
+    }
+
+    /// ### ellipsis
+    ///
+    /// Set the string (e.g. `…`) ending the rows which don't fit the area, instead of cutting them at the edge.
+    /// The arrow rendered after the label is kept visible at the end of the truncated row
+    pub fn ellipsis(mut self, ellipsis: &str) -> Self {
+        self.ellipsis = Some(ellipsis.to_string());
+        self
+    }
+
+    /// ### guides_style
+    ///
+    /// Set style patched on the guide lines, if they're rendered (see `guides`)
+    pub fn guides_style(mut self, s: Style) -> Self {
+        self.guides_style = Some(s);
+        self
+    }
+
+    /// ### icon_style
+    ///
+    /// Set style patched on the icons of nodes (see `NodeValue::icon`)
+    pub fn icon_style(mut self, s: Style) -> Self {
+        self.icon_style = Some(s);
+        self
+    }
+
+    /// ### tab_width
+    ///
+    /// Set the amount of columns between two tab stops, used to expand tabs in labels. Default is 4.
+    /// If 0, tabs are removed
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// ### soft_selection
+    ///
+    /// Render the selected node of the state as a preview with `preview_style`,
+    /// while the `committed` node is rendered with the highlight style
+    pub fn soft_selection(mut self, committed: Option<&'a str>, preview_style: Style) -> Self {
+        self.soft_selection = Some((committed, preview_style));
+        self
+    }
+
     /// ### indent_size
     ///
     /// Size for indentation
@@ -84,13 +343,235 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         self.indent_size = sz;
         self
     }
+
+    /// ### auto_indent
+    ///
+    /// Compute the indent size on each render from the deepest visible row and the available width, so that deep trees
+    /// stay readable on narrow areas: the indentation of the deepest rows takes at most half of the width.
+    /// The indent size is kept between `min` and `max`; `indent_size` is ignored
+    pub fn auto_indent(mut self, min: usize, max: usize) -> Self {
+        self.auto_indent = Some((min, max.max(min)));
+        self
+    }
+
+    /// ### footer
+    ///
+    /// Set a line to render at the bottom of the tree area (e.g. key hints)
+    pub fn footer(mut self, footer: String) -> Self {
+        self.footer = Some(footer);
+        self
+    }
+
+    /// ### columns
+    ///
+    /// Set the titles and the widths of the columns of the table mode. The titles are rendered on the first row and
+    /// the cells of each node (see `NodeValue::cell`) in the columns after the first one, which is the tree column and
+    /// takes the width left by the others
+    pub fn columns(mut self, columns: Vec<(String, u16)>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// ### sorted_column
+    ///
+    /// Set the column the siblings are sorted by, whose title is rendered with the indicator of `order`
+    pub fn sorted_column(mut self, column: usize, order: SortOrder) -> Self {
+        self.sorted_column = Some((column, order));
+        self
+    }
+
+    /// ### decorations
+    ///
+    /// Set decorations to render for nodes, by node id
+    pub fn decorations(mut self, decorations: &'a HashMap<String, Decoration>) -> Self {
+        self.decorations = Some(decorations);
+        self
+    }
+
+    /// ### decoration_placeholder
+    ///
+    /// Set decoration to render for nodes which have no decoration yet
+    pub fn decoration_placeholder(mut self, decoration: Decoration) -> Self {
+        self.decoration_placeholder = Some(decoration);
+        self
+    }
+
+    /// ### recent
+    ///
+    /// Render `decoration` for the nodes with the provided ids (e.g. the recently submitted ones),
+    /// unless they have their own decoration
+    pub fn recent(mut self, ids: &'a [String], decoration: &'a Decoration) -> Self {
+        self.recent = Some((ids, decoration));
+        self
+    }
+
+    /// ### bookmarks
+    ///
+    /// Merge `decoration` on top of the decoration of the bookmarked nodes with the provided ids
+    pub fn bookmarks(mut self, ids: &'a [String], decoration: &'a Decoration) -> Self {
+        self.bookmarks = Some((ids, decoration));
+        self
+    }
+
+    /// ### loading
+    ///
+    /// Render `placeholder` after the label of the nodes with the provided ids, whose children are being loaded
+    pub fn loading(mut self, ids: &'a HashSet<String>, placeholder: &'a str) -> Self {
+        self.loading = Some((ids, placeholder));
+        self
+    }
+
+    /// ### dirty_badge
+    ///
+    /// Render `badge` with `style` after the label of the closed nodes with the provided ids
+    /// (e.g. the collapsed ancestors of the nodes marked as dirty)
+    pub fn dirty_badge(mut self, ids: &'a HashSet<String>, badge: &'a str, style: Style) -> Self {
+        self.dirty = Some((ids, badge, style));
+        self
+    }
+
+    /// ### marks
+    ///
+    /// Render the nodes with the provided ids as marked: `symbol` is rendered before their label, and `style` is patched
+    /// on both. Unmarked nodes get a blank of the same width, so labels stay aligned
+    pub fn marks(mut self, ids: &'a HashSet<String>, symbol: &'a str, style: Style) -> Self {
+        self.marks = Some((ids, symbol, style));
+        self
+    }
+
+    /// ### drop_indicator
+    ///
+    /// Patch `style` on the row of the node with `id`, to show that a node being moved will land right below it
+    pub fn drop_indicator(mut self, id: &'a str, style: Style) -> Self {
+        self.drop_indicator = Some((id, style));
+        self
+    }
+
+    /// ### decorators
+    ///
+    /// Set decorators to run for each node. Their decorations are merged in order on top of the node decoration
+    pub fn decorators(mut self, decorators: &'a [Box<dyn NodeDecorator<V>>]) -> Self {
+        self.decorators = decorators;
+        self
+    }
+
+    /// ### highlight_matches
+    ///
+    /// Highlight the occurrences of `query` (case-insensitive) in labels, patching their style with `style`.
+    /// Each render part is matched separately. Empty queries are ignored
+    pub fn highlight_matches(mut self, query: &str, style: Style) -> Self {
+        self.matches = match query.is_empty() {
+            true => None,
+            false => Some((Box::new(CaseInsensitiveMatcher::new(query)), style)),
+        };
+        self
+    }
+
+    /// ### highlight_matcher
+    ///
+    /// Highlight the matches of `matcher` in labels (e.g. regex or fuzzy matches), patching their style with `style`.
+    /// Each render part is matched separately
+    pub fn highlight_matcher<M: Matcher + 'a>(mut self, matcher: M, style: Style) -> Self {
+        self.matches = Some((Box::new(matcher), style));
+        self
+    }
+
+    /// ### arrow_first
+    ///
+    /// Set whether to render the open/closed arrow before the label, instead of after it.
+    /// Leaves get a blank column of the same width, so labels at the same depth stay aligned
+    pub fn arrow_first(mut self, arrow_first: bool) -> Self {
+        self.arrow_first = arrow_first;
+        self
+    }
+
+    /// ### row_renderer
+    ///
+    /// Set a callback to draw rows on its own (e.g. progress bars or sparklines inside the tree).
+    /// The callback is called for each visible row, after the row style has been applied; if it returns `true`
+    /// the row is considered drawn, otherwise the row is rendered as usual.
+    /// Layout, scrolling and selection are still handled by the widget
+    pub fn row_renderer<F>(mut self, renderer: F) -> Self
+    where
+        F: Fn(&Node<V>, RowCtx, &mut Buffer) -> bool + 'a,
+    {
+        self.row_renderer = Some(Box::new(renderer));
+        self
+    }
+
+    /// ### display_map
+    ///
+    /// Set a callback which maps node values to the label to render instead of their render parts
+    /// (e.g. to show file names instead of full paths, or to localize labels), without changing the tree.
+    /// Mapped labels have the row style and no key
+    pub fn display_map<F>(mut self, map: F) -> Self
+    where
+        F: Fn(&V) -> Cow<'_, str> + 'a,
+    {
+        self.display_map = Some(Box::new(map));
+        self
+    }
+
+    /// ### cache_labels
+    ///
+    /// Set whether to cache the render parts of labels in the `TreeState`, so that values whose formatting is costly
+    /// (e.g. dates or humanized sizes) are formatted once instead of on each render. The cache is dropped when
+    /// `TreeState::tree_changed` or `TreeState::invalidate_labels` is called
+    pub fn cache_labels(mut self, cache: bool) -> Self {
+        self.cache_labels = cache;
+        self
+    }
+
+    /// ### rtl
+    ///
+    /// Set whether to render rows from right to left: indentation, highlight symbol and arrows are
+    /// mirrored and labels are right-aligned
+    pub fn rtl(mut self, rtl: bool) -> Self {
+        self.rtl = rtl;
+        self
+    }
+}
+
+// -- layout
+
+impl<'a, V: NodeValue> TreeWidget<'a, V> {
+    /// ### required_height
+    ///
+    /// Get the height required to render all the visible rows of the tree, including the block
+    /// borders (and padding) and the footer. Use this to shrink-wrap the tree area to its content
+    pub fn required_height(&self, state: &TreeState) -> usize {
+        let block_height = match self.block.as_ref() {
+            Some(block) => {
+                let area = Rect::new(0, 0, u16::MAX, u16::MAX);
+                (area.height - block.inner(area).height) as usize
+            }
+            None => 0,
+        };
+        let footer_height = match self.footer {
+            Some(_) => 1,
+            None => 0,
+        };
+        state.visible_len(self.tree.root()) + block_height + footer_height
+    }
 }
 
 // -- render
 
 struct Render {
     depth: usize,
+    /// Spaces to use for each depth level in this render
+    indent_size: usize,
     skip_rows: usize,
+    /// Width of the widest key among the siblings being rendered, if any has a key (see `NodeValue::key_parts`)
+    key_column: Option<usize>,
+    /// Columns (x, width) of the arrow of each rendered row, if it is a branch
+    arrows: Vec<Option<(u16, u16)>>,
+    /// Whether the node being rendered and each of its ancestors below the root have a next visible sibling
+    guides: Vec<bool>,
+    /// Columns (x, width) of the cells of the table mode, after the tree column
+    cells: Vec<(u16, u16)>,
+    /// Columns (x, width) spanned by the cells, with the blank columns between them
+    cells_span: (u16, u16),
 }
 
 impl<'a, V: NodeValue> Widget for TreeWidget<'a, V> {
@@ -105,7 +586,9 @@ impl<'a, V: NodeValue> StatefulWidget for TreeWidget<'a, V> {
 
     fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         // Set style for area
-        buf.set_style(area, self.style);
+        if self.background_fill == BackgroundFill::Area {
+            buf.set_style(area, self.style);
+        }
         // Build block
         let area = match self.block.take() {
             Some(b) => {
@@ -115,20 +598,284 @@ impl<'a, V: NodeValue> StatefulWidget for TreeWidget<'a, V> {
             }
             None => area,
         };
+        if self.background_fill == BackgroundFill::Inner {
+            buf.set_style(area, self.style);
+        }
+        self.render_tree(area, buf, state);
+    }
+}
+
+#[cfg(feature = "unstable-widget-ref")]
+impl<'a, V: NodeValue> WidgetRef for TreeWidget<'a, V> {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let mut state = TreeState::default();
+        StatefulWidgetRef::render_ref(self, area, buf, &mut state);
+    }
+}
+
+#[cfg(feature = "unstable-widget-ref")]
+impl<'a, V: NodeValue> StatefulWidgetRef for TreeWidget<'a, V> {
+    type State = TreeState;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // Set style for area
+        if self.background_fill == BackgroundFill::Area {
+            buf.set_style(area, self.style);
+        }
+        // Build block
+        let area = match self.block.as_ref() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render_ref(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+        if self.background_fill == BackgroundFill::Inner {
+            buf.set_style(area, self.style);
+        }
+        self.render_tree(area, buf, state);
+    }
+}
+
+impl<'a, V: NodeValue> TreeWidget<'a, V> {
+    /// ### render_tree
+    ///
+    /// Render footer and rows in the area inside the block
+    fn render_tree(&self, area: Rect, buf: &mut Buffer, state: &mut TreeState) {
         // Return if too small
         if area.width < 1 || area.height < 1 {
             return;
         }
+        // Render footer on the last row
+        let area = match self.footer.as_deref() {
+            Some(footer) if area.height > 1 => {
+                buf.set_stringn(
+                    area.x,
+                    area.y + area.height - 1,
+                    footer,
+                    area.width as usize,
+                    self.style,
+                );
+                Rect {
+                    height: area.height - 1,
+                    ..area
+                }
+            }
+            _ => area,
+        };
+        // Render column titles on the first row
+        let (area, cells) = self.render_header(area, buf);
+        let cells_span = match (cells.is_empty(), self.rtl) {
+            (true, _) => (area.right(), 0),
+            (false, false) => (
+                area.right(),
+                cells.iter().map(|(x, w)| x + w).max().unwrap_or_default() - area.right(),
+            ),
+            (false, true) => {
+                let x = cells.iter().map(|(x, _)| *x).min().unwrap_or(area.x);
+                (x, area.x - x)
+            }
+        };
         // Recurse render
+        if self.cache_labels {
+            state.enable_label_cache();
+        }
+        state.refresh_rows(self.tree.root());
+        state.diff_rows();
+        let skip_rows = self.calc_rows_to_skip(state, area.height);
+        let skip_rows = match self.smooth_scroll {
+            true => self.ease_rows_to_skip(state, skip_rows, area.height),
+            false => skip_rows,
+        };
+        state.set_viewport(skip_rows, area.height as usize);
+        let area = self.render_scrollbar(area, buf, state, skip_rows);
+        // Rows can be clicked on their cells too
+        state.set_viewport_area(match cells_span.1 {
+            0 => area,
+            width => area.union(Rect {
+                x: cells_span.0,
+                width,
+                ..area
+            }),
+        });
         let mut render = Render {
             depth: 1,
-            skip_rows: self.calc_rows_to_skip(state, area.height),
+            indent_size: self.calc_indent_size(state, area.width),
+            skip_rows,
+            key_column: None,
+            arrows: Vec::new(),
+            guides: Vec::new(),
+            cells,
+            cells_span,
         };
         self.iter_nodes(self.tree.root(), area, buf, state, &mut render);
+        state.set_arrow_columns(render.arrows);
+    }
+
+    /// ### render_header
+    ///
+    /// Render the titles of the columns of the table mode on the first row of `area`, if any, with the indicator of the
+    /// sorted column. Returns the area left to the rows of the tree column and the columns (x, width) of the cells
+    fn render_header(&self, area: Rect, buf: &mut Buffer) -> (Rect, Vec<(u16, u16)>) {
+        if self.columns.is_empty() || area.height < 2 {
+            return (area, Vec::new());
+        }
+        // Each cell is preceded by a blank column
+        let cells_width = self.columns.iter().skip(1).fold(0u16, |acc, (_, width)| {
+            acc.saturating_add(width.saturating_add(1))
+        });
+        let tree_width = area.width.saturating_sub(cells_width);
+        let tree_x = match self.rtl {
+            false => area.x,
+            true => area.right() - tree_width,
+        };
+        // Cells follow the tree column; in RTL mode they precede it
+        let mut cursor = match self.rtl {
+            false => tree_x + tree_width,
+            true => tree_x,
+        };
+        let cells: Vec<(u16, u16)> = self
+            .columns
+            .iter()
+            .skip(1)
+            .map(|(_, width)| match self.rtl {
+                false => {
+                    let x = (cursor + 1).min(area.right());
+                    let width = (*width).min(area.right() - x);
+                    cursor = x + width;
+                    (x, width)
+                }
+                true => {
+                    let end = cursor.saturating_sub(1).max(area.x);
+                    let x = end.saturating_sub(*width).max(area.x);
+                    cursor = x;
+                    (x, end - x)
+                }
+            })
+            .collect();
+        // Titles
+        let style = self.style.add_modifier(Modifier::BOLD);
+        for (column, ((title, _), (x, width))) in self
+            .columns
+            .iter()
+            .zip(iter::once((tree_x, tree_width)).chain(cells.iter().copied()))
+            .enumerate()
+        {
+            // The indicator is kept visible, cutting the title if needed
+            let indicator = match self.sorted_column {
+                Some((sorted, order)) if sorted == column && width >= 2 => {
+                    Some(order.indicator(self.ascii))
+                }
+                _ => None,
+            };
+            let title_width = match indicator {
+                Some(_) => width - 2,
+                None => width,
+            };
+            let (end, _) = buf.set_stringn(x, area.y, title, title_width as usize, style);
+            if let Some(indicator) = indicator {
+                buf.set_stringn(end + 1, area.y, indicator, 1, style);
+            }
+        }
+        let area = Rect {
+            x: tree_x,
+            y: area.y + 1,
+            width: tree_width,
+            height: area.height - 1,
+        };
+        (area, cells)
+    }
+
+    /// ### render_cells
+    ///
+    /// Render the cells of `node` in the table mode on the row `y`, with `style`.
+    /// Rows without a node (i.e. "more" rows) only get the style
+    fn render_cells(
+        &self,
+        node: Option<&Node<V>>,
+        y: u16,
+        style: Style,
+        buf: &mut Buffer,
+        render: &Render,
+    ) {
+        if render.cells.is_empty() {
+            return;
+        }
+        let (x, width) = render.cells_span;
+        buf.set_style(Rect::new(x, y, width, 1), style);
+        let Some(node) = node else {
+            return;
+        };
+        for (column, (x, width)) in render.cells.iter().enumerate() {
+            if let Some(cell) = node.value().cell(column + 1) {
+                buf.set_stringn(*x, y, cell, *width as usize, style);
+            }
+        }
+    }
+
+    /// ### render_scrollbar
+    ///
+    /// Render the scrollbar, if enabled and if the rows don't fit the area, with the view scrolled by `offset` rows.
+    /// Returns the area left to rows
+    fn render_scrollbar(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &TreeState,
+        offset: usize,
+    ) -> Rect {
+        let rows = state.visible_len(self.tree.root());
+        let height = area.height as usize;
+        if !self.scrollbar || rows <= height || area.width < 2 {
+            return area;
+        }
+        let (orientation, rows_area) = match self.rtl {
+            false => (
+                ScrollbarOrientation::VerticalRight,
+                Rect {
+                    width: area.width - 1,
+                    ..area
+                },
+            ),
+            true => (
+                ScrollbarOrientation::VerticalLeft,
+                Rect {
+                    x: area.x + 1,
+                    width: area.width - 1,
+                    ..area
+                },
+            ),
+        };
+        let mut scrollbar = Scrollbar::new(orientation)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .style(self.style);
+        if self.ascii {
+            scrollbar = scrollbar.track_symbol(Some("|")).thumb_symbol("#");
+        }
+        // One position for each offset, so that the thumb reaches the end when the last row is visible
+        let mut scrollbar_state = ScrollbarState::new(rows - height + 1)
+            .position(offset)
+            .viewport_content_length(height);
+        scrollbar.render(area, buf, &mut scrollbar_state);
+        rows_area
+    }
+
+    /// ### calc_indent_size
+    ///
+    /// Get the indent size to use for rendering rows in an area `width` columns wide (see `auto_indent`)
+    fn calc_indent_size(&self, state: &TreeState, width: u16) -> usize {
+        match self.auto_indent {
+            Some((min, max)) => {
+                // Root is rendered at depth 1
+                let levels = state.max_depth() + 1;
+                (width as usize / 2 / levels).clamp(min, max)
+            }
+            None => self.indent_size,
+        }
     }
-}
 
-impl<'a, V: NodeValue> TreeWidget<'a, V> {
     fn iter_nodes(
         &self,
         node: &Node<V>,
@@ -143,99 +890,90 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         if state.is_open(node) {
             // Increment depth
             render.depth += 1;
-            for child in node.iter() {
+            let key_column = state
+                .visible_children(node)
+                .filter(|(_, more)| !more)
+                .filter_map(|(x, _)| self.key_width(x, state))
+                .max();
+            let parent_key_column = std::mem::replace(&mut render.key_column, key_column);
+            let mut children = state.visible_children(node).peekable();
+            while let Some((child, more)) = children.next() {
                 if area.height == 0 {
                     break;
                 }
-                area = self.iter_nodes(child, area, buf, state, render);
+                render.guides.push(children.peek().is_some());
+                area = match more {
+                    true => {
+                        let hidden = state.hidden_children(node);
+                        self.render_more_row(child, hidden, area, buf, state, render)
+                    }
+                    false => self.iter_nodes(child, area, buf, state, render),
+                };
+                render.guides.pop();
             }
             // Decrement depth
             render.depth -= 1;
+            render.key_column = parent_key_column;
         }
         area
     }
 
-    fn render_node(
+    /// ### render_more_row
+    ///
+    /// Render the placeholder row which replaces the `hidden` children of a node, starting from `node`
+    fn render_more_row(
         &self,
         node: &Node<V>,
+        hidden: usize,
         area: Rect,
         buf: &mut Buffer,
         state: &TreeState,
         render: &mut Render,
     ) -> Rect {
-        // If row should skip, then skip
         if render.skip_rows > 0 {
             render.skip_rows -= 1;
             return area;
         }
-        let highlight_symbol = match state.is_selected(node) {
-            true => Some(self.highlight_symbol.clone().unwrap_or_default()),
-            false => None,
-        };
-        // Get area for current node
+        render.arrows.push(None);
+        let selected = state.is_selected(node);
         let node_area = Rect {
             x: area.x,
             y: area.y,
             width: area.width,
             height: 1,
         };
-        // Get style to use
-        let style = match state.is_selected(node) {
-            false => self.style,
-            true => self.highlight_style,
-        };
-        // Apply style
+        let style = self.row_style(node, state, render.depth - 1);
         buf.set_style(node_area, style);
-        // Calc depth for node (is selected?)
-        let indent_size = render.depth * self.indent_size;
-        let indent_size = match state.is_selected(node) {
-            true if highlight_symbol.is_some() => {
-                indent_size.saturating_sub(highlight_symbol.as_deref().unwrap().width() + 1)
-            }
-            _ => indent_size,
-        };
-        let width: usize = area.width as usize;
-        // Write indentation
-        let (start_x, start_y) = buf.set_stringn(
-            area.x,
-            area.y,
-            " ".repeat(indent_size),
-            width - indent_size,
-            style,
-        );
-        // Write highlight symbol
-        let (start_x, start_y) = highlight_symbol
-            .map(|x| buf.set_stringn(start_x, start_y, x, width - start_x as usize, style))
-            .map(|(x, y)| buf.set_stringn(x, y, " ", width - start_x as usize, style))
-            .unwrap_or((start_x, start_y));
-
-        let mut start_x = start_x;
-        let mut start_y = start_y;
-        for (text, part_style) in node.value().render_parts_iter() {
-            let part_style = part_style.unwrap_or(style);
-            // Write node name
-            (start_x, start_y) =
-                buf.set_stringn(start_x, start_y, text, width - start_x as usize, part_style);
-        }
-        // Write arrow based on node
-        let write_after = if state.is_open(node) {
-            // Is open
-            " \u{25bc}" // Arrow down
-        } else if node.is_leaf() {
-            // Is leaf (has no children)
-            "  "
+        self.render_cells(None, node_area.y, style, buf, render);
+        let mut indent_size = render.depth * render.indent_size;
+        let mut parts: Vec<(Cow<str>, Style)> = Vec::new();
+        if selected {
+            let symbol = self.highlight_symbol.as_deref().unwrap_or_default();
+            indent_size = indent_size.saturating_sub(display_width(symbol) + 1);
+            parts.push((self.indent(render, indent_size), self.indent_style(style)));
+            parts.push((
+                Cow::Borrowed(symbol),
+                self.highlight_symbol_style.unwrap_or(style),
+            ));
+            parts.push((Cow::Borrowed(" "), style));
         } else {
-            // Has children, but is closed
-            " \u{25b6}" // Arrow to right
-        };
-        let _ = buf.set_stringn(
-            start_x,
-            start_y,
-            write_after,
-            width - start_x as usize,
+            parts.push((self.indent(render, indent_size), self.indent_style(style)));
+        }
+        parts.push((
+            Cow::Owned(format!(
+                "{} {} more (press Enter to expand)",
+                match self.ascii {
+                    true => "...",
+                    false => "\u{2026}",
+                },
+                thousands(hidden)
+            )),
             style,
-        );
-        // Return new area
+        ));
+        match self.rtl {
+            false => Self::write_row(node_area, buf, &parts),
+            true => Self::write_row_rtl(node_area, buf, &parts),
+        }
         Rect {
             x: area.x,
             y: area.y + 1,
@@ -244,54 +982,685 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
         }
     }
 
-    /// ### calc_rows_to__skip
-    ///
-    /// Calculate rows to skip before starting rendering the current tree
-    fn calc_rows_to_skip(&self, state: &TreeState, height: u16) -> usize {
-        // if no node is selected, return 0
-        let selected = match state.selected() {
-            Some(s) => s,
-            None => return 0,
+    fn render_node(
+        &self,
+        node: &Node<V>,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &TreeState,
+        render: &mut Render,
+    ) -> Rect {
+        // If row should skip, then skip
+        if render.skip_rows > 0 {
+            render.skip_rows -= 1;
+            return area;
+        }
+        let highlight_symbol = match state.is_selected(node) {
+            true => Some(self.highlight_symbol.as_deref().unwrap_or_default()),
+            false => None,
         };
-        /// ### calc_rows_to_skip_r
-        ///
-        /// Inner recursive call to calc rows to skip.
-        /// Returns the rows to skip and whether the item has been found (this last oneshould be ignored)
-        fn calc_rows_to_skip_r<V: NodeValue>(
-            node: &Node<V>,
-            state: &TreeState,
-            selected: &str,
-            mut acc: usize,
-        ) -> (usize, bool) {
-            // If node is selected, return `acc`
-            if node.id().as_str() == selected {
-                (acc + 1, true)
-            } else if state.is_closed(node) {
-                // If node is closed, then return acc + 1
-                (acc + 1, false)
-            } else {
-                // is open and is not selected
-                // I increment the accumulator by one
-                acc += 1;
-                // For each child, let's call this function
-                for child in node.iter() {
-                    let (ret, found) = calc_rows_to_skip_r(child, state, selected, acc);
-                    // Set acc to ret
-                    acc = ret;
-                    // If found, return
-                    if found {
-                        return (acc, true);
+        // Get area for current node
+        let node_area = Rect {
+            x: area.x,
+            y: area.y,
+            width: area.width,
+            height: 1,
+        };
+        // Get style to use
+        let style = self.row_style(node, state, render.depth - 1);
+        // Apply style
+        buf.set_style(node_area, style);
+        self.render_cells(Some(node), node_area.y, style, buf, render);
+        // Area for next node
+        let next_area = Rect {
+            x: area.x,
+            y: area.y + 1,
+            width: area.width,
+            height: area.height - 1,
+        };
+        // Custom renderer
+        if let Some(renderer) = self.row_renderer.as_ref() {
+            let ctx = RowCtx {
+                area: node_area,
+                depth: render.depth - 1,
+                indent: render.depth * render.indent_size,
+                selected: state.is_selected(node),
+                open: state.is_open(node),
+                style,
+            };
+            if renderer(node, ctx, buf) {
+                render.arrows.push(None);
+                return next_area;
+            }
+        }
+        // Calc depth for node (is selected?)
+        let indent_size = render.depth * render.indent_size;
+        let indent_size = match highlight_symbol {
+            Some(symbol) => indent_size.saturating_sub(display_width(symbol) + 1),
+            None => indent_size,
+        };
+        // Compose row
+        let mut parts: Vec<(Cow<str>, Style)> = Vec::new();
+        // Indentation
+        parts.push((self.indent(render, indent_size), self.indent_style(style)));
+        // Highlight symbol
+        if let Some(symbol) = highlight_symbol {
+            parts.push((
+                Cow::Borrowed(symbol),
+                self.highlight_symbol_style.unwrap_or(style),
+            ));
+            parts.push((Cow::Borrowed(" "), style));
+        }
+        // Arrow before label
+        let arrow = self.node_arrow(node, state);
+        let mut arrow_index = parts.len();
+        if self.arrow_first {
+            parts.push((Cow::Borrowed(arrow), style));
+        }
+        // Mark symbol
+        let mark_style = match self.marks {
+            Some((ids, symbol, mark_style)) if ids.contains(node.id()) => {
+                parts.push((Cow::Borrowed(symbol), style.patch(mark_style)));
+                Some(mark_style)
+            }
+            Some((_, symbol, _)) => {
+                parts.push((spaces(display_width(symbol)), style));
+                None
+            }
+            None => None,
+        };
+        // Decoration prefix
+        let decoration = self.node_decoration(node);
+        let decoration = decoration.as_deref();
+        let decoration_style = decoration.and_then(|x| x.get_style()).unwrap_or(style);
+        if let Some(prefix) = decoration.and_then(|x| x.get_prefix()) {
+            parts.push((Cow::Borrowed(prefix), decoration_style));
+        }
+        // Icon
+        if let Some(icon) = node.value().icon() {
+            let icon_style = match self.icon_style {
+                Some(icon_style) => style.patch(icon_style),
+                None => style,
+            };
+            parts.push((Cow::Borrowed(icon), icon_style));
+            parts.push((Cow::Borrowed(" "), style));
+        }
+        // Node name; parts after the key are aligned to the key column
+        let label = self.display_map.as_ref().map(|map| map(node.value()));
+        let key_parts = match label {
+            Some(_) => None,
+            None => node.value().key_parts(),
+        };
+        let cached = self.cached_label_parts(node, state);
+        let key_padding = match (self.key_width(node, state), render.key_column) {
+            (Some(width), Some(column)) => column.saturating_sub(width) + 1,
+            _ => 0,
+        };
+        for (i, (text, part_style)) in
+            Self::label_parts(node, label.as_deref(), cached.as_deref()).enumerate()
+        {
+            if Some(i) == key_parts {
+                parts.push((spaces(key_padding), style));
+            }
+            let part_style = part_style.unwrap_or(style);
+            let part_style = match mark_style {
+                Some(mark_style) => part_style.patch(mark_style),
+                None => part_style,
+            };
+            match self.matches.as_ref() {
+                Some((matcher, match_style)) => {
+                    // Split part around matches
+                    let mut last = 0;
+                    for range in matcher.match_ranges(text) {
+                        let (start, end) = (range.start, range.end);
+                        if start > last {
+                            parts.push((Cow::Borrowed(&text[last..start]), part_style));
+                        }
+                        parts.push((
+                            Cow::Borrowed(&text[start..end]),
+                            part_style.patch(*match_style),
+                        ));
+                        last = end;
+                    }
+                    if last < text.len() {
+                        parts.push((Cow::Borrowed(&text[last..]), part_style));
                     }
                 }
-                (acc, false)
+                None => parts.push((Cow::Borrowed(text), part_style)),
+            }
+        }
+        // Decoration suffix
+        if let Some(suffix) = decoration.and_then(|x| x.get_suffix()) {
+            parts.push((Cow::Borrowed(suffix), decoration_style));
+        }
+        // Arrow after label
+        if !self.arrow_first {
+            arrow_index = parts.len();
+            parts.push((Cow::Borrowed(arrow), style));
+        }
+        // Expected children of closed branches which haven't been loaded yet
+        if let Some(count) = node
+            .value()
+            .child_count_hint()
+            .filter(|_| node.is_leaf() && state.is_closed(node) && state.is_branch(node))
+        {
+            parts.push((Cow::Owned(format!(" ({count})")), style));
+        }
+        // Placeholder of nodes whose children are being loaded
+        if let Some((_, placeholder)) = self.loading.filter(|(ids, _)| ids.contains(node.id())) {
+            parts.push((Cow::Borrowed(" "), style));
+            parts.push((Cow::Borrowed(placeholder), style));
+        }
+        // Badge of collapsed nodes with dirty descendants
+        if let Some((_, badge, badge_style)) = self
+            .dirty
+            .filter(|(ids, _, _)| state.is_closed(node) && ids.contains(node.id()))
+        {
+            parts.push((Cow::Borrowed(" "), style));
+            parts.push((Cow::Borrowed(badge), style.patch(badge_style)));
+        }
+        // Labels may come from external data
+        expand_tabs(&mut parts, self.tab_width);
+        if let Some(ellipsis) = self.ellipsis.as_deref() {
+            let pinned = (!self.arrow_first).then_some(arrow_index);
+            if let Some(index) =
+                Self::ellipsize(&mut parts, node_area.width.into(), ellipsis, style, pinned)
+            {
+                arrow_index = index;
+            }
+        }
+        render.arrows.push(
+            state
+                .is_branch(node)
+                .then(|| self.arrow_columns(node_area, &parts, arrow_index))
+                .flatten(),
+        );
+        // Write row
+        match self.rtl {
+            false => Self::write_row(node_area, buf, &parts),
+            true => Self::write_row_rtl(node_area, buf, &parts),
+        }
+        next_area
+    }
+
+    /// ### indent
+    ///
+    /// Get the indentation of the row being rendered, `columns` wide: spaces, or the guide lines if enabled.
+    /// Guides are cut on the side of the label, to make room for the highlight symbol
+    fn indent(&self, render: &Render, columns: usize) -> Cow<'static, str> {
+        if !self.guides {
+            return spaces(columns);
+        }
+        let width = render.indent_size;
+        let last = render.guides.len();
+        // The root level is blank
+        let mut indent = " ".repeat(width);
+        for (level, next) in render.guides.iter().enumerate() {
+            let (head, fill) = match (level + 1 == last, next) {
+                (true, true) => ('\u{251c}', '\u{2500}'),
+                (true, false) => ('\u{2514}', '\u{2500}'),
+                (false, true) => ('\u{2502}', ' '),
+                (false, false) => (' ', ' '),
+            };
+            if width > 0 {
+                indent.push(head);
+                indent.extend(iter::repeat_n(fill, width.saturating_sub(2)));
+            }
+            if width > 1 {
+                indent.push(' ');
+            }
+        }
+        let indent = indent.chars().take(columns);
+        let indent: String = match self.rtl {
+            false => indent.map(|x| self.guide_char(x)).collect(),
+            true => indent
+                .collect::<Vec<char>>()
+                .into_iter()
+                .rev()
+                .map(|x| match x {
+                    '\u{251c}' => '\u{2524}',
+                    '\u{2514}' => '\u{2518}',
+                    x => x,
+                })
+                .map(|x| self.guide_char(x))
+                .collect(),
+        };
+        Cow::Owned(indent)
+    }
+
+    /// ### indent_style
+    ///
+    /// Get the style for the indentation of a row with `style`: the guides style is patched on it, if guides are rendered
+    fn indent_style(&self, style: Style) -> Style {
+        match self.guides_style.filter(|_| self.guides) {
+            Some(guides_style) => style.patch(guides_style),
+            None => style,
+        }
+    }
+
+    /// ### guide_char
+    ///
+    /// Get the character to render for the guide line character `c`, with ASCII characters only if enabled
+    fn guide_char(&self, c: char) -> char {
+        match (self.ascii, c) {
+            (false, c) => c,
+            (true, '\u{251c}' | '\u{2524}' | '\u{2502}') => '|',
+            (true, '\u{2514}') => '`',
+            (true, '\u{2518}') => '\'',
+            (true, '\u{2500}') => '-',
+            (true, c) => c,
+        }
+    }
+
+    /// ### row_style
+    ///
+    /// Get the style for the row of `node`; with soft selection, the committed node is highlighted,
+    /// while the selected one is rendered as a preview. The node's own style (see `NodeValue::base_style` and
+    /// `NodeValue::selected_style`) and the drop indicator are patched on top
+    fn row_style(&self, node: &Node<V>, state: &TreeState, depth: usize) -> Style {
+        let (style, node_style) = match (self.soft_selection, state.is_selected(node)) {
+            (Some((committed, _)), _) if committed == Some(node.id().as_str()) => {
+                (self.highlight_style, node.value().selected_style())
+            }
+            (Some((_, preview_style)), true) => (preview_style, node.value().selected_style()),
+            (None, true) => (self.highlight_style, node.value().selected_style()),
+            (_, false) => (self.depth_style(depth), node.value().base_style()),
+        };
+        let style = match node_style {
+            Some(node_style) => style.patch(node_style),
+            None => style,
+        };
+        match self.drop_indicator {
+            Some((id, drop_style)) if id == node.id() => style.patch(drop_style),
+            _ => style,
+        }
+    }
+
+    /// ### depth_style
+    ///
+    /// Get the style for the rows at `depth` which aren't highlighted
+    fn depth_style(&self, depth: usize) -> Style {
+        match depth.checked_rem(self.style_per_depth.len()) {
+            Some(index) => self.style.patch(self.style_per_depth[index]),
+            None => self.style,
+        }
+    }
+
+    /// ### node_arrow
+    ///
+    /// Get the arrow to render for `node`; the space always separates the arrow from the label.
+    /// In RTL mode the arrow is mirrored
+    fn node_arrow(&self, node: &Node<V>, state: &TreeState) -> &'static str {
+        // Whether the space must be rendered on the left of the arrow
+        let space_left = self.arrow_first == self.rtl;
+        if state.is_open(node) {
+            // Arrow down
+            match (self.ascii, space_left) {
+                (false, true) => " \u{25bc}",
+                (false, false) => "\u{25bc} ",
+                (true, true) => " v",
+                (true, false) => "v ",
+            }
+        } else if !state.is_branch(node) {
+            // Is leaf (has no children)
+            "  "
+        } else {
+            // Has children, but is closed
+            match (self.ascii, self.rtl, space_left) {
+                (false, false, true) => " \u{25b6}", // Arrow to right
+                (false, false, false) => "\u{25b6} ",
+                (false, true, true) => " \u{25c0}", // Arrow to left
+                (false, true, false) => "\u{25c0} ",
+                (true, false, true) => " >",
+                (true, false, false) => "> ",
+                (true, true, true) => " <",
+                (true, true, false) => "< ",
+            }
+        }
+    }
+
+    /// ### arrow_columns
+    ///
+    /// Get the columns (x, width) where the part at `index` of the row is written in `area`, cut to the area.
+    /// Returns `None` if it is entirely cut
+    fn arrow_columns(
+        &self,
+        area: Rect,
+        parts: &[(Cow<str>, Style)],
+        index: usize,
+    ) -> Option<(u16, u16)> {
+        // The arrow may have been truncated
+        let width = display_width(&parts.get(index)?.0);
+        let before: usize = parts[..index]
+            .iter()
+            .map(|(text, _)| display_width(text))
+            .sum();
+        let (left, right) = (area.x as usize, area.right() as usize);
+        let (start, end) = match self.rtl {
+            false => (left + before, left + before + width),
+            true => (
+                right.saturating_sub(before + width),
+                right.saturating_sub(before),
+            ),
+        };
+        let (start, end) = (start.max(left), end.min(right));
+        match start < end {
+            true => Some((start as u16, (end - start) as u16)),
+            false => None,
+        }
+    }
+
+    /// ### write_row
+    ///
+    /// Write row parts from left to right, truncating them at the end of the area
+    fn write_row(area: Rect, buf: &mut Buffer, parts: &[(Cow<str>, Style)]) {
+        let mut x = area.x;
+        for (text, style) in parts.iter() {
+            if x >= area.right() {
+                break;
+            }
+            (x, _) = buf.set_stringn(x, area.y, text, (area.right() - x) as usize, *style);
+        }
+    }
+
+    /// ### write_row_rtl
+    ///
+    /// Write row parts mirrored, from the right edge of the area to the left.
+    /// If the row is wider than the area, the leftmost columns (the end of the row) are cut
+    fn write_row_rtl(area: Rect, buf: &mut Buffer, parts: &[(Cow<str>, Style)]) {
+        let total: usize = parts.iter().map(|(text, _)| display_width(text)).sum();
+        let mut overflow = total.saturating_sub(area.width as usize);
+        let mut x = area.right() - (total - overflow) as u16;
+        for (text, style) in parts.iter().rev() {
+            let text = match overflow {
+                0 => Cow::Borrowed(text.as_ref()),
+                _ => Cow::Owned(Self::cut_left(text, &mut overflow)),
+            };
+            (x, _) = buf.set_stringn(x, area.y, text, (area.right() - x) as usize, *style);
+        }
+    }
+
+    /// ### ellipsize
+    ///
+    /// Truncate `parts` to `columns`, ending them with `ellipsis`, if they don't fit. If `pinned` is set, the part
+    /// at that index (i.e. the arrow after the label) is moved after the ellipsis, and the parts following it are
+    /// dropped. Returns the index of the pinned part
+    fn ellipsize<'p>(
+        parts: &mut Vec<(Cow<'p, str>, Style)>,
+        columns: usize,
+        ellipsis: &'p str,
+        style: Style,
+        pinned: Option<usize>,
+    ) -> Option<usize> {
+        let total: usize = parts.iter().map(|(text, _)| display_width(text)).sum();
+        if total <= columns {
+            return pinned;
+        }
+        let tail = pinned.and_then(|index| parts.drain(index..).next());
+        let tail_width = tail.as_ref().map(|(text, _)| display_width(text));
+        let mut available =
+            columns.saturating_sub(display_width(ellipsis) + tail_width.unwrap_or_default());
+        let mut kept = 0;
+        for (text, _) in parts.iter_mut() {
+            if available == 0 {
+                break;
+            }
+            kept += 1;
+            let width = display_width(text);
+            if width > available {
+                *text = Cow::Owned(Self::cut_right(text, available));
+                break;
+            }
+            available -= width;
+        }
+        parts.truncate(kept);
+        parts.push((Cow::Borrowed(ellipsis), style));
+        tail.map(|tail| {
+            parts.push(tail);
+            parts.len() - 1
+        })
+    }
+
+    /// ### cut_right
+    ///
+    /// Keep the first `columns` of `text`. A wide grapheme which doesn't fit entirely is replaced by spaces,
+    /// to keep alignment
+    fn cut_right(text: &str, columns: usize) -> String {
+        let mut cut = String::with_capacity(text.len());
+        let mut left = columns;
+        for g in text.graphemes(true) {
+            let w = grapheme_width(g);
+            if w > left {
+                cut.extend(iter::repeat_n(' ', left));
+                break;
+            }
+            left -= w;
+            cut.push_str(g);
+        }
+        cut
+    }
+
+    /// ### cut_left
+    ///
+    /// Cut `columns` from the left of `text`, decreasing `columns` by the amount of columns removed.
+    /// A wide grapheme which doesn't fit entirely is replaced by spaces, to keep alignment
+    fn cut_left(text: &str, columns: &mut usize) -> String {
+        let mut cut = String::with_capacity(text.len());
+        for g in text.graphemes(true) {
+            let w = grapheme_width(g);
+            if *columns == 0 {
+                cut.push_str(g);
+            } else if w <= *columns {
+                *columns -= w;
+            } else {
+                cut.extend(iter::repeat_n(' ', w - *columns));
+                *columns = 0;
+            }
+        }
+        cut
+    }
+
+    /// ### node_decoration
+    ///
+    /// Get decoration for node; if the node has no decoration, the placeholder is returned.
+    /// The bookmark decoration and the decorations of the decorators are then merged in order on top of it
+    fn node_decoration(&self, node: &Node<V>) -> Option<Cow<'_, Decoration>> {
+        let decoration = self
+            .decorations
+            .and_then(|x| x.get(node.id()))
+            .or_else(|| {
+                self.recent
+                    .filter(|(ids, _)| ids.contains(node.id()))
+                    .map(|(_, decoration)| decoration)
+            })
+            .or(self.decoration_placeholder.as_ref())
+            .map(Cow::Borrowed);
+        let bookmark = self
+            .bookmarks
+            .filter(|(ids, _)| ids.contains(node.id()))
+            .map(|(_, decoration)| decoration.clone());
+        bookmark
+            .into_iter()
+            .chain(self.decorators.iter().filter_map(|x| x.decorate(node)))
+            .fold(decoration, |acc, x| match acc {
+                Some(acc) => Some(Cow::Owned(acc.into_owned().merge(&x))),
+                None => Some(Cow::Owned(x)),
+            })
+    }
+
+    /// ### label_parts
+    ///
+    /// Get the parts of the label of `node`: the mapped `label`, if any, otherwise its `cached` render parts,
+    /// if any, otherwise its render parts
+    fn label_parts<'n>(
+        node: &'n Node<V>,
+        label: Option<&'n str>,
+        cached: Option<&'n [(String, Option<Style>)]>,
+    ) -> impl Iterator<Item = (&'n str, Option<Style>)> {
+        let cached = cached.filter(|_| label.is_none());
+        let render_parts = match (label, cached) {
+            (None, None) => Some(node.value().render_parts_iter()),
+            _ => None,
+        };
+        label
+            .map(|x| (x, None))
+            .into_iter()
+            .chain(
+                cached
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(text, style)| (text.as_str(), *style)),
+            )
+            .chain(render_parts.into_iter().flatten())
+    }
+
+    /// ### cached_label_parts
+    ///
+    /// Get the render parts of the label of `node` cached in `state`, if labels are cached
+    fn cached_label_parts(&self, node: &Node<V>, state: &TreeState) -> Option<LabelParts> {
+        match self.cache_labels && self.display_map.is_none() {
+            true => state.label_parts(node),
+            false => None,
+        }
+    }
+
+    /// ### key_width
+    ///
+    /// Get the width of the key of `node`, including its decoration prefix and its icon; `None` if the node has no key
+    fn key_width(&self, node: &Node<V>, state: &TreeState) -> Option<usize> {
+        if self.display_map.is_some() {
+            return None;
+        }
+        let key_parts = node.value().key_parts()?;
+        let cached = self.cached_label_parts(node, state);
+        let prefix = self
+            .node_decoration(node)
+            .as_deref()
+            .and_then(|x| x.get_prefix())
+            .map(display_width)
+            .unwrap_or(0)
+            + node
+                .value()
+                .icon()
+                .map(|x| display_width(x) + 1)
+                .unwrap_or(0);
+        let key: usize = Self::label_parts(node, None, cached.as_deref())
+            .take(key_parts)
+            .map(|(text, _)| display_width(text))
+            .sum();
+        Some(prefix + key)
+    }
+
+    /// ### ease_rows_to_skip
+    ///
+    /// With smooth scroll, keep the current offset if `target` is farther than the viewport height, and store it
+    /// as the scroll target; otherwise scroll to `target` at once
+    fn ease_rows_to_skip(&self, state: &mut TreeState, target: usize, height: u16) -> usize {
+        let max_offset = state
+            .visible_len(self.tree.root())
+            .saturating_sub(height as usize);
+        let offset = state.offset().min(max_offset);
+        match target.abs_diff(offset) > height as usize {
+            true => {
+                state.set_scroll_target(Some(target));
+                offset
+            }
+            false => {
+                state.set_scroll_target(None);
+                target
             }
         }
-        // Return the result of recursive call;
-        // if the result is less than area height, then return 0; otherwise subtract the height to result
-        match calc_rows_to_skip_r(self.tree.root(), state, selected, 0).0 {
-            x if x < (height as usize) => 0,
-            x => x - (height as usize),
+    }
+
+    /// ### calc_rows_to__skip
+    ///
+    /// Calculate rows to skip before starting rendering the current tree.
+    /// The current offset of the state is kept, unless the selected node would be out of the viewport
+    fn calc_rows_to_skip(&self, state: &TreeState, height: u16) -> usize {
+        let height = height as usize;
+        // Don't leave empty rows at the bottom, if the tree fits
+        let max_offset = state.visible_len(self.tree.root()).saturating_sub(height);
+        let offset = state.offset().min(max_offset);
+        // Extra rows to scroll, beyond the ones required to show the selected node
+        let jump = self.scroll_jump.min(height).saturating_sub(1);
+        // if no node is selected, keep offset
+        match state.selected_index(self.tree.root()) {
+            None => offset,
+            Some(index) if index < offset => index.saturating_sub(jump),
+            Some(index) if index >= offset + height => (index + 1 - height + jump).min(max_offset),
+            Some(_) => offset,
+        }
+    }
+}
+
+/// ### thousands
+///
+/// Format `n` with a comma between each group of thousands
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// ### display_width
+///
+/// Get the amount of columns `text` takes once written to the buffer.
+/// The width is calculated by grapheme, as the buffer does, so that sequences (e.g. emojis joined with ZWJ)
+/// are measured as a single symbol
+fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// ### expand_tabs
+///
+/// Replace tabs in `parts` with spaces up to the next tab stop, counting columns from the start of the row,
+/// and remove the other control characters. Parts without control characters are left untouched
+fn expand_tabs(parts: &mut [(Cow<str>, Style)], tab_width: usize) {
+    let mut column = 0;
+    for (text, _) in parts.iter_mut() {
+        if !text.contains(char::is_control) {
+            column += display_width(text);
+            continue;
+        }
+        let mut expanded = String::with_capacity(text.len());
+        for g in text.graphemes(true) {
+            if g == "\t" && tab_width > 0 {
+                let spaces = tab_width - column % tab_width;
+                expanded.extend(iter::repeat_n(' ', spaces));
+                column += spaces;
+            } else if !g.contains(char::is_control) {
+                expanded.push_str(g);
+                column += g.width();
+            }
         }
+        *text = Cow::Owned(expanded);
+    }
+}
+
+/// ### spaces
+///
+/// Get a string of `n` spaces, borrowed from a static buffer unless it is very long
+fn spaces(n: usize) -> Cow<'static, str> {
+    const SPACES: &str = "                                                                                                                                ";
+    match SPACES.get(..n) {
+        Some(spaces) => Cow::Borrowed(spaces),
+        None => Cow::Owned(" ".repeat(n)),
+    }
+}
+
+/// ### grapheme_width
+///
+/// Get the width of a grapheme in the buffer. Graphemes with control characters are not written
+fn grapheme_width(g: &str) -> usize {
+    match g.contains(char::is_control) {
+        true => 0,
+        false => g.width(),
     }
 }
 
@@ -299,10 +1668,12 @@ impl<'a, V: NodeValue> TreeWidget<'a, V> {
 mod test {
 
     use super::*;
+    use crate::fixtures::generate;
     use crate::mock::mock_tree;
+    use crate::{FuzzyMatcher, KeyValueNode};
 
     use pretty_assertions::assert_eq;
-    use tuirealm::ratatui::style::Color;
+    use tuirealm::ratatui::style::{Color, Modifier};
 
     #[test]
     fn should_construct_default_widget() {
@@ -360,4 +1731,1182 @@ mod test {
         // 20th element - height (12) + 1
         assert_eq!(widget.calc_rows_to_skip(&state, 8), 13);
     }
+
+    #[test]
+    fn should_keep_offset_when_selection_in_viewport() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.force_open(&["/", "a", "aA", "aB", "aC", "b", "bA", "bB"]);
+        let bb2 = tree.root().query(&String::from("bB2")).unwrap();
+        state.select(tree.root(), bb2);
+        let widget = TreeWidget::new(&tree);
+        state.set_viewport(13, 8);
+        // Move up: selection is still in viewport
+        state.move_up(tree.root());
+        state.move_up(tree.root());
+        assert_eq!(widget.calc_rows_to_skip(&state, 8), 13);
+        // Selection above the viewport
+        state.select(tree.root(), tree.root().query(&String::from("aC")).unwrap());
+        assert_eq!(widget.calc_rows_to_skip(&state, 8), 10);
+        // Offset out of bounds is clamped
+        state.set_viewport(40, 8);
+        state.select(tree.root(), tree.root());
+        assert_eq!(widget.calc_rows_to_skip(&state, 8), 0);
+    }
+
+    #[test]
+    fn should_render_decorations() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let mut decorations = HashMap::new();
+        decorations.insert(
+            String::from("a"),
+            Decoration::default().prefix("+").suffix("*"),
+        );
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .decorations(&decorations)
+            .decoration_placeholder(Decoration::default().prefix("?"));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    ?/ \u{25bc}            ",
+                "        +a* \u{25b6}       ",
+                "        ?b \u{25b6}        ",
+                "        ?c \u{25b6}        ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_merge_decorators() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let mut decorations = HashMap::new();
+        decorations.insert(
+            String::from("a"),
+            Decoration::default().prefix("+").suffix("*"),
+        );
+        let decorators: Vec<Box<dyn NodeDecorator<String>>> = vec![
+            Box::new(|node: &Node<String>| {
+                ["a", "b"]
+                    .contains(&node.id().as_str())
+                    .then(|| Decoration::default().prefix("!"))
+            }),
+            Box::new(|node: &Node<String>| {
+                (node.id() == "a").then(|| Decoration::default().suffix("~"))
+            }),
+        ];
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .decorations(&decorations)
+            .decoration_placeholder(Decoration::default().prefix("?"))
+            .decorators(&decorators);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    ?/ \u{25bc}            ",
+                "        +!a*~ \u{25b6}     ",
+                "        ?!b \u{25b6}       ",
+                "        ?c \u{25b6}        ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_merge_bookmark_decoration() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let mut decorations = HashMap::new();
+        decorations.insert(String::from("a"), Decoration::default().prefix("+"));
+        let bookmarks = [String::from("a"), String::from("c")];
+        let bookmark = Decoration::default().prefix("*");
+        let area = Rect::new(0, 0, 12, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .decorations(&decorations)
+            .bookmarks(&bookmarks, &bookmark);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    / \u{25bc}     ",
+                "        +*a ",
+                "        b \u{25b6} ",
+                "        *c \u{25b6}",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_render_loading_placeholder() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let loading = HashSet::from([String::from("b")]);
+        let area = Rect::new(0, 0, 18, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .loading(&loading, "\u{280b} loading");
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "  / \u{25bc}             ",
+                "    a \u{25b6}           ",
+                "    b \u{25b6} \u{280b} loading ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_render_dirty_badge() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let dirty = HashSet::from([String::from("/"), String::from("b")]);
+        let area = Rect::new(0, 0, 13, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).dirty_badge(&dirty, "\u{25cf}", Style::default());
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        // Root is open, so it has no badge
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    / \u{25bc}      ",
+                "        a \u{25b6}  ",
+                "        b \u{25b6} \u{25cf}",
+                "        c \u{25b6}  ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_render_marked_nodes() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let marked = HashSet::from([String::from("b")]);
+        let mark_style = Style::default().fg(Color::Green);
+        let area = Rect::new(0, 0, 14, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).marks(&marked, "* ", mark_style);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "      / \u{25bc}     ",
+            "          a \u{25b6} ",
+            "        * b \u{25b6} ",
+            "          c \u{25b6} ",
+        ]);
+        expected.set_style(Rect::new(8, 2, 3, 1), mark_style);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_render_highlight_symbol_with_its_own_style() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .highlight_style(Style::default().fg(Color::Yellow))
+            .highlight_symbol(String::from(">"))
+            .highlight_symbol_style(Style::default().fg(Color::Red));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines(["    / \u{25bc}     ", "      > a \u{25b6} "]);
+        expected.set_style(Rect::new(0, 1, 12, 1), Style::default().fg(Color::Yellow));
+        expected.set_style(Rect::new(6, 1, 1, 1), Style::default().fg(Color::Red));
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_render_ascii_arrows() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree).ascii(true),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["    / v     ", "        a > ", "        b > "])
+        );
+    }
+
+    #[test]
+    fn should_truncate_rows_with_ellipsis() {
+        let tree = Tree::new(
+            Node::new(String::from("/"), String::from("/"))
+                .with_child(
+                    Node::new(String::from("a"), String::from("a long label"))
+                        .with_child(Node::new(String::from("a0"), String::from("a0"))),
+                )
+                .with_child(Node::new(String::from("b"), String::from("b long label"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree).ascii(true).ellipsis("~"),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["    / v     ", "        a~ >", "        b~  "])
+        );
+        // The arrow before the label is kept by the indentation
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree)
+                .ascii(true)
+                .arrow_first(true)
+                .ellipsis("\u{2026}"),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["    v /     ", "        > a\u{2026}", "          b\u{2026}"])
+        );
+    }
+
+    #[test]
+    fn should_render_style_per_depth() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("aA0")).unwrap(),
+        );
+        let area = Rect::new(0, 0, 12, 4);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree)
+                .highlight_style(Style::default().fg(Color::Yellow))
+                .style_per_depth(vec![
+                    Style::default().fg(Color::Red),
+                    Style::default().fg(Color::Blue),
+                ]),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        let colors: Vec<Color> = (0..4).map(|y| buf[(11, y)].fg).collect();
+        assert_eq!(
+            colors,
+            vec![Color::Red, Color::Blue, Color::Red, Color::Yellow]
+        );
+    }
+
+    #[test]
+    fn should_render_scrollbar() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(area);
+        // 4 rows in 2: the thumb covers half of the track
+        let widget = TreeWidget::new(&tree).ascii(true).scrollbar(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(buf, Buffer::with_lines(["    / v    #", "        a >|"]));
+        state.select(tree.root(), tree.root().query(&String::from("c")).unwrap());
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 3));
+        let widget = TreeWidget::new(&tree).ascii(true).scrollbar(true);
+        StatefulWidget::render(widget, Rect::new(0, 0, 12, 3), &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["        a >|", "        b >#", "        c >#"])
+        );
+        // No scrollbar if rows fit
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 4));
+        let widget = TreeWidget::new(&tree).ascii(true).scrollbar(true);
+        StatefulWidget::render(widget, Rect::new(0, 0, 12, 4), &mut buf, &mut state);
+        assert_eq!(buf[(11, 0)].symbol(), " ");
+    }
+
+    #[test]
+    fn should_render_guides() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.open(tree.root());
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 20, 8);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).guides(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    / \u{25bc}            ",
+                "    \u{251c}\u{2500}\u{2500} a \u{25bc}        ",
+                "    \u{2502}   \u{251c}\u{2500}\u{2500} aA \u{25bc}   ",
+                "    \u{2502}   \u{2502}   \u{251c}\u{2500}\u{2500} aA0 ",
+                "    \u{2502}   \u{2502}   \u{251c}\u{2500}\u{2500} aA1 ",
+                "    \u{2502}   \u{2502}   \u{2514}\u{2500}\u{2500} aA2 ",
+                "    \u{2502}   \u{251c}\u{2500}\u{2500} aB \u{25b6}   ",
+                "    \u{2502}   \u{2514}\u{2500}\u{2500} aC \u{25b6}   ",
+            ])
+        );
+        // Ascii, right to left, with highlight symbol
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .guides(true)
+            .ascii(true)
+            .rtl(true)
+            .indent_size(3)
+            .highlight_symbol(String::from(">"));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(buf, Buffer::with_lines(["      v /   ", "   v a >|   "]));
+    }
+
+    #[test]
+    fn should_render_guides_and_icons_styles() {
+        #[derive(Default)]
+        struct Iconed(&'static str);
+
+        impl NodeValue for Iconed {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn icon(&self) -> Option<&str> {
+                (self.0 == "a").then_some("*")
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(String::from("/"), Iconed("/"))
+                .with_child(Node::new(String::from("a"), Iconed("a"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .guides(true)
+            .guides_style(Style::default().fg(Color::DarkGray))
+            .icon_style(Style::default().fg(Color::Cyan));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(buf[(4, 1)].symbol(), "\u{2514}");
+        assert_eq!(buf[(4, 1)].fg, Color::DarkGray);
+        assert_eq!(buf[(8, 1)].symbol(), "*");
+        assert_eq!(buf[(8, 1)].fg, Color::Cyan);
+        assert_eq!(buf[(10, 1)].symbol(), "a");
+        assert_eq!(buf[(10, 1)].fg, Color::Reset);
+        // Guides style is not applied to indentation without guides
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).guides_style(Style::default().fg(Color::DarkGray));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert!(buf.content.iter().all(|x| x.fg == Color::Reset));
+    }
+
+    #[test]
+    fn should_render_right_to_left() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .highlight_symbol(String::from(">"))
+            .rtl(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "     \u{25bc} /    ",
+                " \u{25c0} a >      ",
+                " \u{25c0} b        ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_render_arrow_before_label() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        state.open(tree.root().query(&String::from("aA")).unwrap());
+        let area = Rect::new(0, 0, 14, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).indent_size(2).arrow_first(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "  \u{25bc} /         ",
+                "    \u{25bc} a       ",
+                "      \u{25bc} aA    ",
+                "          aA0 ",
+            ])
+        );
+        // Right to left
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .arrow_first(true)
+            .rtl(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "         / \u{25bc}  ",
+                "       a \u{25bc}    ",
+                "    aA \u{25bc}      ",
+                " aA0          ",
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-widget-ref")]
+    fn should_render_by_ref() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let widget = TreeWidget::new(&tree)
+            .block(Block::default())
+            .highlight_symbol(String::from(">"))
+            .footer(String::from("footer"));
+        // Render twice with the same widget
+        for _ in 0..2 {
+            let mut buf = Buffer::empty(area);
+            StatefulWidgetRef::render_ref(&widget, area, &mut buf, &mut state);
+            assert_eq!(
+                buf,
+                Buffer::with_lines(["    / \u{25bc}     ", "      > a \u{25b6} ", "footer      ",])
+            );
+        }
+    }
+
+    #[test]
+    fn should_highlight_matches_in_labels() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bA0")).unwrap(),
+        );
+        let area = Rect::new(0, 0, 12, 5);
+        let mut buf = Buffer::empty(area);
+        let match_style = Style::default().fg(Color::Yellow);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_matches("a", match_style);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "  / \u{25bc}       ",
+            "    a \u{25b6}     ",
+            "    b \u{25bc}     ",
+            "      bA \u{25bc}  ",
+            "        bA0 ",
+        ]);
+        expected.set_style(Rect::new(4, 1, 1, 1), match_style);
+        expected.set_style(Rect::new(7, 3, 1, 1), match_style);
+        expected.set_style(Rect::new(9, 4, 1, 1), match_style);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_compute_indent_size_from_depth_and_width() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        // Deepest row is bA0, at depth 3
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bA0")).unwrap(),
+        );
+        for (width, indent_size) in [(16, 2), (10, 1), (4, 1), (40, 4)] {
+            let area = Rect::new(0, 0, width, 5);
+            let mut buf = Buffer::empty(area);
+            let widget = TreeWidget::new(&tree).auto_indent(1, 4);
+            StatefulWidget::render(widget, area, &mut buf, &mut state);
+            let mut expected = Buffer::empty(area);
+            let widget = TreeWidget::new(&tree).indent_size(indent_size);
+            StatefulWidget::render(widget, area, &mut expected, &mut state);
+            assert_eq!(buf, expected, "width {width}");
+        }
+    }
+
+    #[test]
+    fn should_highlight_matcher_ranges_in_labels() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("bA0")).unwrap(),
+        );
+        let area = Rect::new(0, 0, 12, 5);
+        let mut buf = Buffer::empty(area);
+        let match_style = Style::default().fg(Color::Yellow);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_matcher(FuzzyMatcher::new("b0"), match_style);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "  / \u{25bc}       ",
+            "    a \u{25b6}     ",
+            "    b \u{25bc}     ",
+            "      bA \u{25bc}  ",
+            "        bA0 ",
+        ]);
+        expected.set_style(Rect::new(8, 4, 1, 1), match_style);
+        expected.set_style(Rect::new(10, 4, 1, 1), match_style);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_align_values_of_siblings() {
+        let tree = Tree::new(
+            Node::new(String::from("/"), KeyValueNode::new("config", ""))
+                .with_child(Node::new(
+                    String::from("name"),
+                    KeyValueNode::new("name", "demo"),
+                ))
+                .with_child(
+                    Node::new(String::from("server"), KeyValueNode::new("server", "")).with_child(
+                        Node::new(String::from("port"), KeyValueNode::new("port", "8080")),
+                    ),
+                )
+                .with_child(Node::new(
+                    String::from("timeout"),
+                    KeyValueNode::new("timeout", "30"),
+                )),
+        );
+        let mut state = TreeState::default();
+        state.select(
+            tree.root(),
+            tree.root().query(&String::from("port")).unwrap(),
+        );
+        let area = Rect::new(0, 0, 20, 5);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree).indent_size(2),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "  config \u{25bc}         ",
+                "    name    demo    ",
+                "    server \u{25bc}       ",
+                "      port 8080     ",
+                "    timeout 30      ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_render_soft_selection() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("b")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let highlight_style = Style::default().fg(Color::Yellow);
+        let preview_style = Style::default().fg(Color::Cyan);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_style(highlight_style)
+            .soft_selection(Some("a"), preview_style);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "  / \u{25bc}       ",
+            "    a \u{25b6}     ",
+            "    b \u{25b6}     ",
+        ]);
+        expected.set_style(Rect::new(0, 1, 12, 1), highlight_style);
+        expected.set_style(Rect::new(0, 2, 12, 1), preview_style);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_render_node_styles_and_icons() {
+        #[derive(Default)]
+        struct Styled(&'static str);
+
+        impl NodeValue for Styled {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn base_style(&self) -> Option<Style> {
+                Some(Style::default().fg(Color::Blue))
+            }
+
+            fn selected_style(&self) -> Option<Style> {
+                Some(Style::default().add_modifier(Modifier::BOLD))
+            }
+
+            fn icon(&self) -> Option<&str> {
+                (self.0 == "a").then_some("*")
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(String::from("/"), Styled("/"))
+                .with_child(Node::new(String::from("a"), Styled("a"))),
+        );
+        let mut state = TreeState::default();
+        state.open(tree.root());
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 10, 2);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_style(Style::default().bg(Color::Red));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(buf[(2, 0)].symbol(), "/");
+        assert_eq!(buf[(2, 0)].fg, Color::Blue);
+        assert_eq!(buf[(2, 0)].bg, Color::Reset);
+        assert_eq!(buf[(4, 1)].symbol(), "*");
+        assert_eq!(buf[(6, 1)].symbol(), "a");
+        assert_eq!(buf[(6, 1)].fg, Color::Reset);
+        assert_eq!(buf[(6, 1)].bg, Color::Red);
+        assert!(buf[(6, 1)].modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn should_render_mapped_labels() {
+        let tree = Tree::new(
+            Node::new(String::from("/"), KeyValueNode::new("root", ""))
+                .with_child(Node::new(String::from("a"), KeyValueNode::new("a", "1")))
+                .with_child(Node::new(String::from("bb"), KeyValueNode::new("bb", "2"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .display_map(|x: &KeyValueNode| Cow::Owned(format!("{}={}", x.key(), x.value())));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["  root= \u{25bc}   ", "    a=1     ", "    bb=2    ",])
+        );
+    }
+
+    #[test]
+    fn should_cache_labels() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        #[derive(Default)]
+        struct Costly {
+            label: &'static str,
+            formatted: Rc<Cell<usize>>,
+        }
+
+        impl NodeValue for Costly {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                self.formatted.set(self.formatted.get() + 1);
+                iter::once((self.label, None))
+            }
+        }
+
+        let formatted = Rc::new(Cell::new(0));
+        let costly = |label| Costly {
+            label,
+            formatted: formatted.clone(),
+        };
+        let tree = Tree::new(
+            Node::new(String::from("/"), costly("/"))
+                .with_child(Node::new(String::from("a"), costly("a"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 10, 2);
+        let mut buf = Buffer::empty(area);
+        for _ in 0..3 {
+            let widget = TreeWidget::new(&tree).indent_size(2).cache_labels(true);
+            StatefulWidget::render(widget, area, &mut buf, &mut state);
+        }
+        assert_eq!(formatted.get(), 2);
+        assert_eq!(buf, Buffer::with_lines(["  / \u{25bc}     ", "    a     "]));
+        state.invalidate_labels();
+        let widget = TreeWidget::new(&tree).indent_size(2).cache_labels(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(formatted.get(), 4);
+        // Not cached
+        let widget = TreeWidget::new(&tree).indent_size(2);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(formatted.get(), 6);
+        // Dropped when the tree changes, also preserving a subtree
+        let tree = Tree::new(
+            Node::new(String::from("/"), costly("/"))
+                .with_child(Node::new(String::from("a"), costly("z"))),
+        );
+        state.subtree_changed(tree.root(), "a");
+        let widget = TreeWidget::new(&tree).indent_size(2).cache_labels(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(formatted.get(), 8);
+        assert_eq!(buf, Buffer::with_lines(["  / \u{25bc}     ", "    z     "]));
+    }
+
+    #[test]
+    fn should_render_drop_indicator() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let drop_style = Style::default().add_modifier(Modifier::UNDERLINED);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .drop_indicator("b", drop_style);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "  / \u{25bc}       ",
+            "    a \u{25b6}     ",
+            "    b \u{25b6}     ",
+        ]);
+        expected.set_style(Rect::new(0, 2, 12, 1), drop_style);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn should_render_child_count_hint() {
+        #[derive(Default)]
+        struct Dir(&'static str, Option<usize>);
+
+        impl NodeValue for Dir {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn is_branch_hint(&self) -> bool {
+                self.1.is_some()
+            }
+
+            fn child_count_hint(&self) -> Option<usize> {
+                self.1
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(String::from("/"), Dir("/", Some(3)))
+                .with_child(Node::new(String::from("a"), Dir("a", Some(12))))
+                .with_child(
+                    Node::new(String::from("b"), Dir("b", Some(1)))
+                        .with_child(Node::new(String::from("b0"), Dir("b0", None))),
+                ),
+        );
+        let mut state = TreeState::default();
+        state.force_open(&["/"]);
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree).indent_size(2),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        // Loaded branches have no hint
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "  / \u{25bc}       ",
+                "    a \u{25b6} (12)",
+                "    b \u{25b6}     "
+            ])
+        );
+    }
+
+    #[test]
+    fn should_fill_background() {
+        let tree = mock_tree();
+        let style = Style::default().bg(Color::Blue);
+        let area = Rect::new(0, 0, 12, 4);
+        for (fill, border_bg, inner_bg) in [
+            (BackgroundFill::Area, Color::Blue, Color::Blue),
+            (BackgroundFill::Inner, Color::Reset, Color::Blue),
+            (BackgroundFill::None, Color::Reset, Color::Reset),
+        ] {
+            let mut buf = Buffer::empty(area);
+            let widget = TreeWidget::new(&tree)
+                .block(Block::bordered())
+                .style(style)
+                .background_fill(fill);
+            Widget::render(widget, area, &mut buf);
+            assert_eq!(buf[(0, 0)].bg, border_bg);
+            // Empty row below the tree
+            assert_eq!(buf[(1, 2)].bg, inner_bg);
+        }
+    }
+
+    #[test]
+    fn should_ease_large_jumps() {
+        let tree = generate(1, 40, |route| format!("{route:?}"));
+        let mut state = TreeState::default();
+        state.force_open(&[tree.root().id().as_str()]);
+        state.select(tree.root(), tree.root());
+        let area = Rect::new(0, 0, 12, 4);
+        let mut buf = Buffer::empty(area);
+        let render = |state: &mut TreeState, buf: &mut Buffer| {
+            StatefulWidget::render(TreeWidget::new(&tree).smooth_scroll(true), area, buf, state);
+        };
+        render(&mut state, &mut buf);
+        // Small moves scroll at once
+        (0..4).for_each(|_| state.move_down(tree.root()));
+        render(&mut state, &mut buf);
+        assert_eq!(state.offset(), 1);
+        assert!(!state.is_scrolling());
+        // Jump to the last node: the viewport stays, then eases toward the target on each step
+        state.select(tree.root(), tree.root().iter().last().unwrap());
+        render(&mut state, &mut buf);
+        assert_eq!(state.offset(), 1);
+        assert!(state.is_scrolling());
+        let mut offsets = Vec::new();
+        while state.step_scroll() {
+            render(&mut state, &mut buf);
+            offsets.push(state.offset());
+        }
+        assert_eq!(offsets, vec![19, 28, 37]);
+    }
+
+    #[test]
+    fn should_scroll_by_jump() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.force_open(&["/", "a", "aA", "aB", "aC", "b"]);
+        state.select(tree.root(), tree.root().query(&String::from("/")).unwrap());
+        let area = Rect::new(0, 0, 12, 4);
+        let render = |state: &mut TreeState| {
+            let mut buf = Buffer::empty(area);
+            StatefulWidget::render(TreeWidget::new(&tree).scroll_jump(3), area, &mut buf, state);
+        };
+        render(&mut state);
+        // Move down to row 4: scroll by 3 rows
+        (0..4).for_each(|_| state.move_down(tree.root()));
+        render(&mut state);
+        assert_eq!(state.offset(), 3);
+        // Rows up to 6 are still visible
+        (0..2).for_each(|_| state.move_down(tree.root()));
+        render(&mut state);
+        assert_eq!(state.offset(), 3);
+        // Move back up to row 2
+        (0..4).for_each(|_| state.move_up(tree.root()));
+        render(&mut state);
+        assert_eq!(state.offset(), 0);
+    }
+
+    #[test]
+    fn should_render_rows_with_row_renderer() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .row_renderer(|node, ctx, buf| {
+                if node.id() != "a" {
+                    return false;
+                }
+                assert_eq!(ctx.depth, 1);
+                assert!(ctx.selected);
+                assert!(!ctx.open);
+                let bar = "#".repeat(ctx.area.width as usize - ctx.indent);
+                buf.set_string(ctx.area.x + ctx.indent as u16, ctx.area.y, bar, ctx.style);
+                true
+            });
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["  / \u{25bc}       ", "    ########", "    b \u{25b6}     ",])
+        );
+    }
+
+    #[test]
+    fn should_render_more_row() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.set_max_children(Some(1));
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        let area = Rect::new(0, 0, 40, 5);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            TreeWidget::new(&tree).indent_size(2),
+            area,
+            &mut buf,
+            &mut state,
+        );
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "  / \u{25bc}                                   ",
+                "    a \u{25bc}                                 ",
+                "      aA \u{25b6}                              ",
+                "      \u{2026} 2 more (press Enter to expand)  ",
+                // 'b' is the "more" row of '/'
+                "    \u{2026} 2 more (press Enter to expand)    ",
+            ])
+        );
+    }
+
+    #[test]
+    fn should_expand_tabs() {
+        let mut parts = vec![
+            (Cow::Borrowed("  "), Style::default()),
+            (Cow::Borrowed("a\tbc\td\u{7}e"), Style::default()),
+            (Cow::Borrowed("\tf\r\n"), Style::default()),
+        ];
+        expand_tabs(&mut parts, 4);
+        assert_eq!(
+            parts.iter().map(|(x, _)| x.as_ref()).collect::<Vec<_>>(),
+            vec!["  ", "a bc  de", "  f"]
+        );
+        let mut parts = vec![(Cow::Borrowed("a\tb"), Style::default())];
+        expand_tabs(&mut parts, 0);
+        assert_eq!(parts[0].0, "ab");
+    }
+
+    #[test]
+    fn should_format_thousands() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(982), "982");
+        assert_eq!(thousands(4982), "4,982");
+        assert_eq!(thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn should_cut_rtl_row_at_the_left() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 9, 2);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(TreeWidget::new(&tree).rtl(true), area, &mut buf, &mut state);
+        assert_eq!(buf, Buffer::with_lines(["  \u{25bc} /    ", "a        "]));
+    }
+
+    #[test]
+    fn should_cut_wide_chars_at_the_left() {
+        let mut columns = 3;
+        assert_eq!(
+            TreeWidget::<String>::cut_left("🦄🦄ab", &mut columns),
+            " ab"
+        );
+        assert_eq!(columns, 0);
+        let mut columns = 2;
+        assert_eq!(TreeWidget::<String>::cut_left("a", &mut columns), "");
+        assert_eq!(columns, 1);
+    }
+
+    #[test]
+    fn should_measure_graphemes() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("文件"), 4);
+        assert_eq!(display_width("🏳️‍🌈"), 2);
+        assert_eq!(display_width("👨‍👩‍👧 a"), 4);
+        assert_eq!(display_width("a\tb"), 2);
+        let mut columns = 1;
+        assert_eq!(TreeWidget::<String>::cut_left("👨‍👩‍👧a", &mut columns), " a");
+    }
+
+    #[test]
+    fn should_render_wide_labels() {
+        let tree = Tree::new(
+            Node::new(String::from("/"), String::from("根"))
+                .with_child(Node::new(String::from("a"), String::from("文件夹")))
+                .with_child(Node::new(String::from("b"), String::from("b🦄🦄🦄🦄"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_symbol(String::from("🏳️‍🌈"));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        // Wide char on the edge is not written
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["  根 \u{25bc}      ", " 🏳️‍🌈 文件夹  ", "    b🦄🦄🦄 "])
+        );
+    }
+
+    #[test]
+    fn should_render_wide_labels_right_to_left() {
+        let tree = Tree::new(
+            Node::new(String::from("/"), String::from("根"))
+                .with_child(Node::new(String::from("a"), String::from("文件夹")))
+                .with_child(Node::new(String::from("b"), String::from("🦄🦄🦄🦄🦄"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("a")).unwrap());
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .highlight_symbol(String::from("🏳️‍🌈"))
+            .rtl(true);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["      \u{25bc} 根  ", "  文件夹 🏳️‍🌈 ", "🦄🦄🦄🦄    "])
+        );
+    }
+
+    #[test]
+    fn should_write_viewport_to_state_after_render() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.force_open(&["/", "a", "aA", "aB", "aC", "b", "bA", "bB"]);
+        let bb2 = tree.root().query(&String::from("bB2")).unwrap();
+        state.select(tree.root(), bb2);
+        let area = Rect::new(0, 0, 20, 8);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(TreeWidget::new(&tree), area, &mut buf, &mut state);
+        assert_eq!(state.offset(), 13);
+        assert_eq!(state.viewport_height(), 8);
+    }
+
+    #[test]
+    fn should_get_required_height() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root().query(&String::from("aA")).unwrap());
+        // '/', 'a', 'aA', 'aB', 'aC', 'b', 'c'
+        assert_eq!(TreeWidget::new(&tree).required_height(&state), 7);
+        assert_eq!(
+            TreeWidget::new(&tree)
+                .block(Block::bordered())
+                .required_height(&state),
+            9
+        );
+        assert_eq!(
+            TreeWidget::new(&tree)
+                .block(Block::bordered())
+                .footer(String::from("Enter: open"))
+                .required_height(&state),
+            10
+        );
+    }
+
+    #[test]
+    fn should_construct_widget_with_footer() {
+        let tree = mock_tree();
+        assert_eq!(TreeWidget::new(&tree).footer, None);
+        let widget = TreeWidget::new(&tree).footer(String::from("Enter: open"));
+        assert_eq!(widget.footer.as_deref().unwrap(), "Enter: open");
+    }
+
+    #[test]
+    fn should_render_columns() {
+        #[derive(Default)]
+        struct Entry(&'static str, &'static str);
+
+        impl NodeValue for Entry {
+            fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+                iter::once((self.0, None))
+            }
+
+            fn cell(&self, column: usize) -> Option<Cow<'_, str>> {
+                (column == 1).then_some(Cow::Borrowed(self.1))
+            }
+        }
+
+        let tree = Tree::new(
+            Node::new(String::from("/"), Entry("/", "4K"))
+                .with_child(Node::new(String::from("a"), Entry("a", "120K"))),
+        );
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 16, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .columns(vec![(String::from("Name"), 0), (String::from("Size"), 6)])
+            .sorted_column(1, SortOrder::Descending)
+            .highlight_style(Style::default().bg(Color::Red));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let mut expected = Buffer::with_lines([
+            "Name      Size \u{25bc}",
+            "  / \u{25bc}     4K    ",
+            "    a     120K  ",
+        ]);
+        expected.set_style(
+            Rect::new(0, 0, 4, 1),
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+        expected.set_style(
+            Rect::new(10, 0, 4, 1),
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+        expected.set_style(
+            Rect::new(15, 0, 1, 1),
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+        // Highlight covers the cells too
+        expected.set_style(Rect::new(0, 1, 16, 1), Style::default().bg(Color::Red));
+        assert_eq!(buf, expected);
+        // Rows are clicked on cells too
+        assert_eq!(state.row_at(12, 2), Some(1));
+        // Right to left: cells precede the tree column
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree)
+            .indent_size(2)
+            .ascii(true)
+            .rtl(true)
+            .columns(vec![(String::from("Name"), 0), (String::from("Size"), 6)])
+            .sorted_column(0, SortOrder::Ascending);
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        let rows: Vec<String> = (0..3)
+            .map(|y| (0..16).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec!["Size   Name ^   ", "4K         v /  ", "120K       a    "]
+        );
+    }
+
+    #[test]
+    fn should_render_footer() {
+        let tree = mock_tree();
+        let mut state = TreeState::default();
+        state.select(tree.root(), tree.root());
+        state.open(tree.root());
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        let widget = TreeWidget::new(&tree).footer(String::from("Enter: open"));
+        StatefulWidget::render(widget, area, &mut buf, &mut state);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "    / \u{25bc}           ",
+                "        a \u{25b6}        ",
+                "Enter: open         ",
+            ])
+        );
+    }
 }